@@ -3,3 +3,71 @@
 pub const DEFAULT_IP: &'static str = "127.0.0.1";
 pub const DEFAULT_PORT: &'static u16 = &7878;
 pub const SQLITE_DB_PATH: &'static str = "./pos_inventory.db";
+
+// Number of threads answering connections, a connection waits to be answered while every thread is busy
+pub const WORKER_THREADS: usize = 16;
+
+// Number of connections to the database opened at startup and shared by the requests being answered
+pub const SQLITE_POOL_SIZE: usize = 8;
+// Milliseconds a connection waits for a lock held by another connection before giving up with SQLITE_BUSY
+pub const SQLITE_BUSY_TIMEOUT_MS: usize = 5000;
+// Number of different sql statements each connection keeps prepared, those used least recently are finalized to make room for others
+pub const SQLITE_STATEMENT_CACHE_SIZE: usize = 64;
+// Seconds a request waits for a connection to be free when every connection of the pool is in use
+pub const DB_POOL_WAIT_SECS: u64 = 10;
+
+// Environment variable naming the database used by the server, "sqlite" (the default) or "postgres"
+pub const DATABASE_VAR: &str = "POS_DATABASE";
+// Environment variable holding the connection string of the PostgreSQL database, e.g. "host=localhost user=pos dbname=pos"
+pub const POSTGRES_URL_VAR: &str = "POS_POSTGRES_URL";
+// Number of connections to the PostgreSQL database opened at startup
+pub const POSTGRES_POOL_SIZE: usize = 8;
+
+// Number of items returned in a page of a collection when cursor pagination is used without a limit
+pub const DEFAULT_PAGE_LIMIT: u64 = 50;
+// Largest page of a collection that can be requested with the limit parameter
+pub const MAX_PAGE_LIMIT: u64 = 1000;
+
+// Number of results of each type returned by /api/search when no limit is given
+pub const DEFAULT_SEARCH_LIMIT: u64 = 10;
+
+// Version 1 of the api is deprecated from 1 Nov 2026 (unix time) and will be removed on the sunset date
+pub const API_V1_DEPRECATED: i64 = 1793491200;
+pub const API_V1_SUNSET: &str = "Sat, 01 May 2027 00:00:00 GMT";
+
+// Problem details responses (RFC 7807) name the type of problem with this prefix and a short name, e.g. "urn:pos:problem:not-found"
+pub const PROBLEM_TYPE_BASE: &str = "urn:pos:problem:";
+
+// Largest number of suppliers that can be added in one request to /api/suppliers/batch
+pub const MAX_BATCH_SIZE: usize = 1000;
+
+// Environment variable holding the token admins send as "Authorization: Bearer <token>", purging data is disabled when it is not set
+pub const ADMIN_TOKEN_VAR: &str = "POS_ADMIN_TOKEN";
+
+/// The settings of the server read from the environment as it starts, which are handed to the requests it answers
+#[derive(Debug, Clone, Default)]
+pub struct ServerConfig {
+    /// The token admins send to purge data, nothing can be purged while there is none
+    pub admin_token: Option<String>,
+}
+
+impl ServerConfig {
+    pub fn from_env() -> Self {
+        ServerConfig {
+            admin_token: std::env::var(ADMIN_TOKEN_VAR).ok().filter(|token| !token.is_empty()),
+        }
+    }
+}
+
+// Seconds a response is kept against the Idempotency-Key it was sent with, a retry within this time is given the same response
+pub const IDEMPOTENCY_RETENTION_SECS: u64 = 24 * 60 * 60;
+// Seconds a key is held by a request still being carried out, after which the request is taken to have died and the key can be used again
+pub const IDEMPOTENCY_IN_FLIGHT_SECS: u64 = 60;
+// Longest Idempotency-Key accepted
+pub const MAX_IDEMPOTENCY_KEY_LEN: usize = 255;
+
+// Environment variable which stops the server migrating the database as it starts when set to "0", "false" or "off"
+pub const AUTO_MIGRATE_VAR: &str = "POS_AUTO_MIGRATE";
+
+// Environment variable which stops the server starting when set to "strict" and the tables and views of the database do not match those it reads
+pub const SCHEMA_CHECK_VAR: &str = "POS_SCHEMA_CHECK";
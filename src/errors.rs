@@ -16,6 +16,43 @@ impl ErrorType {
             ErrorType::NotImplemented(ref s) => s,
         }
     }
+
+    // the http status of a response to a request which failed with this error
+    pub fn status(&self) -> u16 {
+        match *self {
+            ErrorType::InvalidPort(_) => 500,
+            ErrorType::InvalidIp(_) => 500,
+            ErrorType::ParseError(_) => 400,
+            ErrorType::NotImplemented(_) => 501,
+        }
+    }
+}
+
+/// Describes why a single field of a submitted json object was rejected. The field is
+/// given as a path from the root of the object, e.g. "address.postcode".
+#[derive(Debug, Clone)]
+pub struct FieldError {
+    pub field: String,
+    pub code: String,
+    pub message: String,
+}
+
+impl FieldError {
+    pub fn new(field: &str, code: &str, message: &str) -> FieldError {
+        FieldError {
+            field: field.to_string(),
+            code: code.to_string(),
+            message: message.to_string(),
+        }
+    }
+
+    pub fn to_json(&self) -> json::JsonValue {
+        json::object!{
+            "field" => self.field.clone(),
+            "code" => self.code.clone(),
+            "message" => self.message.clone(),
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -23,6 +60,11 @@ pub enum DatabaseError {
     ConnectionError(String),
     QueryError(String),
     SubmissionError(String),
+    InvalidParameter(String),
+    ValidationError(String, Vec<FieldError>),
+    NotFound(String),
+    PreconditionFailed(String),
+    PreconditionRequired(String),
     NotImplemented(String),
 }
 impl DatabaseError {
@@ -32,6 +74,34 @@ impl DatabaseError {
             DatabaseError::QueryError(ref s) => s,
             DatabaseError::NotImplemented(ref s) => s,
             DatabaseError::SubmissionError(ref s) => s,
+            DatabaseError::InvalidParameter(ref s) => s,
+            DatabaseError::ValidationError(ref s, _) => s,
+            DatabaseError::NotFound(ref s) => s,
+            DatabaseError::PreconditionFailed(ref s) => s,
+            DatabaseError::PreconditionRequired(ref s) => s,
+        }
+    }
+
+    // the http status of a response to a request which failed with this error
+    pub fn status(&self) -> u16 {
+        match *self {
+            DatabaseError::ConnectionError(_) => 503,
+            DatabaseError::QueryError(_) => 500,
+            DatabaseError::SubmissionError(_) => 422,
+            DatabaseError::InvalidParameter(_) => 400,
+            DatabaseError::ValidationError(_, _) => 422,
+            DatabaseError::NotFound(_) => 404,
+            DatabaseError::PreconditionFailed(_) => 412,
+            DatabaseError::PreconditionRequired(_) => 428,
+            DatabaseError::NotImplemented(_) => 501,
+        }
+    }
+
+    // the fields at fault, if the error was caused by the content of a submitted object
+    pub fn field_errors(&self) -> &[FieldError] {
+        match *self {
+            DatabaseError::ValidationError(_, ref errors) => errors,
+            _ => &[],
         }
     }
 }
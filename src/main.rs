@@ -16,6 +16,14 @@ fn main() -> Result<(), ErrorKind>{
     // there should be one of two arguments provided, the first is the ip address and the second is the port number
     let args: Vec<_> = std::env::args().collect();
 
+    // "migrations" lists the migrations of the database and "migrate" applies those pending,
+    // neither starts the server
+    match args.get(1).map(|arg| arg.as_str()) {
+        Some("migrations") => return rest_server::migrations_command(false),
+        Some("migrate") => return rest_server::migrations_command(true),
+        _ => {},
+    }
+
     // assign the ip address and port number to a SocketAddr struct
     let socket_addr = assign_socket_addr(args);
     
@@ -5,28 +5,149 @@ pub mod process_query;
 pub mod api;
 
 use connection::connection;
-use std::net::{SocketAddr, TcpListener};
-use api::routing::ApiTree;
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::{mpsc, Mutex};
+use api::routing::ApiVersions;
+use databases::{backend::Database, schema_check, migrations, tables};
+use crate::errors::DatabaseError;
+use crate::config::{AUTO_MIGRATE_VAR, SCHEMA_CHECK_VAR, WORKER_THREADS, ServerConfig};
 
 
 pub fn start(socket_addr: SocketAddr) -> Result<(), std::io::ErrorKind> {
    
-    // create the api tree, this is used to route the incoming requests
-    let mut api_tree = Box::new(ApiTree::new());
+    // create the api trees, one for each version of the api, these are used to route the incoming requests
+    let api_versions = ApiVersions::new();
+    let config = ServerConfig::from_env();
+
+    // the connections to the database named by POS_DATABASE are opened once and shared by the
+    // requests, a server without them can still answer requests which do not use the database
+    let database = match Database::open(None) {
+        Ok(database) => {
+            // a database which could not be migrated is missing tables the requests use, so the
+            // server does not start with it
+            let auto_migrate = auto_migrate();
+            if let Err(error) = databases::requests::prepare_database(auto_migrate, &database) {
+                println!("Error: {}", error.message());
+                if auto_migrate {
+                    println!("Error: The server will not start while the database could not be migrated, set {} to off to start regardless", AUTO_MIGRATE_VAR);
+                    return Err(std::io::ErrorKind::InvalidData);
+                }
+            }
+            if !check_schemas(&database) {
+                println!("Error: The server will not start while the database does not match, unset {} to start regardless", SCHEMA_CHECK_VAR);
+                return Err(std::io::ErrorKind::InvalidData);
+            }
+            Some(database)
+        },
+        Err(error) => {
+            println!("Error: {:?}", error);
+            None
+        },
+    };
 
     // setup the listener to listen for incoming connections   
     if let Ok(listener) = TcpListener::bind(socket_addr){
-      
-        for stream in listener.incoming() {
-            let stream = stream.unwrap();
-        
-            connection(stream, &mut api_tree);
-        
-        }
+
+        // the connections are answered by a fixed number of threads, each taking the next
+        // connection accepted once it is free, and the number of requests using the database
+        // at once is limited by the size of the pool
+        let (sender, receiver) = mpsc::sync_channel::<TcpStream>(WORKER_THREADS);
+        let receiver = Mutex::new(receiver);
+        std::thread::scope(|scope| {
+            for _ in 0..WORKER_THREADS {
+                let receiver = &receiver;
+                let api_versions = &api_versions;
+                let config = &config;
+                let database = database.as_ref();
+
+                scope.spawn(move || loop {
+                    // the lock is only held while waiting, so a connection is answered without it
+                    let stream = match receiver.lock() {
+                        Ok(receiver) => receiver.recv(),
+                        Err(_) => return,
+                    };
+                    match stream {
+                        Ok(stream) => connection(stream, api_versions, config, database),
+                        Err(_) => return,
+                    }
+                });
+            }
+
+            // a connection which could not be accepted is dropped, and the server goes on listening
+            for stream in listener.incoming() {
+                match stream {
+                    Ok(stream) => {
+                        if sender.send(stream).is_err() {
+                            break;
+                        }
+                    },
+                    Err(error) => println!("Error: Failed to accept a connection: {}", error),
+                }
+            }
+            drop(sender);
+        });
         Ok(())
     }
     else {
         println!("Error: Server had a problem binding to the ip address on the host, check if the ip and port are available.");
         Err(std::io::ErrorKind::AddrNotAvailable)
     }
-}
\ No newline at end of file
+}
+
+// The database is migrated as the server starts unless POS_AUTO_MIGRATE turns it off
+fn auto_migrate() -> bool {
+    match std::env::var(AUTO_MIGRATE_VAR) {
+        Ok(value) => !matches!(value.trim().to_lowercase().as_str(), "0" | "false" | "off"),
+        Err(_) => true,
+    }
+}
+
+///
+/// Checks the tables and views the server reads against those held by the database, reporting
+/// every mismatch. False is returned, so the server does not start, if POS_SCHEMA_CHECK is
+/// "strict", otherwise the server starts degraded and requests reading a mismatched table may fail.
+///
+fn check_schemas(database: &Database) -> bool {
+    let strict = matches!(std::env::var(SCHEMA_CHECK_VAR), Ok(value) if value.trim().eq_ignore_ascii_case("strict"));
+
+    match database.connect(|connection| schema_check::check(connection, tables::SCHEMAS)) {
+        Ok(mismatches) if mismatches.is_empty() => true,
+        Ok(mismatches) => {
+            println!("Warning: the database does not match the tables and views the server reads");
+            for mismatch in &mismatches {
+                println!("  {}", mismatch);
+            }
+            !strict
+        },
+        Err(error) => {
+            println!("Error: the tables and views of the database could not be checked: {}", error.message());
+            !strict
+        },
+    }
+}
+
+///
+/// Lists the migrations of the database, marking those which have not been applied, without
+/// starting the server. The pending migrations are applied first if `apply` is set.
+///
+pub fn migrations_command(apply: bool) -> Result<(), std::io::ErrorKind> {
+    let status = || -> Result<(), DatabaseError> {
+        Database::open(Some(1))?.connect(|connection| {
+            if apply {
+                for migration in migrations::migrate(connection)? {
+                    println!("Applied migration {} ({})", migration.version, migration.name);
+                }
+            }
+            let pending = migrations::pending(connection)?;
+            for migration in migrations::migrations(connection.database_type()) {
+                let state = if pending.iter().any(|pending| pending.version == migration.version) { "pending" } else { "applied" };
+                println!("{:>4}  {:<8}  {}", migration.version, state, migration.name);
+            }
+            Ok(())
+        })
+    };
+    status().map_err(|error| {
+        println!("Error: {}", error.message());
+        std::io::ErrorKind::Other
+    })
+}
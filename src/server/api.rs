@@ -3,21 +3,29 @@ pub mod routing;
 pub mod query_types;
 pub mod util_structs;
 pub mod config;
+pub mod openapi;
+pub mod explorer;
+pub mod uri;
+pub mod problem;
 
 
 use query_types::{Query};
 use parsing::{query_with_path_variables};
 use self::routing::ApiTree;
+use self::util_structs::PathSegment;
 
 
 
-pub fn uri_to_api_query(uri: &String, api_tree: &mut Box<ApiTree>) -> Option<Query> {
+pub fn uri_to_api_query(uri: &str, method: &str, query_params: &[(String, String)], api_tree: &ApiTree) -> Option<Query> {
 
     // get query type
 
 
-    // split the uri into segments
-    let uri_segs: Vec<_> = uri.split("/").into_iter().map(|x| String::from(x)).collect();
+    // split the uri into decoded segments, a uri which can not be decoded is invalid
+    let uri_segs: Vec<String> = match uri::path_segments(uri) {
+        Ok(segments) => segments,
+        Err(_) => return Some(Query::ApiInvalidUri),
+    };
     let mut index = 0;
     let mut variables: Vec<String> = Vec::new();
 
@@ -39,14 +47,24 @@ pub fn uri_to_api_query(uri: &String, api_tree: &mut Box<ApiTree>) -> Option<Que
     if !uri_segs[1].eq(&"api".to_string()) {
         return Some(Query::NoneApi)
     }
-    // if there is only the "api" segment then return the ApiDoc query
-    else if uri_segs.len() == 2 {
-        return Some(Query::ApiDoc)
-    }
 
     // increment index to ignore "api" segment
     index += 1;
 
+    // the version segment is optional, e.g. "/api/v1/suppliers", as "/api/suppliers" uses the latest version
+    if uri_segs.len() > index && uri_segs[index] == api_tree.version.segment() {
+        index += 1;
+    }
+
+    // if there are no segments after "api" and the version then return the ApiDoc query
+    if uri_segs.len() == index {
+        return Some(Query::ApiDoc)
+    }
+    // "/api/docs" is the html version of the api documentation
+    else if uri_segs.len() == index + 1 && uri_segs[index] == "docs" {
+        return Some(Query::ApiExplorer)
+    }
+
     let mut tree_seg = &api_tree.tree;
 
     while tree_seg.has_children() {
@@ -62,12 +80,8 @@ pub fn uri_to_api_query(uri: &String, api_tree: &mut Box<ApiTree>) -> Option<Que
                 tree_seg = next;
                 continue;
             }
-            else if let Some(query) = &next.query {
-                // request body content will always be the last value to be pushed to the variables vector
-                return Some(query_with_path_variables(query, &variables));
-            }
 
-            return None;
+            return matched_query(next, method, &variables, uri, query_params);
                 
         }
         // if there is a child segment with the value "{}" that indicates a uri parameter
@@ -80,12 +94,8 @@ pub fn uri_to_api_query(uri: &String, api_tree: &mut Box<ApiTree>) -> Option<Que
                 index += 1;
                 continue;
             }
-            else if let Some(query) = &next.query {
-                // request body content will always be the last value to be pushed to the variables vector
-                return Some(query_with_path_variables(query, &variables));
-            }
-            
-            return None;
+
+            return matched_query(next, method, &variables, uri, query_params);
             
         }
         // Knowing there is no child segment, it should also be the case that there
@@ -93,12 +103,7 @@ pub fn uri_to_api_query(uri: &String, api_tree: &mut Box<ApiTree>) -> Option<Que
         if index == uri_segs.len() {
             
             // see if the current segment has a query associated with it
-            if let Some(query) = &tree_seg.query {
-                // request body content will always be the last value to be pushed to the variables vector
-                return Some(query_with_path_variables(query, &variables));
-            }
-            
-            return None;
+            return matched_query(tree_seg, method, &variables, uri, query_params);
             
         }  
         // Fall through case, the uri segment is not valid
@@ -114,3 +119,14 @@ pub fn uri_to_api_query(uri: &String, api_tree: &mut Box<ApiTree>) -> Option<Que
    
 }
 
+// Picks the query registered on the matched path segment for the method of the request. A 
+// path which has queries, but none for the method, can not be requested with that method.
+fn matched_query(path_seg: &PathSegment, method: &str, variables: &[String], uri: &str, query_params: &[(String, String)]) -> Option<Query> {
+    if path_seg.queries.is_empty() {
+        return None;
+    }
+    match path_seg.query_for_method(method) {
+        Some(query) => Some(query_with_path_variables(query, variables, uri, query_params)),
+        None => Some(Query::ApiMethodNotAllowed(path_seg.allowed_methods())),
+    }
+}
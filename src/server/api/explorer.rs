@@ -0,0 +1,231 @@
+use crate::server::api::openapi::openapi_document;
+use crate::server::api::routing::ApiTree;
+use crate::server::databases::config::data_keys;
+use json::JsonValue;
+
+// Builds a self contained html page listing every api route, it's parameters, an example
+// request and response, and a form for trying GET requests against the server. Everything
+// needed by the page is inlined so it can be used without access to the internet.
+pub fn explorer_html(api_tree: &ApiTree) -> String {
+
+    let document = openapi_document(api_tree);
+    let schemas = &document["components"]["schemas"];
+
+    let mut index = String::new();
+    let mut routes = String::new();
+
+    for (path, operations) in document["paths"].entries() {
+        for (method, operation) in operations.entries() {
+            let anchor = operation["operationId"].as_str().unwrap_or(path);
+            let method = method.to_uppercase();
+
+            index.push_str(&format!(
+                "<li><a href=\"#{anchor}\"><span class=\"method\">{}</span> {}</a></li>\n",
+                method, html_escape(path)
+            ));
+
+            routes.push_str(&format!(
+                "<section id=\"{anchor}\">\n<h2><span class=\"method\">{}</span> {}</h2>\n",
+                method, html_escape(path)
+            ));
+            routes.push_str(&parameters_table(&operation["parameters"]));
+
+            routes.push_str("<h3>Example request</h3>\n");
+            let mut example_request = format!("{} {}", method, example_path(path, &operation["parameters"]));
+            if operation["requestBody"].is_object() {
+                let body_schema = &operation["requestBody"]["content"]["application/json"]["schema"];
+                example_request.push_str("\nContent-Type: application/json\n\n");
+                example_request.push_str(&json::stringify_pretty(example_value(body_schema, schemas, ""), 2));
+            }
+            routes.push_str(&format!("<pre>{}</pre>\n", html_escape(&example_request)));
+
+            routes.push_str("<h3>Example response</h3>\n");
+            let success = operation["responses"].entries()
+                .find(|(status, _)| status.starts_with('2'))
+                .map(|(_, response)| response)
+                .unwrap_or(&JsonValue::Null);
+            let response_schema = &success["content"]["application/json"]["schema"];
+            routes.push_str(&format!(
+                "<pre>{}</pre>\n",
+                html_escape(&json::stringify_pretty(example_value(response_schema, schemas, ""), 2))
+            ));
+
+            if method == "GET" {
+                routes.push_str(&try_it_form(path, &operation["parameters"]));
+            }
+            routes.push_str("</section>\n");
+        }
+    }
+
+    format!(r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<title>{title} - API Explorer</title>
+<style>
+body {{ font-family: sans-serif; margin: 0; display: flex; color: #222; }}
+nav {{ width: 22rem; height: 100vh; overflow-y: auto; position: sticky; top: 0; background: #f3f3f3; padding: 1rem; box-sizing: border-box; }}
+nav ul {{ list-style: none; padding: 0; font-size: 0.9rem; }}
+nav li {{ margin: 0.3rem 0; }}
+nav a {{ color: #222; text-decoration: none; }}
+main {{ flex: 1; padding: 1rem 2rem; max-width: 60rem; }}
+section {{ border-bottom: 1px solid #ddd; padding-bottom: 1rem; }}
+.method {{ display: inline-block; min-width: 3.5rem; font-weight: bold; color: #fff; background: #2b6cb0; border-radius: 3px; padding: 0 0.3rem; text-align: center; font-size: 0.85em; }}
+table {{ border-collapse: collapse; margin-bottom: 1rem; }}
+th, td {{ border: 1px solid #ccc; padding: 0.2rem 0.5rem; text-align: left; font-size: 0.9rem; }}
+pre {{ background: #f7f7f7; border: 1px solid #ddd; padding: 0.5rem; overflow-x: auto; }}
+form label {{ display: block; margin: 0.3rem 0; }}
+form label span {{ display: inline-block; min-width: 8rem; }}
+</style>
+</head>
+<body>
+<nav>
+<h1>{title}</h1>
+<p>Version {version}. The machine readable <a href="/api">OpenAPI document</a> is served from the api root.</p>
+<ul>
+{index}</ul>
+</nav>
+<main>
+{routes}</main>
+<script>
+document.querySelectorAll('form.try').forEach(function (form) {{
+    form.addEventListener('submit', function (event) {{
+        event.preventDefault();
+        var path = form.dataset.path;
+        var query = [];
+        form.querySelectorAll('input').forEach(function (input) {{
+            if (input.dataset.in === 'path') {{
+                path = path.replace('{{' + input.name + '}}', encodeURIComponent(input.value));
+            }} else if (input.value !== '') {{
+                query.push(encodeURIComponent(input.name) + '=' + encodeURIComponent(input.value));
+            }}
+        }});
+        var url = path + (query.length ? '?' + query.join('&') : '');
+        var output = form.querySelector('pre');
+        output.textContent = 'GET ' + url + '\n\nWaiting for response...';
+        fetch(url).then(function (response) {{
+            return response.text().then(function (text) {{
+                try {{ text = JSON.stringify(JSON.parse(text), null, 2); }} catch (e) {{}}
+                output.textContent = 'GET ' + url + '\n' + response.status + ' ' + response.statusText + '\n\n' + text;
+            }});
+        }}).catch(function (error) {{
+            output.textContent = 'GET ' + url + '\n\nRequest failed: ' + error;
+        }});
+    }});
+}});
+</script>
+</body>
+</html>
+"#,
+        title = html_escape(document["info"]["title"].as_str().unwrap_or("")),
+        version = html_escape(document["info"]["version"].as_str().unwrap_or("")),
+        index = index,
+        routes = routes,
+    )
+}
+
+fn parameters_table(parameters: &JsonValue) -> String {
+    if parameters.is_empty() {
+        return String::from("<p>No parameters.</p>\n");
+    }
+    let mut table = String::from("<table>\n<tr><th>Name</th><th>In</th><th>Type</th><th>Required</th><th>Description</th></tr>\n");
+    for parameter in parameters.members() {
+        table.push_str(&format!(
+            "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+            html_escape(parameter["name"].as_str().unwrap_or("")),
+            html_escape(parameter["in"].as_str().unwrap_or("")),
+            html_escape(parameter["schema"]["type"].as_str().unwrap_or("")),
+            if parameter["required"].as_bool().unwrap_or(false) { "yes" } else { "no" },
+            html_escape(parameter["description"].as_str().unwrap_or("")),
+        ));
+    }
+    table.push_str("</table>\n");
+    table
+}
+
+fn try_it_form(path: &str, parameters: &JsonValue) -> String {
+    let mut form = format!("<h3>Try it</h3>\n<form class=\"try\" data-path=\"{}\">\n", html_escape(path));
+    for parameter in parameters.members() {
+        let name = html_escape(parameter["name"].as_str().unwrap_or(""));
+        let location = html_escape(parameter["in"].as_str().unwrap_or(""));
+        let required = if parameter["required"].as_bool().unwrap_or(false) { " required" } else { "" };
+        form.push_str(&format!(
+            "<label><span>{name}</span> <input name=\"{name}\" data-in=\"{location}\"{required}></label>\n"
+        ));
+    }
+    form.push_str("<button type=\"submit\">Send</button>\n<pre></pre>\n</form>\n");
+    form
+}
+
+// Fills the path parameters in with example values
+fn example_path(path: &str, parameters: &JsonValue) -> String {
+    let mut path = path.to_string();
+    for parameter in parameters.members() {
+        if parameter["in"] == "path" {
+            let name = parameter["name"].as_str().unwrap_or("");
+            let value = if parameter["schema"]["type"] == "integer" { "1" } else { "Example%20Supplies%20Ltd" };
+            path = path.replace(&format!("{{{}}}", name), value);
+        }
+    }
+    path
+}
+
+// Builds an example json value matching a schema, the key of the value is used to
+// pick a more realistic example for strings
+fn example_value(schema: &JsonValue, schemas: &JsonValue, key: &str) -> JsonValue {
+    if let Some(reference) = schema["$ref"].as_str() {
+        let name = reference.rsplit('/').next().unwrap_or("");
+        return example_value(&schemas[name], schemas, key);
+    }
+
+    match schema["type"].as_str() {
+        Some("object") => {
+            let mut object = json::object!{};
+            for (property_key, property) in schema["properties"].entries() {
+                object[property_key] = example_value(property, schemas, property_key);
+            }
+            object
+        },
+        Some("array") => json::array![example_value(&schema["items"], schemas, key)],
+        Some("integer") if key == "code" => 200.into(),
+        Some("integer") => 1.into(),
+        Some("number") => 1.5.into(),
+        Some("boolean") => true.into(),
+        Some("string") => example_string(key).into(),
+        _ => JsonValue::Null,
+    }
+}
+
+fn example_string(key: &str) -> &'static str {
+    match key {
+        data_keys::NAME => "Example Supplies Ltd",
+        data_keys::EMAIL => "sales@example.com",
+        data_keys::NUMBER => "01234 567890",
+        data_keys::ADDRESS_LINE1 => "1 High Street",
+        data_keys::ADDRESS_LINE2 => "Unit 2",
+        data_keys::ADDRESS_TOWN => "Glasgow",
+        data_keys::ADDRESS_COUNCIL => "Glasgow City",
+        data_keys::ADDRESS_POSTCODE => "G1 1AA",
+        data_keys::TITLE => "Mr",
+        data_keys::FIRST_NAME => "John",
+        data_keys::LAST_NAME => "Smith",
+        data_keys::CATEGORY_TYPE => "Groceries",
+        "self" | "next" | "prev" => "/api/suppliers?limit=10&offset=10",
+        _ => "string",
+    }
+}
+
+pub fn html_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
@@ -0,0 +1,560 @@
+use crate::server::api::query_types::{Query, ApiVersion, Content, Expansion};
+use crate::server::api::routing::ApiTree;
+use crate::server::api::problem;
+use crate::server::databases::{
+    data_structs::{DBTableStruct, Value},
+    tables, search, versions,
+    config::data_keys,
+};
+use crate::config::MAX_IDEMPOTENCY_KEY_LEN;
+use json::JsonValue;
+
+// Builds an OpenAPI 3 document describing a version of the api. The paths are taken from 
+// the routes registered on the version's api tree, and the schemas of the json objects sent
+// and received are taken from the table structures used by the database.
+pub fn openapi_document(api_tree: &ApiTree) -> JsonValue {
+
+    let version = api_tree.version;
+    let mut paths = json::object!{};
+
+    for (path, query) in api_tree.routes() {
+        let (path, parameters) = path_with_parameters(&path, query);
+        let method = query.http_method().to_lowercase();
+
+        let mut operation = json::object!{
+            "operationId" => format!("{:?}", query).split('(').next().unwrap_or(""),
+            "parameters" => parameters,
+            "responses" => json::object!{
+                "200" => json::object!{
+                    "description" => "Success",
+                    "content" => json::object!{
+                        "application/json" => json::object!{ "schema" => response_schema(query, version) }
+                    }
+                },
+                "400" => error_response("Bad Request"),
+                "404" => error_response("Resource Not Found"),
+                "500" => error_response("Internal Server Error"),
+            }
+        };
+
+        // database rows can also be rendered as csv or xml, see negotiate_format
+        if method == "get" {
+            for content_type in ["text/csv", "application/xml"] {
+                operation["responses"]["200"]["content"][content_type] = json::object!{
+                    "schema" => json::object!{ "type" => "string" }
+                };
+            }
+            operation["responses"]["406"] = error_response("Not Acceptable");
+            operation["parameters"].push(query_parameter(
+                "format",
+                "Output format, takes precedence over the Accept header",
+                json::object!{ "type" => "string", "enum" => json::array!["json", "csv", "xml"] }
+            )).unwrap();
+        }
+
+        if api_tree.deprecation.is_some() {
+            operation["deprecated"] = true.into();
+        }
+
+        // a single new resource is reported as created
+        if let Query::POSTSupplier(_) = query {
+            let created = operation["responses"].remove("200");
+            operation["responses"]["201"] = created;
+            operation["responses"]["201"]["description"] = "Created".into();
+        }
+
+        // a deleted resource has nothing to return, and only an admin can purge a resource
+        if let Query::DELETESupplier(_, _) = query {
+            operation["responses"].remove("200");
+            operation["responses"]["204"] = json::object!{ "description" => "Deleted" };
+            operation["responses"]["403"] = error_response("Forbidden");
+            operation["security"] = json::array![json::object!{}, json::object!{ "adminToken" => json::array![] }];
+        }
+
+        // a post sent again with the same Idempotency-Key is given the first response, see process_query
+        if method == "post" {
+            operation["parameters"].push(json::object!{
+                "name" => "Idempotency-Key",
+                "in" => "header",
+                "required" => false,
+                "description" => "Unique key for the request, a retry with the same key and body is given the original response",
+                "schema" => json::object!{ "type" => "string", "minLength" => 1, "maxLength" => MAX_IDEMPOTENCY_KEY_LEN },
+            }).unwrap();
+            operation["responses"]["422"] = error_response("Unprocessable Entity");
+        }
+
+        // versioned resources give their ETag, which must be sent back as If-Match to change them
+        if versions::resource_query(query).is_some() {
+            if method != "delete" {
+                operation["responses"]["200"]["headers"] = json::object!{
+                    "ETag" => json::object!{
+                        "description" => "The version of the resource",
+                        "schema" => json::object!{ "type" => "string" },
+                    }
+                };
+            }
+            if method != "get" {
+                operation["parameters"].push(json::object!{
+                    "name" => "If-Match",
+                    "in" => "header",
+                    "required" => false,
+                    "description" => "ETag of the resource as it was read, needed when the resource exists",
+                    "schema" => json::object!{ "type" => "string" },
+                }).unwrap();
+                operation["responses"]["412"] = error_response("Precondition Failed, the problem holds the current resource as current");
+                operation["responses"]["428"] = error_response("Precondition Required");
+            }
+        }
+
+        if let Some(schema) = request_schema(query) {
+            operation["responses"]["415"] = error_response("Unsupported Media Type");
+            operation["responses"]["422"] = error_response("Unprocessable Entity");
+            operation["requestBody"] = json::object!{
+                "required" => true,
+                "content" => json::object!{
+                    "application/json" => json::object!{ "schema" => schema.clone() }
+                }
+            };
+            if method == "patch" {
+                operation["requestBody"]["content"]["application/merge-patch+json"] = json::object!{ "schema" => schema };
+            }
+        }
+
+        paths[path][method] = operation;
+    }
+
+    json::object!{
+        "openapi" => "3.0.3",
+        "info" => json::object!{
+            "title" => "POS Stock Management API",
+            "version" => version.segment(),
+            "description" => format!(
+                "Server build {}. Paths without a version segment use the latest version ({}).",
+                env!("CARGO_PKG_VERSION"), ApiVersion::LATEST.segment()
+            ),
+        },
+        "paths" => paths,
+        "components" => json::object!{
+            "schemas" => component_schemas(version),
+            "securitySchemes" => json::object!{
+                "adminToken" => json::object!{
+                    "type" => "http",
+                    "scheme" => "bearer",
+                    "description" => "Needed to purge data, the token is set by the POS_ADMIN_TOKEN environment variable",
+                }
+            },
+        }
+    }
+}
+
+// The table structures shared between operations, keyed by their schema name
+pub fn component_schemas(version: ApiVersion) -> JsonValue {
+    json::object!{
+        "Supplier" => supplier_schema(&[], version),
+        "Email" => table_schema(&tables::email_table(), &[]),
+        "Number" => table_schema(&tables::numbers_table(), &[]),
+        "Id" => table_schema(&tables::id_table(), &[]),
+        "SupplierName" => table_schema(&tables::supplier_name_table(), &[]),
+        "Address" => table_schema(&tables::address_table(), &[]),
+        "Rep" => table_schema(&tables::rep_table(), &[]),
+        "Category" => table_schema(&tables::categories_table(), &[]),
+        "Contact" => {
+            let mut contact = json::object!{
+                "type" => "object",
+                "properties" => json::object!{
+                    data_keys::EMAIL => string_array(),
+                    data_keys::NUMBER => string_array(),
+                }
+            };
+            if version != ApiVersion::V1 {
+                contact["required"] = json::array![data_keys::EMAIL, data_keys::NUMBER];
+            }
+            contact
+        },
+        "Problem" => json::object!{
+            "type" => "object",
+            "required" => json::array!["type", "title", "status", "detail", "requestId", "errors"],
+            "properties" => json::object!{
+                "type" => json::object!{ "type" => "string", "format" => "uri" },
+                "title" => json::object!{ "type" => "string" },
+                "status" => json::object!{ "type" => "integer" },
+                "detail" => json::object!{ "type" => "string" },
+                "instance" => json::object!{ "type" => "string" },
+                "requestId" => json::object!{ "type" => "string" },
+                "errors" => field_errors_schema(),
+            }
+        },
+    }
+}
+
+// Converts the structure of a table into a json schema object, leaving out the excluded fields
+pub fn table_schema(table: &DBTableStruct, exclude: &[&str]) -> JsonValue {
+    let mut properties = json::object!{};
+    let mut required = JsonValue::new_array();
+
+    for field in &table.fields {
+        if exclude.contains(&field.name.as_str()) {
+            continue;
+        }
+        let mut property = value_schema(&field.field_type);
+        if field.not_null {
+            required.push(field.name.clone()).unwrap();
+        }
+        else {
+            property["nullable"] = true.into();
+        }
+        properties[field.name.clone()] = property;
+    }
+
+    let mut schema = json::object!{
+        "type" => "object",
+        "properties" => properties,
+    };
+    if !required.is_empty() {
+        schema["required"] = required;
+    }
+    schema
+}
+
+// From version 2 a supplier's active value is a boolean, see get_processing::versioned_payload
+fn supplier_schema(exclude: &[&str], version: ApiVersion) -> JsonValue {
+    let mut supplier = table_schema(&tables::supplier_table(), exclude);
+    if version != ApiVersion::V1 {
+        supplier["properties"][data_keys::ACTIVE] = json::object!{ "type" => "boolean" };
+    }
+    supplier
+}
+
+// From version 2 the contact object of a supplier or rep, and both it's arrays, are always present
+fn contact_schema(object: &mut JsonValue, version: ApiVersion) {
+    object["properties"][data_keys::CONTACT] = schema_ref("Contact");
+    if version != ApiVersion::V1 {
+        if !object["required"].is_array() {
+            object["required"] = JsonValue::new_array();
+        }
+        object["required"].push(data_keys::CONTACT).unwrap();
+    }
+}
+
+// adds the related resources which can be embedded within a supplier, see Expansion
+fn embedded_schemas(supplier: &mut JsonValue, version: ApiVersion) {
+    let mut rep = table_schema(&tables::rep_table(), &[data_keys::ID, data_keys::CONTACT_ID]);
+    contact_schema(&mut rep, version);
+    rep["nullable"] = true.into();
+
+    let mut address = table_schema(&tables::address_table(), &[data_keys::ID]);
+    address["nullable"] = true.into();
+
+    supplier["properties"][Expansion::Address.name()] = address;
+    supplier["properties"][Expansion::Contact.name()] = schema_ref("Contact");
+    supplier["properties"][Expansion::Rep.name()] = rep;
+}
+
+fn value_schema(value: &Value) -> JsonValue {
+    match value {
+        Value::Boolean(_) => json::object!{ "type" => "boolean" },
+        Value::Binary(_) => json::object!{ "type" => "string", "format" => "byte" },
+        Value::Float(_) => json::object!{ "type" => "number", "format" => "double" },
+        Value::Integer(_) => json::object!{ "type" => "integer", "format" => "int64" },
+        Value::String(_) => json::object!{ "type" => "string" },
+        Value::Null => json::object!{ "nullable" => true },
+    }
+}
+
+// the fields of a submitted object which were rejected
+fn field_errors_schema() -> JsonValue {
+    array_of(json::object!{
+        "type" => "object",
+        "properties" => json::object!{
+            "field" => json::object!{ "type" => "string" },
+            "code" => json::object!{ "type" => "string" },
+            "message" => json::object!{ "type" => "string" },
+        }
+    })
+}
+
+fn schema_ref(name: &str) -> JsonValue {
+    json::object!{ "$ref" => format!("#/components/schemas/{}", name) }
+}
+
+fn string_array() -> JsonValue {
+    json::object!{ "type" => "array", "items" => json::object!{ "type" => "string" } }
+}
+
+fn array_of(items: JsonValue) -> JsonValue {
+    json::object!{ "type" => "array", "items" => items }
+}
+
+fn error_response(description: &str) -> JsonValue {
+    json::object!{
+        "description" => description,
+        "content" => json::object!{
+            problem::PROBLEM_CONTENT_TYPE => json::object!{ "schema" => schema_ref("Problem") }
+        }
+    }
+}
+
+// Replaces the "{}" path variables with named parameters, and lists the path and
+// query string parameters accepted by the query
+fn path_with_parameters(path: &str, query: &Query) -> (String, JsonValue) {
+    let mut parameters = JsonValue::new_array();
+
+    let (name, schema) = match query {
+        Query::GETSupplierIdFromName(_) => ("name", json::object!{ "type" => "string" }),
+        _ => ("id", json::object!{ "type" => "integer", "format" => "int64", "minimum" => 0 }),
+    };
+    if path.contains("{}") {
+        parameters.push(json::object!{
+            "name" => name,
+            "in" => "path",
+            "required" => true,
+            "schema" => schema,
+        }).unwrap();
+    }
+    let path = path.replace("{}", &format!("{{{}}}", name));
+
+    if let Query::POSTSuppliersBatch(_) = query {
+        parameters.push(query_parameter(
+            "mode", 
+            "atomic adds every supplier or none of them, best-effort adds every supplier it can", 
+            json::object!{ "type" => "string", "enum" => json::array!["atomic", "best-effort"], "default" => "atomic" }
+        )).unwrap();
+    }
+
+    if let Query::DELETESupplier(_, _) = query {
+        parameters.push(query_parameter(
+            "mode",
+            "soft marks the supplier as inactive so it can be restored, purge removes it and needs an admin token",
+            json::object!{ "type" => "string", "enum" => json::array!["soft", "purge"], "default" => "soft" }
+        )).unwrap();
+    }
+
+    if let Query::GETSuppliers(_) | Query::GETSuppliersEmail(_) | Query::GETSuppliersNumbers(_) | Query::GETSupplierFromId(_, _) = query {
+        parameters.push(query_parameter("fields", "Comma separated fields to return, other fields are left out", json::object!{ "type" => "string" })).unwrap();
+    }
+    if let Query::GETSuppliers(_) | Query::GETSupplierFromId(_, _) = query {
+        let names: Vec<&str> = Expansion::ALL.iter().map(|expansion| expansion.name()).collect();
+        parameters.push(query_parameter(
+            "expand",
+            &format!("Comma separated related resources to embed, from {}", names.join(", ")),
+            json::object!{ "type" => "string" }
+        )).unwrap();
+    }
+
+    if let Query::GETSearch(_) = query {
+        let mut text = query_parameter("q", "Text to search for, each term must be at least 3 characters long", json::object!{ "type" => "string" });
+        text["required"] = true.into();
+        parameters.push(text).unwrap();
+        parameters.push(query_parameter("limit", "Largest number of results of each type to return", json::object!{ "type" => "integer", "minimum" => 1 })).unwrap();
+    }
+
+    if let Query::GETSuppliers(_) | Query::GETSuppliersEmail(_) | Query::GETSuppliersNumbers(_) = query {
+        let table = tables::get_tables(query.clone());
+
+        parameters.push(query_parameter("limit", "Largest number of items to return", json::object!{ "type" => "integer", "minimum" => 1 })).unwrap();
+        parameters.push(query_parameter("offset", "Number of items to skip", json::object!{ "type" => "integer", "minimum" => 0 })).unwrap();
+        parameters.push(query_parameter("cursor", "Page cursor taken from the next or prev links, empty for the first page", json::object!{ "type" => "string" })).unwrap();
+        parameters.push(query_parameter("sort", "Comma separated fields to sort by, prefix a field with - to sort descending", json::object!{ "type" => "string" })).unwrap();
+
+        for field in &table.fields {
+            parameters.push(query_parameter(&field.name, "Filter on the field value", value_schema(&field.field_type))).unwrap();
+        }
+        for (name, schema) in [
+            (data_keys::ACTIVE, json::object!{ "type" => "boolean" }),
+            (data_keys::CATEGORY_TYPE, json::object!{ "type" => "string" }),
+            (data_keys::CATEGORY_ID, json::object!{ "type" => "integer" }),
+        ] {
+            if !table.fields.iter().any(|field| field.name == name) {
+                parameters.push(query_parameter(name, "Filter on the supplier", schema)).unwrap();
+            }
+        }
+    }
+
+    (path, parameters)
+}
+
+fn query_parameter(name: &str, description: &str, schema: JsonValue) -> JsonValue {
+    json::object!{
+        "name" => name,
+        "in" => "query",
+        "required" => false,
+        "description" => description,
+        "schema" => schema,
+    }
+}
+
+// The schema of the payload returned by each query, this mirrors the json built in get_processing
+fn payload_schema(query: &Query, version: ApiVersion) -> JsonValue {
+    match query {
+        Query::GETSuppliers(_) => {
+            // the fields of each supplier can be chosen, and its related resources embedded
+            let mut supplier = supplier_schema(&[], version);
+            supplier.remove("required");
+            embedded_schemas(&mut supplier, version);
+            array_of(supplier)
+        },
+        Query::GETSuppliersEmail(_) => array_of(schema_ref("Email")),
+        Query::GETSuppliersNumbers(_) => array_of(schema_ref("Number")),
+        Query::GETSuppliersCategories |
+        Query::GETSupplierCategoriesFromId(_) => array_of(schema_ref("Category")),
+        Query::GETSupplierIdFromName(_) => schema_ref("Id"),
+        Query::GETSupplierNameFromId(_) => schema_ref("SupplierName"),
+        Query::GETSupplierAddressFromId(_) | Query::PUTSupplierAddress(_) => schema_ref("Address"),
+        Query::GETSupplierFromId(_, _) |
+        Query::PATCHSupplier(_) |
+        Query::PUTSupplier(_) |
+        Query::POSTSupplierRestore(_) => {
+            let mut supplier = supplier_schema(&[
+                data_keys::ADDRESS_ID, data_keys::CONTACT_ID, data_keys::REP_ID
+            ], version);
+            contact_schema(&mut supplier, version);
+            embedded_schemas(&mut supplier, version);
+
+            // a supplier fetched by id holds only the fields chosen by `fields` and `expand`
+            if let Query::GETSupplierFromId(_, _) = query {
+                supplier.remove("required");
+            }
+            supplier
+        },
+        Query::GETSupplierRepFromId(_) | Query::GETSupplyRepFromId(_) | Query::PUTSupplyRep(_) => {
+            let table_query = match query {
+                Query::PUTSupplyRep(id) => Query::GETSupplyRepFromId(*id),
+                _ => query.clone(),
+            };
+            let mut rep = table_schema(&tables::get_tables(table_query), &[data_keys::CONTACT_ID]);
+            contact_schema(&mut rep, version);
+            rep
+        },
+        Query::GETSupplierEmailFromId(_) |
+        Query::GETSupplierNumbersFromId(_) |
+        Query::GETSupplyRepEmailFromId(_) |
+        Query::GETSupplyRepPhoneNumbersFromId(_) => string_array(),
+        Query::GETSearch(_) => {
+            let result = table_schema(&tables::search_table(), &[data_keys::SEARCH_TYPE]);
+            let mut groups = json::object!{};
+            for (_, group) in search::RESULT_GROUPS {
+                groups[group] = array_of(result.clone());
+            }
+            json::object!{ "type" => "object", "properties" => groups }
+        },
+        Query::POSTSupplier(_) => schema_ref("Id"),
+        Query::POSTSuppliersBatch(_) => array_of(json::object!{
+            "type" => "object",
+            "properties" => json::object!{
+                "index" => json::object!{ "type" => "integer" },
+                "success" => json::object!{ "type" => "boolean" },
+                data_keys::ID => json::object!{ "type" => "integer" },
+                "status" => json::object!{ "type" => "integer" },
+                "detail" => json::object!{ "type" => "string" },
+                "errors" => field_errors_schema(),
+            }
+        }),
+        _ => json::object!{},
+    }
+}
+
+fn response_schema(query: &Query, version: ApiVersion) -> JsonValue {
+    let mut schema = json::object!{
+        "type" => "object",
+        "properties" => json::object!{
+            "code" => json::object!{ "type" => "integer" },
+            "success" => json::object!{ "type" => "boolean" },
+            "payload" => payload_schema(query, version),
+        }
+    };
+
+    if let Query::GETSuppliers(_) | Query::GETSuppliersEmail(_) | Query::GETSuppliersNumbers(_) = query {
+        schema["properties"]["meta"] = json::object!{
+            "type" => "object",
+            "properties" => json::object!{
+                "total" => json::object!{ "type" => "integer" },
+                "count" => json::object!{ "type" => "integer" },
+                "limit" => json::object!{ "type" => "integer", "nullable" => true },
+                "offset" => json::object!{ "type" => "integer", "nullable" => true },
+            }
+        };
+        let link = json::object!{ "type" => "string", "nullable" => true };
+        schema["properties"]["links"] = json::object!{
+            "type" => "object",
+            "properties" => json::object!{
+                "self" => json::object!{ "type" => "string" },
+                "next" => link.clone(),
+                "prev" => link,
+            }
+        };
+    }
+    schema
+}
+
+// The schema of the json body sent with the query, None if the query does not take a body
+fn request_schema(query: &Query) -> Option<JsonValue> {
+    match query {
+        Query::POSTSupplier(_) |
+        Query::POSTAddress(_) |
+        Query::POSTContactEmails(_) |
+        Query::POSTContactPhoneNumbers(_) |
+        Query::POSTRep(_) => Some(table_schema(&tables::post_tables(query.clone()), &[])),
+        Query::POSTSuppliersBatch(_) => Some(array_of(request_schema(&Query::POSTSupplier(Content::None))?)),
+        Query::PUTSupplier(_) => request_schema(&Query::POSTSupplier(Content::None)),
+        Query::PUTSupplierAddress(_) => request_schema(&Query::POSTAddress(Content::None)),
+        Query::PUTSupplyRep(_) => {
+            // contact details left out of the replacement are removed
+            let mut rep = request_schema(&Query::POSTRep(Content::None))?;
+            rep["properties"][data_keys::CONTACT] = json::object!{
+                "type" => "object",
+                "properties" => json::object!{
+                    data_keys::EMAIL => string_array(),
+                    data_keys::NUMBER => string_array(),
+                }
+            };
+            Some(rep)
+        },
+        Query::PATCHSupplier(_) => {
+            // a merge patch holds any of the fields of the supplier, the ids can not be patched
+            let mut supplier = payload_schema(query, ApiVersion::LATEST);
+            supplier["properties"].remove(data_keys::ID);
+            Some(merge_patch_schema(supplier))
+        },
+        _ => None,
+    }
+}
+
+// Every field of a merge patch is optional, and may be null to remove the field
+fn merge_patch_schema(mut schema: JsonValue) -> JsonValue {
+    schema.remove("required");
+    if schema["properties"].is_object() {
+        let keys: Vec<String> = schema["properties"].entries().map(|(key, _)| key.to_string()).collect();
+        for key in keys {
+            let mut property = merge_patch_schema(schema["properties"][&key].take());
+            if !property.has_key("$ref") {
+                property["nullable"] = true.into();
+            }
+            schema["properties"][&key] = property;
+        }
+    }
+    schema
+}
+
+
+#[cfg(test)]
+mod test {
+    use crate::server::api::routing::ApiTree;
+    use crate::server::api::query_types::ApiVersion;
+
+    #[test]
+    fn test_openapi_paths_from_routes() {
+        let api_tree = ApiTree::new(ApiVersion::V2);
+        let document = super::openapi_document(&api_tree);
+
+        assert_eq!(document["openapi"], "3.0.3");
+        assert!(document["paths"]["/api/v2/suppliers"]["get"].is_object());
+        assert_eq!(document["paths"]["/api/v2/supplier/{id}/address"]["get"]["parameters"][0]["name"], "id");
+        assert!(document["paths"]["/api/v2/supplier/id/{name}"]["get"].is_object());
+        assert_eq!(document["components"]["schemas"]["Address"]["properties"]["line2"]["nullable"], true);
+        assert_eq!(document["components"]["schemas"]["Supplier"]["properties"]["active"]["type"], "boolean");
+
+        let document = super::openapi_document(&ApiTree::new(ApiVersion::V1));
+        assert_eq!(document["paths"]["/api/v1/suppliers"]["get"]["deprecated"], true);
+        assert_eq!(document["components"]["schemas"]["Supplier"]["properties"]["active"]["type"], "integer");
+    }
+}
@@ -1,6 +1,8 @@
 use crate::server::api::query_types::Query;
-use crate::server::api::query_types::Content;
-use regex::Regex;
+use crate::server::api::query_types::{Content, CollectionParams, PageCursor, SortKey, OutputFormat, BatchMode, DeleteMode, SearchParams, FieldSelection, Expansion};
+use crate::config::{DEFAULT_PAGE_LIMIT, MAX_PAGE_LIMIT, DEFAULT_SEARCH_LIMIT};
+use json::JsonValue;
+use crate::server::api::uri::percent_encode;
 
 
 // This function uses a tree to validate and parse the api uri resource path
@@ -10,22 +12,30 @@ use regex::Regex;
 // The main purpose of this function is to take a query and a set of variables 
 // and assign the variables to the query. To do so it must parse the variables, 
 // if the variables are not valid then it will returns ApiInvalidUri.
-pub fn query_with_path_variables(query: &Query, variables: &Vec<String>) -> Query {
+// Collection queries also take their pagination, sorting and filtering options from
+// the query string parameters of the request, and suppliers the fields and related 
+// resources to return.
+pub fn query_with_path_variables(query: &Query, variables: &[String], path: &str, query_params: &[(String, String)]) -> Query {
 
     let mut response_query = Query::ApiInvalidUri;
 
     match query {
 
         // suppliers
-        Query::GETSuppliers => {
-            response_query = Query::GETSuppliers;
+        Query::GETSuppliers(_) => {
+            if let Some(params) = collection_params(path, query_params) {
+                response_query = Query::GETSuppliers(params);
+            }
         },
-        Query::GETSuppliersEmail => {
-            response_query = Query::GETSuppliersEmail;
-                
+        Query::GETSuppliersEmail(_) => {
+            if let Some(params) = collection_params(path, query_params) {
+                response_query = Query::GETSuppliersEmail(params);
+            }
         },
-        Query::GETSuppliersNumbers => {
-            response_query = Query::GETSuppliersNumbers;
+        Query::GETSuppliersNumbers(_) => {
+            if let Some(params) = collection_params(path, query_params) {
+                response_query = Query::GETSuppliersNumbers(params);
+            }
         },
         Query::GETSuppliersCategories => {
             response_query = Query::GETSuppliersCategories;
@@ -37,15 +47,13 @@ pub fn query_with_path_variables(query: &Query, variables: &Vec<String>) -> Quer
                 response_query = Query::GETSupplierNameFromId(id);
             }
         },
-        Query::GETSupplierFromId(_) => {
-            if let Ok(id) = variables[0].parse::<u64>() {
-                response_query = Query::GETSupplierFromId(id);
+        Query::GETSupplierFromId(_, _) => {
+            if let (Ok(id), Some(selection)) = (variables[0].parse::<u64>(), field_selection(query_params)) {
+                response_query = Query::GETSupplierFromId(id, selection);
             }    
         },
         Query::GETSupplierIdFromName(_) => {
-            let name = &variables[0];
-            let name = uri_seg_decode(name);
-            response_query = Query::GETSupplierIdFromName(name.clone());
+            response_query = Query::GETSupplierIdFromName(variables[0].clone());
         },
         Query::GETSupplierEmailFromId(_) => {
             if let Ok(id) = variables[0].parse::<u64>() {
@@ -90,12 +98,67 @@ pub fn query_with_path_variables(query: &Query, variables: &Vec<String>) -> Quer
             }
         },
 
-        // POST supplier
+        // search, "?q=" holds the text to search for
+        Query::GETSearch(_) => {
+            if let Some(params) = search_params(query_params) {
+                response_query = Query::GETSearch(params);
+            }
+        },
+
+        // POST supplier, the body of the request is handed to the query when it is processed
         Query::POSTSupplier(_) => {
-            let json_body = json::parse(&variables[0].clone());
-            if json_body.is_ok() {
-                response_query = Query::POSTSupplier(Content::Json(json_body.unwrap()));
-            }     
+            response_query = Query::POSTSupplier(Content::None);
+        },
+        Query::POSTSuppliersBatch(_) => {
+            // the batch is stored atomically unless best effort is asked for with "?mode=best-effort"
+            let mode = match query_params.iter().find(|(key, _)| key == "mode") {
+                Some((_, name)) => BatchMode::from_name(name),
+                None => Some(BatchMode::Atomic),
+            };
+            if let Some(mode) = mode {
+                response_query = Query::POSTSuppliersBatch(mode);
+            }
+        },
+        Query::POSTSupplierRestore(_) => {
+            if let Ok(id) = variables[0].parse::<u64>() {
+                response_query = Query::POSTSupplierRestore(id);
+            }
+        },
+
+        // PATCH supplier, the merge patch is handed to the query when it is processed
+        Query::PATCHSupplier(_) => {
+            if let Ok(id) = variables[0].parse::<u64>() {
+                response_query = Query::PATCHSupplier(id);
+            }
+        },
+
+        // PUT queries, the replacement is handed to the query when it is processed
+        Query::PUTSupplier(_) => {
+            if let Ok(id) = variables[0].parse::<u64>() {
+                response_query = Query::PUTSupplier(id);
+            }
+        },
+        Query::PUTSupplierAddress(_) => {
+            if let Ok(id) = variables[0].parse::<u64>() {
+                response_query = Query::PUTSupplierAddress(id);
+            }
+        },
+        Query::PUTSupplyRep(_) => {
+            if let Ok(id) = variables[0].parse::<u64>() {
+                response_query = Query::PUTSupplyRep(id);
+            }
+        },
+
+        // DELETE supplier
+        Query::DELETESupplier(_, _) => {
+            // a supplier is soft deleted unless it is purged with "?mode=purge"
+            let mode = match query_params.iter().find(|(key, _)| key == "mode") {
+                Some((_, name)) => DeleteMode::from_name(name),
+                None => Some(DeleteMode::Soft),
+            };
+            if let (Ok(id), Some(mode)) = (variables[0].parse::<u64>(), mode) {
+                response_query = Query::DELETESupplier(id, mode);
+            }
         },
 
         _ => panic!("Query has not been implemented at query_add_variables in parsing.rs {:?}", query),
@@ -104,28 +167,293 @@ pub fn query_with_path_variables(query: &Query, variables: &Vec<String>) -> Quer
 }
 
 
-fn uri_seg_decode(uri: &str) -> String {
-    let mut uri = uri.to_string();
-    let special_regex = Regex::new(r"(%[0-9A-Fa-f]{2})").unwrap();
-    for cap in special_regex.captures_iter(&uri.clone()) {
-        let special = cap.get(1).unwrap().as_str();
-        let special = special.replace("%", "");
-        let special = u8::from_str_radix(&special, 16).unwrap();
-        let special = special as char;
-        uri = uri.replace(cap.get(1).unwrap().as_str(), &special.to_string());
+// Takes the `fields` and `expand` options from the query string, both are comma separated 
+// lists. Field names are checked against the resource when the query is made, while an 
+// unknown expansion returns None.
+fn field_selection(query_params: &[(String, String)]) -> Option<FieldSelection> {
+    let mut selection = FieldSelection::default();
+    for (key, value) in query_params {
+        let names = value.split(',').map(|x| x.trim()).filter(|x| !x.is_empty());
+        match key.as_str() {
+            "fields" => {
+                selection.fields = names.map(|name| name.to_string()).collect();
+            },
+            "expand" => {
+                let mut expand = Vec::new();
+                for name in names {
+                    let expansion = Expansion::from_name(name)?;
+                    if !expand.contains(&expansion) {
+                        expand.push(expansion);
+                    }
+                }
+                selection.expand = Some(expand);
+            },
+            _ => {}
+        }
+    }
+    Some(selection)
+}
+
+// Builds the options for a search, the text to search for is checked when the search is made
+// so that a search without any text can be reported as such
+fn search_params(query_params: &[(String, String)]) -> Option<SearchParams> {
+    let mut params = SearchParams {
+        text: String::new(),
+        limit: DEFAULT_SEARCH_LIMIT,
+    };
+    for (key, value) in query_params {
+        match key.as_str() {
+            "q" => params.text = value.clone(),
+            "limit" => {
+                params.limit = value.parse::<u64>().ok()?;
+                if params.limit == 0 || params.limit > MAX_PAGE_LIMIT {
+                    return None;
+                }
+            },
+            "format" => {},
+            _ => return None,
+        }
     }
-    uri
+    Some(params)
 }
 
+// Builds the pagination, sorting and filtering options for a collection query.
+//
+// `limit` and `offset` select a page by position, while `cursor` selects a page relative
+// to the row given in the cursor (an empty cursor starts from the first row). A cursor can
+// not be combined with an offset or with `sort`, as cursor pages are always in key order.
+// `sort` takes a comma separated list of fields, a leading "-" sorts that field descending.
+// `fields` and `expand` are read by field_selection, `format` selects the output format, see 
+// negotiate_format, and every other parameter is treated as a field filter. Returns None if 
+// any value is invalid.
+pub fn collection_params(path: &str, query_params: &[(String, String)]) -> Option<CollectionParams> {
+    let mut params = CollectionParams {
+        path: path.to_string(),
+        selection: field_selection(query_params)?,
+        ..Default::default()
+    };
+    let mut has_offset = false;
+
+    for (key, value) in query_params {
+        match key.as_str() {
+            "limit" => {
+                let limit = value.parse::<u64>().ok()?;
+                if limit == 0 || limit > MAX_PAGE_LIMIT {
+                    return None;
+                }
+                params.limit = Some(limit);
+            },
+            "offset" => {
+                params.offset = value.parse::<u64>().ok()?;
+                has_offset = true;
+            },
+            "cursor" => {
+                params.cursor = Some(decode_cursor(value)?);
+            },
+            "sort" => {
+                params.sort.clear();
+                for field in value.split(',').map(|x| x.trim()).filter(|x| !x.is_empty()) {
+                    let sort_key = match field.strip_prefix('-') {
+                        Some(field) => SortKey { field: field.to_string(), descending: true },
+                        None => SortKey { field: field.to_string(), descending: false },
+                    };
+                    params.sort.push(sort_key);
+                }
+            },
+            "format" | "fields" | "expand" => {},
+            _ => {
+                params.filters.push((key.clone(), value.clone()));
+            }
+        }
+    }
+
+    if params.cursor.is_some() {
+        if has_offset || !params.sort.is_empty() {
+            return None;
+        }
+        if params.limit.is_none() {
+            params.limit = Some(DEFAULT_PAGE_LIMIT);
+        }
+    }
+    Some(params)
+}
 
+// Picks the format a response is rendered in. A `format` query string parameter takes
+// precedence over the `Accept` header, whose media ranges are tried in order of their
+// quality value. Having neither gives json, while None is returned when no acceptable
+// format can be produced.
+pub fn negotiate_format(accept: Option<&str>, query_params: &[(String, String)]) -> Option<OutputFormat> {
+    if let Some((_, name)) = query_params.iter().find(|(key, _)| key == "format") {
+        return OutputFormat::from_name(name);
+    }
 
+    let accept = match accept {
+        Some(accept) if !accept.trim().is_empty() => accept,
+        _ => return Some(OutputFormat::Json),
+    };
+
+    let mut ranges: Vec<(&str, f32)> = Vec::new();
+    for range in accept.split(',') {
+        let mut parts = range.split(';').map(|x| x.trim());
+        let media_range = parts.next().unwrap_or("");
+        let mut quality = 1.0;
+        for parameter in parts {
+            if let Some(("q", value)) = parameter.split_once('=').map(|(k, v)| (k.trim(), v.trim())) {
+                quality = value.parse::<f32>().unwrap_or(0.0);
+            }
+        }
+        if quality > 0.0 {
+            ranges.push((media_range, quality));
+        }
+    }
+    // a stable sort keeps ranges of equal quality in the order the client gave them
+    ranges.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+    ranges.iter().find_map(|(media_range, _)| OutputFormat::from_media_range(media_range))
+}
+
+// Cursors are passed to clients as a hex encoded json array, the first item being the 
+// direction of the page relative to the rest of the items, which are the key values of a row.
+pub fn encode_cursor(cursor: &PageCursor) -> String {
+    let mut array = JsonValue::new_array();
+    let keys = match cursor {
+        PageCursor::Start => return String::new(),
+        PageCursor::After(keys) => {
+            array.push("after").unwrap();
+            keys
+        },
+        PageCursor::Before(keys) => {
+            array.push("before").unwrap();
+            keys
+        },
+    };
+    for key in keys {
+        array.push(key.clone()).unwrap();
+    }
+    array.dump().bytes().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn decode_cursor(value: &str) -> Option<PageCursor> {
+    if value.is_empty() {
+        return Some(PageCursor::Start);
+    }
+    if !value.len().is_multiple_of(2) || !value.is_ascii() {
+        return None;
+    }
+    let mut bytes = Vec::new();
+    for i in (0..value.len()).step_by(2) {
+        bytes.push(u8::from_str_radix(&value[i..i + 2], 16).ok()?);
+    }
+    let mut array = json::parse(&String::from_utf8(bytes).ok()?).ok()?;
+    if !array.is_array() || array.len() < 2 {
+        return None;
+    }
+    let direction = array.array_remove(0);
+    let keys: Vec<JsonValue> = array.members().cloned().collect();
+    match direction.as_str() {
+        Some("after") => Some(PageCursor::After(keys)),
+        Some("before") => Some(PageCursor::Before(keys)),
+        _ => None,
+    }
+}
+
+// Builds the link to another page of a collection, keeping the filters, sorting and limit
+// of the original request. A cursor takes the place of the offset when one is given.
+pub fn collection_link(params: &CollectionParams, offset: u64, cursor: Option<&PageCursor>) -> String {
+    let mut query: Vec<String> = Vec::new();
+    for (key, value) in &params.filters {
+        query.push(format!("{}={}", percent_encode(key), percent_encode(value)));
+    }
+    if !params.sort.is_empty() {
+        let sort: Vec<String> = params.sort.iter()
+            .map(|key| format!("{}{}", if key.descending { "-" } else { "" }, percent_encode(&key.field)))
+            .collect();
+        query.push(format!("sort={}", sort.join(",")));
+    }
+    if !params.selection.fields.is_empty() {
+        let fields: Vec<String> = params.selection.fields.iter().map(|field| percent_encode(field)).collect();
+        query.push(format!("fields={}", fields.join(",")));
+    }
+    if let Some(expand) = &params.selection.expand {
+        let expand: Vec<&str> = expand.iter().map(|expansion| expansion.name()).collect();
+        query.push(format!("expand={}", expand.join(",")));
+    }
+    if let Some(limit) = params.limit {
+        query.push(format!("limit={}", limit));
+    }
+    match cursor {
+        Some(cursor) => query.push(format!("cursor={}", encode_cursor(cursor))),
+        None if offset > 0 => query.push(format!("offset={}", offset)),
+        None => {}
+    }
+
+    if query.is_empty() {
+        params.path.clone()
+    } else {
+        format!("{}?{}", params.path, query.join("&"))
+    }
+}
 
 #[cfg(test)]
 mod test {
+    use crate::server::api::uri::split_request_target;
+
+    #[test]
+    fn test_collection_params() {
+        let (path, query) = split_request_target("/api/suppliers?active=true&sort=name,-id&limit=10&offset=20").unwrap();
+        let params = super::collection_params(&path, &query).unwrap();
+        assert_eq!(params.path, "/api/suppliers");
+        assert_eq!(params.limit, Some(10));
+        assert_eq!(params.offset, 20);
+        assert_eq!(params.sort.len(), 2);
+        assert!(params.sort[1].descending);
+        assert_eq!(params.filters, vec![("active".to_string(), "true".to_string())]);
+        assert_eq!(super::collection_link(&params, 30, None), "/api/suppliers?active=true&sort=name,-id&limit=10&offset=30");
+
+        let (path, query) = split_request_target("/api/suppliers?cursor=&sort=name").unwrap();
+        assert!(super::collection_params(&path, &query).is_none());
+    }
+
+    #[test]
+    fn test_field_selection() {
+        use super::Expansion;
+        let (path, query) = split_request_target("/api/suppliers?fields=name,active&expand=rep,address,rep").unwrap();
+        let params = super::collection_params(&path, &query).unwrap();
+        assert_eq!(params.selection.fields, vec!["name".to_string(), "active".to_string()]);
+        assert_eq!(params.selection.expand, Some(vec![Expansion::Rep, Expansion::Address]));
+        assert!(params.filters.is_empty());
+        assert_eq!(super::collection_link(&params, 0, None), "/api/suppliers?fields=name,active&expand=rep,address");
+
+        let (path, query) = split_request_target("/api/suppliers?expand=categories").unwrap();
+        assert!(super::collection_params(&path, &query).is_none());
+    }
+
+    #[test]
+    fn test_negotiate_format() {
+        use super::{negotiate_format, OutputFormat};
+        let format = |name: &str| vec![("format".to_string(), name.to_string())];
+
+        assert_eq!(negotiate_format(None, &[]), Some(OutputFormat::Json));
+        assert_eq!(negotiate_format(Some("*/*"), &[]), Some(OutputFormat::Json));
+        assert_eq!(negotiate_format(Some("text/csv"), &[]), Some(OutputFormat::Csv));
+        assert_eq!(negotiate_format(Some("text/html, application/xml;q=0.9, */*;q=0.8"), &[]), Some(OutputFormat::Xml));
+        assert_eq!(negotiate_format(Some("application/json;q=0.5, text/csv"), &[]), Some(OutputFormat::Csv));
+        assert_eq!(negotiate_format(Some("text/html"), &[]), None);
+        assert_eq!(negotiate_format(Some("text/csv;q=0"), &[]), None);
+        assert_eq!(negotiate_format(Some("text/html"), &format("csv")), Some(OutputFormat::Csv));
+        assert_eq!(negotiate_format(None, &format("pdf")), None);
+    }
+
     #[test]
-    fn test_uri_seg_decode() {
-        let uri = "/api/supplier/1/Mr%20Smith%20%26%20Co%20Ltd/name";
-        let decoded = super::uri_seg_decode(uri);
-        assert_eq!(decoded, "/api/supplier/1/Mr Smith & Co Ltd/name");
+    fn test_cursor_round_trip() {
+        let cursor = super::PageCursor::After(vec![json::JsonValue::from(12), json::JsonValue::from("a@b.com")]);
+        let encoded = super::encode_cursor(&cursor);
+        match super::decode_cursor(&encoded) {
+            Some(super::PageCursor::After(keys)) => {
+                assert_eq!(keys[0], 12);
+                assert_eq!(keys[1], "a@b.com");
+            },
+            _ => panic!("cursor did not decode"),
+        }
     }
 }
@@ -0,0 +1,131 @@
+//! Error responses in the problem details format of RFC 7807.
+//!
+//! Every error is sent as an `application/problem+json` object holding the type, title and
+//! status of the problem, a detail message for this occurrence, the path and id of the
+//! request, and an `errors` array naming any fields of a submitted object which were rejected.
+//! Some problems add members of their own, such as the current representation of a resource.
+
+use crate::config::PROBLEM_TYPE_BASE;
+use crate::errors::{DatabaseError, ErrorType, FieldError};
+use json::JsonValue;
+
+pub const PROBLEM_CONTENT_TYPE: &str = "application/problem+json";
+
+pub struct Problem {
+    pub status: u16,
+    pub detail: String,
+    pub errors: Vec<FieldError>,
+    pub members: Vec<(String, JsonValue)>,
+}
+
+impl Problem {
+    pub fn new(status: u16, detail: &str) -> Problem {
+        Problem {
+            status,
+            detail: detail.to_string(),
+            errors: Vec::new(),
+            members: Vec::new(),
+        }
+    }
+
+    // The details of server errors are kept from the client, which is given a general message
+    pub fn from_database_error(error: &DatabaseError) -> Problem {
+        let status = error.status();
+        let mut problem = Problem::new(status, &client_detail(status, error.message()));
+        problem.errors = error.field_errors().to_vec();
+        problem
+    }
+
+    pub fn from_error_type(error: &ErrorType) -> Problem {
+        let status = error.status();
+        Problem::new(status, &client_detail(status, error.message()))
+    }
+
+    pub fn to_json(&self, instance: &str, request_id: &str) -> JsonValue {
+        let (name, title) = problem_type(self.status);
+
+        let mut errors = JsonValue::new_array();
+        for error in &self.errors {
+            errors.push(error.to_json()).unwrap();
+        }
+
+        let mut problem = json::object!{
+            "type" => format!("{}{}", PROBLEM_TYPE_BASE, name),
+            "title" => title,
+            "status" => self.status,
+            "detail" => self.detail.clone(),
+            "instance" => instance,
+            "requestId" => request_id,
+            "errors" => errors,
+        };
+        for (key, value) in &self.members {
+            problem[key.as_str()] = value.clone();
+        }
+        problem
+    }
+
+    // The content, content type and status line of the response
+    pub fn response(&self, instance: &str, request_id: &str) -> (String, String, String) {
+        (
+            self.to_json(instance, request_id).dump(),
+            PROBLEM_CONTENT_TYPE.to_string(),
+            status_line(self.status),
+        )
+    }
+}
+
+fn client_detail(status: u16, message: &str) -> String {
+    if status >= 500 {
+        String::from("The server was unable to complete the request")
+    } else {
+        message.to_string()
+    }
+}
+
+pub fn status_line(status: u16) -> String {
+    format!("HTTP/1.1 {} {}", status, problem_type(status).1)
+}
+
+// The short name used in the type uri and the title of the problem for each status
+fn problem_type(status: u16) -> (&'static str, &'static str) {
+    match status {
+        400 => ("bad-request", "Bad Request"),
+        403 => ("forbidden", "Forbidden"),
+        404 => ("not-found", "Not Found"),
+        405 => ("method-not-allowed", "Method Not Allowed"),
+        406 => ("not-acceptable", "Not Acceptable"),
+        409 => ("conflict", "Conflict"),
+        412 => ("precondition-failed", "Precondition Failed"),
+        415 => ("unsupported-media-type", "Unsupported Media Type"),
+        422 => ("validation-error", "Unprocessable Entity"),
+        428 => ("precondition-required", "Precondition Required"),
+        501 => ("not-implemented", "Not Implemented"),
+        503 => ("service-unavailable", "Service Unavailable"),
+        _ => ("server-error", "Internal Server Error"),
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use crate::errors::{DatabaseError, FieldError};
+
+    #[test]
+    fn test_problem_from_database_error() {
+        let error = DatabaseError::ValidationError(
+            String::from("The supplier is not valid"),
+            vec![FieldError::new("address.postcode", "required", "address.postcode is required")],
+        );
+        let problem = super::Problem::from_database_error(&error).to_json("/api/suppliers", "abc");
+        assert_eq!(problem["type"], "urn:pos:problem:validation-error");
+        assert_eq!(problem["status"], 422);
+        assert_eq!(problem["detail"], "The supplier is not valid");
+        assert_eq!(problem["requestId"], "abc");
+        assert_eq!(problem["errors"][0]["field"], "address.postcode");
+
+        // server errors do not expose their cause
+        let problem = super::Problem::from_database_error(&DatabaseError::QueryError(String::from("no such table"))).to_json("/", "abc");
+        assert_eq!(problem["status"], 500);
+        assert_ne!(problem["detail"], "no such table");
+    }
+}
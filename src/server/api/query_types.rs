@@ -23,15 +23,205 @@ impl Clone for Content {
     }
 }
 
+/// The versions of the api, each version fixes the shape of the json payloads it returns
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApiVersion {
+    V1,
+    V2,
+}
+
+impl ApiVersion {
+    /// The version used by "/api" paths which do not name a version
+    pub const LATEST: ApiVersion = ApiVersion::V2;
+
+    pub fn segment(&self) -> &'static str {
+        match self {
+            ApiVersion::V1 => "v1",
+            ApiVersion::V2 => "v2",
+        }
+    }
+
+    pub fn from_segment(segment: &str) -> Option<ApiVersion> {
+        match segment {
+            "v1" => Some(ApiVersion::V1),
+            "v2" => Some(ApiVersion::V2),
+            _ => None,
+        }
+    }
+}
+
+/// The formats a response holding database rows can be rendered in, chosen from the
+/// `Accept` header of a request or the `format` query string parameter
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    Json,
+    Csv,
+    Xml,
+}
+
+impl OutputFormat {
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            OutputFormat::Json => "application/json",
+            OutputFormat::Csv => "text/csv",
+            OutputFormat::Xml => "application/xml",
+        }
+    }
+
+    /// Matches the value of a `format` query string parameter, e.g. `?format=csv`
+    pub fn from_name(name: &str) -> Option<OutputFormat> {
+        match name.to_ascii_lowercase().as_str() {
+            "json" => Some(OutputFormat::Json),
+            "csv" => Some(OutputFormat::Csv),
+            "xml" => Some(OutputFormat::Xml),
+            _ => None,
+        }
+    }
+
+    /// Matches a media range from an `Accept` header, wildcards match the first
+    /// format with a suitable type
+    pub fn from_media_range(range: &str) -> Option<OutputFormat> {
+        match range.to_ascii_lowercase().as_str() {
+            "*/*" | "application/*" | "application/json" => Some(OutputFormat::Json),
+            "text/*" | "text/csv" => Some(OutputFormat::Csv),
+            "application/xml" | "text/xml" => Some(OutputFormat::Xml),
+            _ => None,
+        }
+    }
+}
+
+/// A single `sort` key taken from a collection query string, e.g. `-id`
+#[derive(Debug, Clone)]
+pub struct SortKey {
+    pub field: String,
+    pub descending: bool,
+}
+
+/// Position within a collection used for cursor based pagination. The values are
+/// those of the collection's key fields for the row the page starts after, or ends before.
+#[derive(Debug, Clone)]
+pub enum PageCursor {
+    Start,
+    After(Vec<JsonValue>),
+    Before(Vec<JsonValue>),
+}
+
+/// Holds the pagination, sorting and filtering options given in the query string
+/// of a request made against a collection endpoint such as `/api/suppliers`
+#[derive(Debug, Clone, Default)]
+pub struct CollectionParams {
+    pub path: String,
+    pub limit: Option<u64>,
+    pub offset: u64,
+    pub cursor: Option<PageCursor>,
+    pub sort: Vec<SortKey>,
+    pub filters: Vec<(String, String)>,
+    pub selection: FieldSelection,
+}
+
+/// The related resources which can be embedded within a supplier using `?expand=`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Expansion {
+    Address,
+    Contact,
+    Rep,
+}
+
+impl Expansion {
+    pub const ALL: [Expansion; 3] = [Expansion::Address, Expansion::Contact, Expansion::Rep];
+
+    /// The name of the expansion, which is also the key the related resource is embedded under
+    pub fn name(&self) -> &'static str {
+        match self {
+            Expansion::Address => "address",
+            Expansion::Contact => "contact",
+            Expansion::Rep => "rep",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Expansion> {
+        Expansion::ALL.into_iter().find(|expansion| expansion.name() == name)
+    }
+}
+
+/// Holds the `fields` and `expand` options given in the query string of a request, e.g.
+/// `?fields=name,active&expand=address`. An empty `fields` returns every field, and when
+/// `expand` is None the endpoint's own related resources are embedded, none for a collection
+/// and all of them for a single supplier.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FieldSelection {
+    pub fields: Vec<String>,
+    pub expand: Option<Vec<Expansion>>,
+}
+
+/// Holds the options given in the query string of a request made against `/api/search`.
+/// `limit` is the largest number of results returned for each type of result.
+#[derive(Debug, Clone)]
+pub struct SearchParams {
+    pub text: String,
+    pub limit: u64,
+}
+
+/// How a batch of submitted items is handled when some of them can not be stored.
+/// `Atomic` stores all of the items or none of them, while `BestEffort` stores every
+/// item it can and reports the ones which failed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BatchMode {
+    Atomic,
+    BestEffort,
+}
+
+impl BatchMode {
+    pub fn name(&self) -> &'static str {
+        match self {
+            BatchMode::Atomic => "atomic",
+            BatchMode::BestEffort => "best-effort",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<BatchMode> {
+        match name {
+            "atomic" => Some(BatchMode::Atomic),
+            "best-effort" => Some(BatchMode::BestEffort),
+            _ => None,
+        }
+    }
+}
+
+/// How a supplier is deleted. `Soft` marks the supplier as inactive, so it can be restored,
+/// while `Purge` removes the supplier along with the rows which only it made use of.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeleteMode {
+    Soft,
+    Purge,
+}
+
+impl DeleteMode {
+    pub fn name(&self) -> &'static str {
+        match self {
+            DeleteMode::Soft => "soft",
+            DeleteMode::Purge => "purge",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<DeleteMode> {
+        match name {
+            "soft" => Some(DeleteMode::Soft),
+            "purge" => Some(DeleteMode::Purge),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub enum Query {
-    GETSuppliers,
-    GETSuppliersEmail,
-    GETSuppliersNumbers,
+    GETSuppliers(CollectionParams),
+    GETSuppliersEmail(CollectionParams),
+    GETSuppliersNumbers(CollectionParams),
     GETSuppliersCategories,
 
     GETSupplierNameFromId(u64),
-    GETSupplierFromId(u64),
+    GETSupplierFromId(u64, FieldSelection),
     GETSupplierIdFromName(String),
     GETSupplierEmailFromId(u64),
     GETSupplierNumbersFromId(u64),
@@ -44,27 +234,62 @@ pub enum Query {
     GETSupplyRepPhoneNumbersFromId(u64),
     GETSupplyRepEmailFromId(u64),
 
+    GETSearch(SearchParams),
+
     POSTSupplier(Content),
     POSTAddress(Content),
     POSTContactEmails(Content),
     POSTContactPhoneNumbers(Content),
     POSTRep(Content),
+    POSTSuppliersBatch(BatchMode),
+    POSTSupplierRestore(u64),
+
+    PATCHSupplier(u64),
+
+    PUTSupplier(u64),
+    PUTSupplierAddress(u64),
+    PUTSupplyRep(u64),
+
+    DELETESupplier(u64, DeleteMode),
 
     ApiInvalidUri,
+    ApiMethodNotAllowed(Vec<&'static str>),
     NoneApi,
     ApiDoc,
+    ApiExplorer,
+}
+
+impl Query {
+    /// The http method used to make the query
+    pub fn http_method(&self) -> &'static str {
+        match self {
+            Query::POSTSupplier(_) |
+            Query::POSTAddress(_) |
+            Query::POSTContactEmails(_) |
+            Query::POSTContactPhoneNumbers(_) |
+            Query::POSTRep(_) |
+            Query::POSTSuppliersBatch(_) |
+            Query::POSTSupplierRestore(_) => "POST",
+            Query::PATCHSupplier(_) => "PATCH",
+            Query::PUTSupplier(_) |
+            Query::PUTSupplierAddress(_) |
+            Query::PUTSupplyRep(_) => "PUT",
+            Query::DELETESupplier(_, _) => "DELETE",
+            _ => "GET",
+        }
+    }
 }
 
 impl Clone for Query {
     fn clone(&self) -> Query {
         match self {
-            Query::GETSuppliers => Query::GETSuppliers,    
-            Query::GETSuppliersEmail => Query::GETSuppliersEmail,  
-            Query::GETSuppliersNumbers => Query::GETSuppliersNumbers,
+            Query::GETSuppliers(params) => Query::GETSuppliers(params.clone()),
+            Query::GETSuppliersEmail(params) => Query::GETSuppliersEmail(params.clone()),
+            Query::GETSuppliersNumbers(params) => Query::GETSuppliersNumbers(params.clone()),
             Query::GETSuppliersCategories => Query::GETSuppliersCategories,
 
             Query::GETSupplierNameFromId(id) => Query::GETSupplierNameFromId(*id),
-            Query::GETSupplierFromId(id) => Query::GETSupplierFromId(*id),
+            Query::GETSupplierFromId(id, selection) => Query::GETSupplierFromId(*id, selection.clone()),
             Query::GETSupplierIdFromName(name) => Query::GETSupplierIdFromName(name.clone()),
             Query::GETSupplierEmailFromId(id) => Query::GETSupplierEmailFromId(*id),
             Query::GETSupplierNumbersFromId(id) => Query::GETSupplierNumbersFromId(*id),
@@ -76,15 +301,29 @@ impl Clone for Query {
             Query::GETSupplyRepPhoneNumbersFromId(id) => Query::GETSupplyRepPhoneNumbersFromId(*id),
             Query::GETSupplyRepEmailFromId(id) => Query::GETSupplyRepEmailFromId(*id),
 
+            Query::GETSearch(params) => Query::GETSearch(params.clone()),
+
             Query::POSTSupplier(content) => Query::POSTSupplier(content.clone()),
             Query::POSTAddress(content) => Query::POSTAddress(content.clone()),
             Query::POSTContactEmails(content) => Query::POSTContactEmails(content.clone()),
             Query::POSTContactPhoneNumbers(content) => Query::POSTContactPhoneNumbers(content.clone()),
             Query::POSTRep(content) => Query::POSTRep(content.clone()),
+            Query::POSTSuppliersBatch(mode) => Query::POSTSuppliersBatch(*mode),
+            Query::POSTSupplierRestore(id) => Query::POSTSupplierRestore(*id),
+
+            Query::PATCHSupplier(id) => Query::PATCHSupplier(*id),
+
+            Query::PUTSupplier(id) => Query::PUTSupplier(*id),
+            Query::PUTSupplierAddress(id) => Query::PUTSupplierAddress(*id),
+            Query::PUTSupplyRep(id) => Query::PUTSupplyRep(*id),
+
+            Query::DELETESupplier(id, mode) => Query::DELETESupplier(*id, *mode),
        
             Query::ApiInvalidUri => Query::ApiInvalidUri,
+            Query::ApiMethodNotAllowed(methods) => Query::ApiMethodNotAllowed(methods.clone()),
             Query::NoneApi => Query::NoneApi,
             Query::ApiDoc => Query::ApiDoc,
+            Query::ApiExplorer => Query::ApiExplorer,
         }
     }
 }
\ No newline at end of file
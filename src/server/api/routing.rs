@@ -1,12 +1,71 @@
 use crate::server::api::util_structs::PathSegment;
-use crate::server::api::query_types::Query;
+use crate::server::api::query_types::{Query, Content, CollectionParams, ApiVersion, BatchMode, DeleteMode, SearchParams, FieldSelection};
+use crate::config::{API_V1_DEPRECATED, API_V1_SUNSET};
+
+/// Holds a route tree for every supported version of the api
+pub struct ApiVersions {
+    pub trees: Vec<ApiTree>,
+}
+
+impl ApiVersions {
+    pub fn new() -> ApiVersions {
+        ApiVersions {
+            trees: vec![
+                ApiTree::new(ApiVersion::V1),
+                ApiTree::new(ApiVersion::V2),
+            ],
+        }
+    }
+
+    /// Picks the tree for the version named in the path, e.g. "/api/v1/suppliers". 
+    /// Paths without a version segment use the latest version of the api.
+    pub fn for_path(&self, path: &str) -> &ApiTree {
+        let version = path.split('/').nth(2)
+            .and_then(ApiVersion::from_segment)
+            .unwrap_or(ApiVersion::LATEST);
+
+        self.trees.iter().find(|tree| tree.version == version).unwrap()
+    }
+}
+
+impl Default for ApiVersions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Dates given to clients of a deprecated version of the api. `deprecated` is the unix
+/// time the version was deprecated from, and `sunset` the http date it will be removed on.
+pub struct Deprecation {
+    pub deprecated: i64,
+    pub sunset: &'static str,
+}
+
+impl Deprecation {
+    /// The response headers sent with requests made against a deprecated route, 
+    /// the link header points to the same route on the latest version of the api.
+    pub fn headers(&self, path: &str, version: ApiVersion) -> Vec<(String, String)> {
+        let prefix = format!("/api/{}", version.segment());
+        let successor = match path.strip_prefix(&prefix) {
+            Some(rest) => format!("/api/{}{}", ApiVersion::LATEST.segment(), rest),
+            None => format!("/api/{}", ApiVersion::LATEST.segment()),
+        };
+        vec![
+            (String::from("Deprecation"), format!("@{}", self.deprecated)),
+            (String::from("Sunset"), self.sunset.to_string()),
+            (String::from("Link"), format!("<{}>; rel=\"successor-version\"", successor)),
+        ]
+    }
+}
 
 pub struct ApiTree {
+    pub version: ApiVersion,
+    pub deprecation: Option<Deprecation>,
     pub tree: Box<PathSegment>,
 }
 
 impl ApiTree {
-    pub fn new() -> ApiTree {
+    pub fn new(version: ApiVersion) -> ApiTree {
 
         // A RESTful API can be thought as a tree like structure
         // With each part of the uri acting as a branch or node.
@@ -17,11 +76,40 @@ impl ApiTree {
         // as it contains all the possible api routes.
         build_api_tree(&mut root);
 
+        // Older versions of the api share the same routes, but are deprecated in favour 
+        // of the latest version. 
+        let deprecation = match version {
+            ApiVersion::V1 => Some(Deprecation {
+                deprecated: API_V1_DEPRECATED,
+                sunset: API_V1_SUNSET,
+            }),
+            ApiVersion::V2 => None,
+        };
+
         ApiTree {
+            version,
+            deprecation,
             tree: Box::new(root),
         }
 
     }
+
+    /// Lists every route registered on the tree as its versioned uri path and query,
+    /// with path variables shown as "{}".
+    pub fn routes(&self) -> Vec<(String, &Query)> {
+        let mut routes = Vec::new();
+        collect_routes(&self.tree, format!("/{}/{}", self.tree.value, self.version.segment()), &mut routes);
+        routes
+    }
+}
+
+fn collect_routes<'a>(path_seg: &'a PathSegment, path: String, routes: &mut Vec<(String, &'a Query)>) {
+    for query in &path_seg.queries {
+        routes.push((path.clone(), query));
+    }
+    for child in &path_seg.children_segments {
+        collect_routes(child, format!("{}/{}", path, child.value), routes);
+    }
 }
 
 
@@ -30,20 +118,31 @@ fn build_api_tree(path_seg_root: &mut PathSegment)  {
    
     // **GET /api/suppliers query/branch
     let suppliers = path_seg_root.child_seg_by_value(String::from("suppliers"));
-    suppliers.query = Some(Query::GETSuppliers);
+    suppliers.add_query(Query::GETSuppliers(CollectionParams::default()));
+
+    // **POST /api/suppliers query
+    suppliers.add_query(Query::POSTSupplier(Content::None));
+
+        // **POST /api/suppliers/batch query
+        let suppliers_batch = suppliers.child_seg_by_value(String::from("batch"));
+        suppliers_batch.add_query(Query::POSTSuppliersBatch(BatchMode::Atomic));
 
         // **GET /api/suppliers/email query
         let suppliers_email = suppliers.child_seg_by_value(String::from("email"));
-        suppliers_email.query = Some(Query::GETSuppliersEmail);
+        suppliers_email.add_query(Query::GETSuppliersEmail(CollectionParams::default()));
 
         // **GET /api/suppliers/numbers query
         let suppliers_numbers = suppliers.child_seg_by_value(String::from("numbers"));
-        suppliers_numbers.query = Some(Query::GETSuppliersNumbers);
+        suppliers_numbers.add_query(Query::GETSuppliersNumbers(CollectionParams::default()));
 
         let suppliers_categories = suppliers.child_seg_by_value(String::from("categories"));
-        suppliers_categories.query = Some(Query::GETSuppliersCategories);
+        suppliers_categories.add_query(Query::GETSuppliersCategories);
 
     
+    // **GET /api/search query
+    let search = path_seg_root.child_seg_by_value(String::from("search"));
+    search.add_query(Query::GETSearch(SearchParams { text: String::new(), limit: 0 }));
+
     // GET /api/supplier branch
     let supplier = path_seg_root.child_seg_by_value(String::from("supplier"));
 
@@ -52,27 +151,43 @@ fn build_api_tree(path_seg_root: &mut PathSegment)  {
             
             // GET /api/supplier/id/{name} query
             let supplier_id_name = supplier_id_seg.child_seg_by_value(String::from("{}"));
-                supplier_id_name.query = Some(Query::GETSupplierIdFromName(String::from("{}")));
+                supplier_id_name.add_query(Query::GETSupplierIdFromName(String::from("{}")));
 
         // GET /api/supplier/{id} query/branch
         let supplier_id = supplier.child_seg_by_value(String::from("{}"));
-            supplier_id.query = Some(Query::GETSupplierFromId(0));
+            supplier_id.add_query(Query::GETSupplierFromId(0, FieldSelection::default()));
+
+            // PATCH /api/supplier/{id} query
+            supplier_id.add_query(Query::PATCHSupplier(0));
+
+            // PUT /api/supplier/{id} query
+            supplier_id.add_query(Query::PUTSupplier(0));
+
+            // DELETE /api/supplier/{id} query
+            supplier_id.add_query(Query::DELETESupplier(0, DeleteMode::Soft));
+
+            // POST /api/supplier/{id}/restore query
+            let supplier_id_restore = supplier_id.child_seg_by_value(String::from("restore"));
+                supplier_id_restore.add_query(Query::POSTSupplierRestore(0));
             
             // GET /api/supplier/{id}/name query
             let supplier_id_name = supplier_id.child_seg_by_value(String::from("name"));
-                supplier_id_name.query = Some(Query::GETSupplierNameFromId(0));
+                supplier_id_name.add_query(Query::GETSupplierNameFromId(0));
                 
             // GET /api/supplier/{id}/address query
             let supplier_id_address = supplier_id.child_seg_by_value(String::from("address"));
-                supplier_id_address.query = Some(Query::GETSupplierAddressFromId(0));
+                supplier_id_address.add_query(Query::GETSupplierAddressFromId(0));
+
+                // PUT /api/supplier/{id}/address query
+                supplier_id_address.add_query(Query::PUTSupplierAddress(0));
             
             // GET /api/supplier/{id}/rep query
             let supplier_id_rep = supplier_id.child_seg_by_value(String::from("rep"));
-                supplier_id_rep.query = Some(Query::GETSupplierRepFromId(0));
+                supplier_id_rep.add_query(Query::GETSupplierRepFromId(0));
 
             // GET /api/supplier/{id}/categories query
             let supplier_id_categories = supplier_id.child_seg_by_value(String::from("categories"));
-                supplier_id_categories.query = Some(Query::GETSupplierCategoriesFromId(0));
+                supplier_id_categories.add_query(Query::GETSupplierCategoriesFromId(0));
 
 
         // GET /api/supplier/rep branch
@@ -80,15 +195,18 @@ fn build_api_tree(path_seg_root: &mut PathSegment)  {
 
             // GET /api/supplier/rep/{id} query/branch
             let rep_id = supplier_rep.child_seg_by_value(String::from("{}"));
-                rep_id.query = Some(Query::GETSupplyRepFromId(0));
+                rep_id.add_query(Query::GETSupplyRepFromId(0));
+
+                // PUT /api/supplier/rep/{id} query
+                rep_id.add_query(Query::PUTSupplyRep(0));
 
                 // GET /api/supplier/rep/{id}/numbers query
                 let rep_id_numbers = rep_id.child_seg_by_value(String::from("numbers"));
-                    rep_id_numbers.query = Some(Query::GETSupplyRepPhoneNumbersFromId(0));
+                    rep_id_numbers.add_query(Query::GETSupplyRepPhoneNumbersFromId(0));
 
                 // GET /api/supplier/rep/{id}/email query
                 let rep_id_email = rep_id.child_seg_by_value(String::from("email"));
-                    rep_id_email.query = Some(Query::GETSupplyRepEmailFromId(0));
+                    rep_id_email.add_query(Query::GETSupplyRepEmailFromId(0));
              
                 
 
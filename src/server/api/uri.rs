@@ -0,0 +1,163 @@
+//! Parsing of request uris following RFC 3986.
+//!
+//! Percent encoded octets are decoded to bytes first and then read as UTF-8, so multi
+//! byte characters such as "é" ("%C3%A9") decode to a single character, and every octet
+//! is decoded exactly once ("%2525" decodes to "%25"). Badly formed escapes and bytes
+//! which are not valid UTF-8 are rejected.
+
+use crate::errors::ErrorType;
+
+// Splits a request target into its path and decoded query string pairs. The path is
+// checked but returned still encoded, see path_segments. Absolute form targets, e.g.
+// "http://host/api", have their scheme and authority removed and any fragment is ignored.
+pub fn split_request_target(target: &str) -> Result<(String, Vec<(String, String)>), ErrorType> {
+
+    let mut target = target.split('#').next().unwrap_or("");
+
+    for scheme in ["http://", "https://"] {
+        if let Some(rest) = target.strip_prefix(scheme) {
+            target = match rest.find('/') {
+                Some(index) => &rest[index..],
+                None => "/",
+            };
+        }
+    }
+
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+
+    if !path.starts_with('/') {
+        return Err(ErrorType::ParseError(format!("Request path must start with /: {}", path)));
+    }
+    // check the path can be decoded before handing it on
+    path_segments(path)?;
+
+    Ok((path.to_string(), parse_query_string(query)?))
+}
+
+// Splits a path into its decoded segments, the first segment is always the empty
+// segment before the leading "/". A single trailing "/" is ignored, so "/api/suppliers/"
+// is the same path as "/api/suppliers", but empty segments within the path are rejected.
+pub fn path_segments(path: &str) -> Result<Vec<String>, ErrorType> {
+    let mut raw_segments: Vec<&str> = path.split('/').collect();
+
+    if raw_segments.len() > 1 && raw_segments.last() == Some(&"") {
+        raw_segments.pop();
+    }
+
+    let mut segments = Vec::with_capacity(raw_segments.len());
+    for (i, segment) in raw_segments.iter().enumerate() {
+        if i > 0 && segment.is_empty() {
+            return Err(ErrorType::ParseError(format!("Empty segment in path: {}", path)));
+        }
+        segments.push(percent_decode(segment)?);
+    }
+    Ok(segments)
+}
+
+// Parses a query string into decoded key value pairs. The order of the pairs is kept,
+// keys without a value are given an empty string and "+" is read as a space.
+pub fn parse_query_string(query: &str) -> Result<Vec<(String, String)>, ErrorType> {
+    let mut params = Vec::new();
+    for pair in query.split('&') {
+        if pair.is_empty() {
+            continue;
+        }
+        let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+        params.push((decode_query_component(key)?, decode_query_component(value)?));
+    }
+    Ok(params)
+}
+
+// Decodes a key or value from a query string, where a literal "+" stands for a space
+pub fn decode_query_component(component: &str) -> Result<String, ErrorType> {
+    percent_decode(&component.replace('+', " "))
+}
+
+// Decodes the percent encoded octets of a uri component and reads the result as UTF-8
+pub fn percent_decode(component: &str) -> Result<String, ErrorType> {
+    let bytes = component.as_bytes();
+    let mut decoded: Vec<u8> = Vec::with_capacity(bytes.len());
+
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let octet = bytes.get(i + 1..i + 3)
+                .and_then(|hex| std::str::from_utf8(hex).ok())
+                .filter(|hex| hex.chars().all(|c| c.is_ascii_hexdigit()))
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+
+            match octet {
+                Some(octet) => decoded.push(octet),
+                None => return Err(ErrorType::ParseError(format!("Invalid percent encoding in: {}", component))),
+            }
+            i += 3;
+        }
+        else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+
+    String::from_utf8(decoded)
+        .map_err(|_| ErrorType::ParseError(format!("Percent encoding is not valid UTF-8 in: {}", component)))
+}
+
+// Percent encodes every byte apart from the unreserved characters of a uri
+pub fn percent_encode(component: &str) -> String {
+    let mut encoded = String::with_capacity(component.len());
+    for byte in component.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-._~".contains(&byte) {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    encoded
+}
+
+
+#[cfg(test)]
+mod test {
+    #[test]
+    fn test_percent_decode() {
+        let uri = "/api/supplier/1/Mr%20Smith%20%26%20Co%20Ltd/name";
+        let decoded = super::percent_decode(uri).unwrap();
+        assert_eq!(decoded, "/api/supplier/1/Mr Smith & Co Ltd/name");
+
+        assert_eq!(super::percent_decode("Caf%C3%A9%20Ltd").unwrap(), "Café Ltd");
+        assert_eq!(super::percent_decode("M%C3%BCller%20GmbH").unwrap(), "Müller GmbH");
+        assert_eq!(super::percent_decode("100%2525").unwrap(), "100%25");
+        assert_eq!(super::percent_decode("a+b").unwrap(), "a+b");
+
+        assert!(super::percent_decode("%C3").is_err());
+        assert!(super::percent_decode("%2").is_err());
+        assert!(super::percent_decode("%G1").is_err());
+    }
+
+    #[test]
+    fn test_percent_encode_round_trip() {
+        let name = "Müller & Söhne/O'Brien";
+        assert_eq!(super::percent_decode(&super::percent_encode(name)).unwrap(), name);
+    }
+
+    #[test]
+    fn test_split_request_target() {
+        let (path, params) = super::split_request_target("/api/suppliers/?name=Caf%C3%A9+Ltd&active=1#top").unwrap();
+        assert_eq!(path, "/api/suppliers/");
+        assert_eq!(params[0], ("name".to_string(), "Café Ltd".to_string()));
+        assert_eq!(params[1], ("active".to_string(), "1".to_string()));
+
+        let (path, _) = super::split_request_target("http://shop.local:7878/api/suppliers").unwrap();
+        assert_eq!(path, "/api/suppliers");
+
+        assert!(super::split_request_target("/api//suppliers").is_err());
+        assert!(super::split_request_target("/api/suppliers?name=%E9").is_err());
+    }
+
+    #[test]
+    fn test_path_segments() {
+        assert_eq!(super::path_segments("/api/suppliers/").unwrap(), vec!["", "api", "suppliers"]);
+        assert_eq!(super::path_segments("/api/supplier/id/A%2FB").unwrap(), vec!["", "api", "supplier", "id", "A/B"]);
+        assert_eq!(super::path_segments("/").unwrap(), vec![""]);
+    }
+}
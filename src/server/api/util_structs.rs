@@ -4,7 +4,7 @@ pub struct PathSegment {
     pub value: String,
     pub seg_number: u16,
     pub children_segments: Vec<Box<PathSegment>>,
-    pub query: Option<Query>,
+    pub queries: Vec<Query>,
 }
 
 impl PathSegment {
@@ -13,10 +13,23 @@ impl PathSegment {
             value,
             seg_number,
             children_segments: Vec::new(),
-            query: None,
+            queries: Vec::new(),
         }
     }
 
+    // A path can be requested with a different query for each http method
+    pub fn add_query(&mut self, query: Query) {
+        self.queries.push(query);
+    }
+
+    pub fn query_for_method(&self, method: &str) -> Option<&Query> {
+        self.queries.iter().find(|query| query.http_method() == method)
+    }
+
+    pub fn allowed_methods(&self) -> Vec<&'static str> {
+        self.queries.iter().map(|query| query.http_method()).collect()
+    }
+
     pub fn child_seg(&mut self, child: PathSegment) {
 
         self.children_segments.push(Box::new(child));
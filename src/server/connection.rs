@@ -4,36 +4,88 @@ use std::io::{BufReader, prelude::*};
 use json;
 use std::time::Duration;
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
 use crate::server::api::{
     uri_to_api_query, 
-    query_types::{Query, Content},
-    config::responses::{self, standard_json_response, standard_html_response},
+    uri::split_request_target,
+    openapi::openapi_document,
+    explorer::explorer_html,
+    query_types::{Query, Content, ApiVersion},
+    problem::Problem,
 };
-use crate::server::api::routing::ApiTree;
+use crate::server::api::routing::ApiVersions;
 use crate::server::process_query;
+use crate::server::databases::backend::Database;
+use crate::config::ServerConfig;
 
 use super::api::query_types;
 
 pub struct Request {
     pub method: String,
+    pub target: String,
     pub path: String,
+    pub query_params: Vec<(String, String)>,
     pub http_version: String,
     pub headers: HashMap<String, String>,
     pub body: query_types::Content,
+    pub api_version: ApiVersion,
+    pub request_id: String,
+}
+
+impl Request {
+    // header names are case insensitive, so look the header up ignoring case
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
 }
 
 
-pub fn connection(mut stream: TcpStream, api_tree: &mut Box<ApiTree>) {
+pub fn connection(mut stream: TcpStream, api_versions: &ApiVersions, config: &ServerConfig, database: Option<&Database>) {
     
 
     let some_request = stream_to_request(&mut stream);
     if some_request.is_none() {
         return;
     }
-    let the_request = some_request.unwrap();
+    let mut the_request = some_request.unwrap();
+    let request_id = the_request.request_id.clone();
+
+    // split the request target into its path and query string, a target which can not 
+    // be decoded is a bad request
+    let mut target_error: Option<Problem> = None;
+    match split_request_target(&the_request.target) {
+        Ok((path, query_params)) => {
+            the_request.path = path;
+            the_request.query_params = query_params;
+        },
+        Err(e) => {
+            the_request.path = the_request.target.split('?').next().unwrap_or("").to_string();
+            target_error = Some(Problem::from_error_type(&e));
+        }
+    };
 
+    // the route tree, and so the shape of the response, depends on the version of the api requested
+    let api_tree = api_versions.for_path(&the_request.path);
+    the_request.api_version = api_tree.version;
 
-    let some_query = uri_to_api_query(&the_request.path, api_tree);
+    let some_query = if target_error.is_none() {
+        uri_to_api_query(&the_request.path, &the_request.method, &the_request.query_params, api_tree)
+    } else {
+        Some(Query::ApiInvalidUri)
+    };
+
+    // every response carries the id of the request, so it can be matched to the server log
+    let mut response_headers: Vec<(String, String)> = vec![(String::from("X-Request-Id"), request_id.clone())];
+
+    // routes of a deprecated version of the api tell the client when the version will be removed
+    if let Some(deprecation) = &api_tree.deprecation {
+        if !matches!(some_query, None | Some(Query::NoneApi)) {
+            response_headers.append(&mut deprecation.headers(&the_request.path, api_tree.version));
+        }
+    }
 
 
     // if for whatever reason the request is empty then simply exit the function
@@ -46,62 +98,86 @@ pub fn connection(mut stream: TcpStream, api_tree: &mut Box<ApiTree>) {
     // Valid and Invalid API URIs will respond in a json content type  
     match some_query {
         Some(Query::ApiDoc) => {
+            response_content_type = String::from("application/json");
+            response_content = openapi_document(api_tree).dump();
+            response_status_line = "HTTP/1.1 200 OK".to_string();
+        },
+        Some(Query::ApiExplorer) => {
             response_content_type = String::from("text/html");
-            response_content = String::from("Api Docs");
+            response_content = explorer_html(api_tree);
             response_status_line = "HTTP/1.1 200 OK".to_string();
         },
-        Some(Query::NoneApi) => {
+        Some(Query::ApiInvalidUri) => {
+            let problem = target_error.unwrap_or(Problem::new(400, "The request uri is not a valid api uri"));
+            (
+                response_content, 
+                response_content_type, 
+                response_status_line
+            ) = problem.response(&the_request.path, &request_id);
+        },
+        Some(Query::ApiMethodNotAllowed(ref methods)) => {
+            let detail = format!("The {} method can not be used with this resource", the_request.method);
+            response_headers.push((String::from("Allow"), methods.join(", ")));
+            (
+                response_content, 
+                response_content_type, 
+                response_status_line
+            ) = Problem::new(405, &detail).response(&the_request.path, &request_id);
+        },
+        Some(Query::NoneApi) | None => {
             // TODO: implement some sort of routing for none api requests
             (
                 response_content, 
                 response_content_type, 
                 response_status_line
-            ) = standard_json_response(responses::JSON_RESOURCE_NOT_FOUND);
-
+            ) = Problem::new(404, "No resource exists at the requested path").response(&the_request.path, &request_id);
         },
-        Some(Query::ApiInvalidUri) => {
-    
+        // the remaining queries are answered from the database, which may not have opened
+        Some(_) if database.is_none() => {
             (
                 response_content, 
                 response_content_type, 
                 response_status_line
-            ) = standard_json_response(responses::JSON_BAD_REQUEST);
+            ) = Problem::new(503, "The database is not available").response(&the_request.path, &request_id);
         },
         Some(_) => {
+            let path = the_request.path.clone();
+            let database = database.unwrap();
             (
                 response_content, 
                 response_content_type, 
                 response_status_line
             ) =  match the_request.method.as_str() {
                 "GET" => {
-                    process_query::get_request(some_query.unwrap(), the_request)
+                    process_query::get_request(some_query.unwrap(), the_request, &mut response_headers, database)
                 },
                 "POST" => {
-                    process_query::post_request(some_query.unwrap(), the_request)
+                    process_query::post_request(some_query.unwrap(), the_request, &mut response_headers, database)
+                },
+                "PATCH" => {
+                    process_query::patch_request(some_query.unwrap(), the_request, &mut response_headers, database)
                 },
                 "PUT" => {
-                    process_query::get_request(some_query.unwrap(), the_request)
+                    process_query::put_request(some_query.unwrap(), the_request, &mut response_headers, database)
                 },
                 "DELETE" => {
-                    process_query::delete_request(some_query.unwrap(), the_request)
+                    process_query::delete_request(some_query.unwrap(), the_request, &mut response_headers, config, database)
+                },
+                method => {
+                    let detail = format!("The {} method is not supported", method);
+                    Problem::new(405, &detail).response(&path, &request_id)
                 },
-                _ => standard_json_response(responses::JSON_BAD_REQUEST),
             }
 
         },
-        None => {
-            (
-                response_content, 
-                response_content_type, 
-                response_status_line
-            ) = standard_html_response(responses::HTML_NOT_FOUND);
-        }
         
     }
     
     // create the response
-    let content_length = format!("Content-Length: {}", response_content.len());
-    let headers = format!("Content-Type: {response_content_type}; charset=UTF-8; {}", content_length);
+    let mut headers = format!("Content-Type: {response_content_type}; charset=UTF-8\r\nContent-Length: {}", response_content.len());
+    for (key, value) in response_headers {
+        headers.push_str(&format!("\r\n{}: {}", key, value));
+    }
     let response = format!("{response_status_line}\r\n{headers}\r\n\r\n{response_content}");
  
     // send the response back to the client
@@ -115,10 +191,14 @@ fn stream_to_request(stream: &mut TcpStream) -> Option<Request> {
     // create a struct to hold the request information
     let mut request_struct = Request {
         method: String::new(),
+        target: String::new(),
         path: String::new(),
+        query_params: Vec::new(),
         http_version: String::new(),
         headers: HashMap::new(),
         body: Content::None,
+        api_version: ApiVersion::LATEST,
+        request_id: String::new(),
     };
 
 
@@ -133,8 +213,9 @@ fn stream_to_request(stream: &mut TcpStream) -> Option<Request> {
     }
     //println!("buffer_string: {}", buffer_string);
 
-    // look for the empty line in an http request and split the request into two parts, the header and the body
-    let request_vec = buffer_string.split_once("\n\n");
+    // look for the empty line in an http request and split the request into two parts, the header and the body,
+    // http uses CRLF line endings but a bare LF is also accepted
+    let request_vec = buffer_string.split_once("\r\n\r\n").or_else(|| buffer_string.split_once("\n\n"));
 
     // sometimes request will have a body, so split the request into two parts, the header and the body
     // if needed.
@@ -155,16 +236,20 @@ fn stream_to_request(stream: &mut TcpStream) -> Option<Request> {
     }
 
     // First get the start line of the request as it is in a different format to the rest of the header
-    let header_section: Vec<String> = header_section.split("\r\n").map(|s| s.to_string()).collect();
+    let header_section: Vec<String> = header_section.lines().map(|s| s.to_string()).collect();
 
 
     // get the request method, path and http version from the start line
     let start_line = header_section[0].clone();
   
     let start_line_parts: Vec<&str> = start_line.split(" ").collect();
+    if start_line_parts.len() != 3 {
+        println!("Error: Malformed request line: {}", start_line);
+        return None;
+    }
   
     request_struct.method = start_line_parts[0].trim().to_string();
-    request_struct.path = start_line_parts[1].trim().to_string();
+    request_struct.target = start_line_parts[1].trim().to_string();
     request_struct.http_version = start_line_parts[2].trim().to_string();
 
 
@@ -179,30 +264,31 @@ fn stream_to_request(stream: &mut TcpStream) -> Option<Request> {
         if header == "" {
             break;
         }
-        let header_parts = header.split_once(":").unwrap();
+        let header_parts = match header.split_once(":") {
+            Some(header_parts) => header_parts,
+            None => continue,
+        };
         let key = header_parts.0.trim().to_string();
         let value = header_parts.1.trim().to_string();
         
         headers.insert(key, value);
     }
     request_struct.headers = headers;
+    request_struct.request_id = request_id(request_struct.header("X-Request-Id"));
  
 
 
-    if let Some(content_type) = request_struct.headers.get("Content-Type") {
-        match content_type.as_str() {
-            "application/json" => {
-                let parsed = json::parse(&body_section);
-                if let Ok(parsed) = parsed {
-                    request_struct.body = Content::Json(parsed);
-                }
-                else {
-                    request_struct.body = Content::None;
-                    println!("Error: Failed to parse json body");
-
-                }
-            },
-            _ => {}
+    if let Some(content_type) = request_struct.header("Content-Type") {
+        if is_json_media_type(content_type) {
+            let parsed = json::parse(&body_section);
+            if let Ok(parsed) = parsed {
+                request_struct.body = Content::Json(parsed);
+            }
+            else {
+                request_struct.body = Content::None;
+                println!("Error: Failed to parse json body");
+
+            }
         }
     }
 
@@ -210,5 +296,24 @@ fn stream_to_request(stream: &mut TcpStream) -> Option<Request> {
 
 }
 
+// Checks the media type of a Content-Type header is json, ignoring any parameters such as the charset. 
+// Types with a "+json" suffix, such as "application/merge-patch+json", are also json.
+pub fn is_json_media_type(content_type: &str) -> bool {
+    let media_type = content_type.split(';').next().unwrap_or("").trim().to_ascii_lowercase();
+    media_type == "application/json" || (media_type.starts_with("application/") && media_type.ends_with("+json"))
+}
 
+// A request id given by the client is kept, as long as it is short and printable, otherwise
+// a new id is made from the time the request was received and a count of the requests.
+fn request_id(client_id: Option<&str>) -> String {
+    static REQUEST_COUNT: AtomicU64 = AtomicU64::new(0);
 
+    if let Some(id) = client_id {
+        if !id.is_empty() && id.len() <= 64 && id.chars().all(|c| c.is_ascii_graphic()) {
+            return id.to_string();
+        }
+    }
+    let time = SystemTime::now().duration_since(UNIX_EPOCH).map(|x| x.as_millis()).unwrap_or(0);
+    let count = REQUEST_COUNT.fetch_add(1, Ordering::Relaxed);
+    format!("{:x}-{:04x}", time, count)
+}
@@ -1,3 +1,24 @@
 pub mod sqlite;
+pub mod postgres;
+pub mod backend;
+pub mod pool;
 pub mod data_structs;
+pub mod rows;
+pub mod records;
+pub mod schema_check;
 pub mod config;
+pub mod util;
+pub mod tables;
+pub mod requests;
+pub mod migrations;
+pub mod get_processing;
+pub mod get_sql_queries;
+pub mod post_sql_queries;
+pub mod post_processing;
+pub mod patch_processing;
+pub mod put_processing;
+pub mod delete_processing;
+pub mod search;
+pub mod expansion;
+pub mod idempotency;
+pub mod versions;
@@ -0,0 +1,213 @@
+use crate::errors::DatabaseError;
+use crate::config::{DATABASE_VAR, POSTGRES_URL_VAR, POSTGRES_POOL_SIZE, SQLITE_DB_PATH, SQLITE_POOL_SIZE};
+use crate::server::databases::{
+    data_structs::{DBTable, DBTableStruct, DbFieldStruct, DatabaseColumn, Type, Value},
+    pool::Pool,
+    postgres::{self, PostgresConnection},
+    sqlite::{self, pool::SqliteConnection},
+};
+
+///
+/// A connection to one of the databases the server can use. Statements are written with "?"
+/// parameters, which a backend binds in order to the values given with the statement, and
+/// rows are read into a DBTable using the structure given with the query.
+///
+pub trait Backend {
+    /// The type of database, for the few statements which are written differently for each
+    fn database_type(&self) -> Type;
+
+    /// Runs a statement, reading the rows it returns into a table of the given structure
+    fn query(&self, sql: &str, values: &[Value], structure: DBTableStruct) -> Result<DBTable, DatabaseError>;
+
+    /// Runs a statement, any rows it returns are left unread
+    fn execute(&self, sql: &str, values: &[Value]) -> Result<(), DatabaseError>;
+
+    /// Runs one or more statements which take no values, such as a migration
+    fn execute_batch(&self, sql: &str) -> Result<(), DatabaseError>;
+
+    /// Begins a transaction, see transaction
+    fn begin(&self) -> Result<(), DatabaseError>;
+
+    fn commit(&self) -> Result<(), DatabaseError>;
+
+    fn rollback(&self) -> Result<(), DatabaseError>;
+
+    /// The columns of a table or view, in order, none are given if there is no such table or view
+    fn columns(&self, source: &str) -> Result<Vec<DatabaseColumn>, DatabaseError>;
+
+    ///
+    /// Runs a statement and returns the first row it gives, if any. The cells of the row hold
+    /// the types of `types`, in order, a cell which is null is Value::Null.
+    ///
+    fn query_row(&self, sql: &str, values: &[Value], types: &[Value]) -> Result<Option<Vec<Value>>, DatabaseError> {
+        let mut structure = DBTableStruct::new();
+        for (i, field_type) in types.iter().enumerate() {
+            structure.fields.push(DbFieldStruct::new(i, &i.to_string(), field_type.clone(), false));
+        }
+        let table = self.query(sql, values, structure)?;
+        Ok(table.rows.into_iter().next().map(|row| row.cells))
+    }
+}
+
+/// The structure of the rows describing the columns of a table or view, its name, declared type and whether it is not null
+pub fn column_structure() -> DBTableStruct {
+    let mut structure = DBTableStruct::new();
+    structure.fields.push(DbFieldStruct::new(0, "name", Value::String(String::new()), true));
+    structure.fields.push(DbFieldStruct::new(1, "type", Value::String(String::new()), false));
+    structure.fields.push(DbFieldStruct::new(2, "notNull", Value::Boolean(false), true));
+    structure
+}
+
+///
+/// The database the server uses, along with the pool of connections to it. The server opens
+/// it as it starts and hands it to each request, and a test can open one of its own, e.g. a
+/// SQLite database held in memory.
+///
+pub enum Database {
+    Sqlite(Pool<SqliteConnection>),
+    Postgres(Pool<PostgresConnection>),
+}
+
+impl Database {
+    ///
+    /// Opens the database named by POS_DATABASE, SQLite unless it is set to "postgres", with a
+    /// pool of `size` connections, or the default size of the pool if it is not given. The
+    /// connection string of a PostgreSQL database is held by POS_POSTGRES_URL.
+    ///
+    pub fn open(size: Option<usize>) -> Result<Database, DatabaseError> {
+        match std::env::var(DATABASE_VAR).map(|name| name.trim().to_lowercase()) {
+            Ok(name) if name == "postgres" || name == "postgresql" => {
+                let url = std::env::var(POSTGRES_URL_VAR)
+                    .map_err(|_| DatabaseError::ConnectionError(format!("{} must hold the connection string of the database", POSTGRES_URL_VAR)))?;
+                Database::postgres(&url, size.unwrap_or(POSTGRES_POOL_SIZE))
+            },
+            Ok(name) if !name.is_empty() && name != "sqlite" => {
+                Err(DatabaseError::ConnectionError(format!("{} must be sqlite or postgres, not {}", DATABASE_VAR, name)))
+            },
+            _ => Database::sqlite(SQLITE_DB_PATH, size.unwrap_or(SQLITE_POOL_SIZE)),
+        }
+    }
+
+    /// Opens a SQLite database. Each connection to ":memory:" has a database of its own, so a
+    /// database held in memory is opened with a pool of one connection.
+    pub fn sqlite(database_path: &str, size: usize) -> Result<Database, DatabaseError> {
+        Ok(Database::Sqlite(sqlite::pool::open(database_path, size)?))
+    }
+
+    pub fn postgres(url: &str, size: usize) -> Result<Database, DatabaseError> {
+        Ok(Database::Postgres(postgres::open(url, size)?))
+    }
+
+    /// Borrows a connection to the database, which is used by `body` and given back once `body` returns
+    pub fn connect<T, F>(&self, body: F) -> Result<T, DatabaseError>
+    where
+        F: FnOnce(&dyn Backend) -> Result<T, DatabaseError>,
+    {
+        match self {
+            Database::Sqlite(pool) => {
+                let pooled = pool.get()?;
+                body(&*pooled)
+            },
+            Database::Postgres(pool) => {
+                let pooled = pool.get()?;
+                body(&*pooled)
+            },
+        }
+    }
+}
+
+///
+/// Runs `body` within a transaction, which is committed if `body` succeeds and rolled back
+/// if it fails. Rows read within the transaction can not be changed by another connection
+/// before they are written, and foreign keys are checked as the transaction commits, so rows
+/// can be removed within it in any order.
+///
+pub fn transaction<T, F>(connection: &dyn Backend, body: F) -> Result<T, DatabaseError>
+where
+    F: FnOnce() -> Result<T, DatabaseError>,
+{
+    connection.begin()?;
+    match body().and_then(|result| connection.commit().map(|_| result)) {
+        Ok(result) => Ok(result),
+        Err(error) => {
+            let _ = connection.rollback();
+            Err(error)
+        }
+    }
+}
+
+///
+/// Runs `body` within a savepoint, which is released if `body` succeeds and rolled back to if
+/// it fails, leaving nothing `body` did behind. Savepoints nest, so a helper which makes several
+/// changes can use one whether it is called directly within a transaction or within another savepoint.
+///
+pub fn savepoint<T, F>(connection: &dyn Backend, name: &'static str, body: F) -> Result<T, DatabaseError>
+where
+    F: FnOnce() -> Result<T, DatabaseError>,
+{
+    let execute = |sql: String| connection.execute_batch(&sql)
+        .map_err(|_| DatabaseError::QueryError(format!("Failed to execute: {}", sql)));
+
+    execute(format!("SAVEPOINT {}", name))?;
+    match body() {
+        Ok(result) => {
+            execute(format!("RELEASE SAVEPOINT {}", name))?;
+            Ok(result)
+        },
+        Err(error) => {
+            let _ = execute(format!("ROLLBACK TO SAVEPOINT {}", name));
+            let _ = execute(format!("RELEASE SAVEPOINT {}", name));
+            Err(error)
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use crate::errors::DatabaseError;
+    use crate::server::databases::{data_structs::{DBTableStruct, DbFieldStruct, Value}, sqlite::pool::SqliteConnection};
+    use super::Backend;
+
+    fn names(connection: &dyn Backend) -> Vec<String> {
+        let mut structure = DBTableStruct::new();
+        structure.fields.push(DbFieldStruct::new(0, "name", Value::String(String::new()), true));
+        let table = connection.query("SELECT name FROM supplier ORDER BY id", &[], structure).unwrap();
+        table.rows.iter().map(|row| row.cells[0].to_text()).collect()
+    }
+
+    #[test]
+    fn test_savepoint_rolls_back_only_its_own_changes() {
+        let connection = SqliteConnection::new(sqlite::open(":memory:").unwrap());
+        connection.execute_batch("CREATE TABLE supplier (id INTEGER PRIMARY KEY, name TEXT)").unwrap();
+        let insert = |name: &str| connection.execute("INSERT INTO supplier (name) VALUES (?)", &[Value::String(name.to_string())])
+            .map_err(|_| DatabaseError::QueryError("insert failed".to_string()));
+
+        let result = super::transaction(&connection, || {
+            insert("kept")?;
+            let failed: Result<(), DatabaseError> = super::savepoint(&connection, "outer", || {
+                insert("dropped")?;
+                super::savepoint(&connection, "inner", || insert("also dropped"))?;
+                Err(DatabaseError::SubmissionError("failed part way".to_string()))
+            });
+            assert!(failed.is_err());
+            super::savepoint(&connection, "outer", || insert("released"))
+        });
+        assert!(result.is_ok());
+        assert_eq!(names(&connection), vec!["kept", "released"]);
+
+        // a failure within the transaction leaves nothing behind
+        let result: Result<(), DatabaseError> = super::transaction(&connection, || {
+            super::savepoint(&connection, "item", || insert("rolled back"))?;
+            Err(DatabaseError::SubmissionError("failed".to_string()))
+        });
+        assert!(result.is_err());
+        assert_eq!(names(&connection), vec!["kept", "released"]);
+
+        // the first row of a query, if there is one
+        let row = connection.query_row("SELECT id, name FROM supplier WHERE name = ?", &[Value::String("released".to_string())],
+            &[Value::Integer(0), Value::String(String::new())]).unwrap();
+        assert_eq!(row, Some(vec![Value::Integer(2), Value::String("released".to_string())]));
+        assert_eq!(connection.query_row("SELECT id FROM supplier WHERE id = 10", &[], &[Value::Integer(0)]).unwrap(), None);
+    }
+}
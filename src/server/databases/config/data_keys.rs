@@ -19,5 +19,9 @@ pub const TITLE: &str = "title";
 pub const FIRST_NAME: &str = "firstName";
 pub const LAST_NAME: &str = "lastName";
 pub const CATEGORY_TYPE: &str = "category";
+pub const SUPPLIER_ID: &str = "supplierId";
+pub const SEARCH_TYPE: &str = "type";
+pub const SEARCH_TEXT: &str = "text";
+pub const SEARCH_RANK: &str = "rank";
 
 
@@ -1,12 +1,14 @@
 use json::{self, JsonValue};
 
 /// Provides a database type selection
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Type {
     Sqlite,
     Postgres,
 }
 
 /// Represents a cell value for a relational database
+#[derive(Debug, PartialEq)]
 pub enum Value {
     Boolean(bool),
     Binary(Vec<u8>),
@@ -28,10 +30,34 @@ impl Value {
             
         }
     }
-    
+
+    /// The value as plain text, binary values are written as hex and null is empty
+    pub fn to_text(&self) -> String {
+        match self {
+            Value::Boolean(value) => value.to_string(),
+            Value::Binary(value) => value.iter().map(|byte| format!("{:02x}", byte)).collect(),
+            Value::Float(value) => value.to_string(),
+            Value::Integer(value) => value.to_string(),
+            Value::String(value) => value.clone(),
+            Value::Null => String::new(),
+        }
+    }
 
 }
 
+impl Clone for Value {
+    fn clone(&self) -> Value {
+        match self {
+            Value::Boolean(b) => Value::Boolean(*b),
+            Value::Binary(b) => Value::Binary(b.clone()),
+            Value::Float(f) => Value::Float(*f),
+            Value::Integer(i) => Value::Integer(*i),
+            Value::String(s) => Value::String(s.clone()),
+            Value::Null => Value::Null,
+        }
+    }
+}
+
 /// Represents the characteristics of a field/column in a relational database
 pub struct DbFieldStruct {
     pub index: usize,
@@ -136,6 +162,23 @@ impl DBTable {
         self.rows.remove(index);
     }
 
+    ///
+    /// Keeps only the named fields, and their cells in each row. Naming no fields keeps all of them.
+    /// 
+    pub fn select_fields(&mut self, names: &[String]) {
+        if names.is_empty() {
+            return;
+        }
+        let keep: Vec<bool> = self.structure.fields.iter().map(|field| names.contains(&field.name)).collect();
+
+        let mut kept = keep.iter();
+        self.structure.fields.retain(|_| *kept.next().unwrap());
+        for row in self.rows.iter_mut() {
+            let mut kept = keep.iter();
+            row.cells.retain(|_| *kept.next().unwrap_or(&false));
+        }
+    }
+
     ///
     /// Converts the table to a json array
     /// 
@@ -166,13 +209,79 @@ impl DBTable {
     
         json_table
     }
+
+    ///
+    /// Converts the table to RFC 4180 csv, the first record is a header holding the
+    /// field names
+    /// 
+    pub fn to_csv(&self) -> String {
+        let header: Vec<String> = self.structure.fields.iter()
+            .map(|field| csv_field(&field.name))
+            .collect();
+        let mut csv = header.join(",");
+        csv.push_str("\r\n");
+
+        for row in &self.rows {
+            let record: Vec<String> = row.cells.iter()
+                .map(|cell| csv_field(&cell.to_text()))
+                .collect();
+            csv.push_str(&record.join(","));
+            csv.push_str("\r\n");
+        }
+        csv
+    }
+
+    ///
+    /// Converts the table to an xml document, each row is an element named `row_name`
+    /// within the `root_name` element, holding an element for each field. Null values
+    /// are left as empty elements.
+    /// 
+    pub fn to_xml(&self, root_name: &str, row_name: &str) -> String {
+        let mut xml = format!("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<{}>\n", root_name);
+
+        for row in &self.rows {
+            xml.push_str(&format!("  <{}>\n", row_name));
+            for (field, cell) in self.structure.fields.iter().zip(row.cells.iter()) {
+                match cell {
+                    Value::Null => xml.push_str(&format!("    <{}/>\n", field.name)),
+                    _ => xml.push_str(&format!("    <{0}>{1}</{0}>\n", field.name, xml_escape(&cell.to_text()))),
+                }
+            }
+            xml.push_str(&format!("  </{}>\n", row_name));
+        }
+        xml.push_str(&format!("</{}>\n", root_name));
+        xml
+    }
     
 }
 
+// fields holding a comma, quote or line break are quoted, with any quotes doubled
+fn csv_field(text: &str) -> String {
+    if text.contains([',', '"', '\r', '\n']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+fn xml_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
 pub enum JsonStructType {
     Table,
     Object,
-    TableColumn(usize)
 }
 
 // Uses the data held within a DBTable and returns a json object with a data structure of the stated type.
@@ -192,13 +301,143 @@ pub fn table_to_json_struct(table: &DBTable, json_type: JsonStructType) -> JsonV
                 return JsonValue::Null;
             }
         }
-        JsonStructType::TableColumn(column_index) => {
-            let mut temp_json = JsonValue::new_array();
-            for row in table.rows.iter() {
-                let temp = row.cells[column_index].to_json();
-                temp_json.push(temp).expect("Error adding column to json object");
-            }
-            return temp_json;
+    }
+}
+#[cfg(test)]
+mod test {
+    use super::{DBTable, DBTableStruct, DBTableRow, DbFieldStruct, Value};
+
+    #[test]
+    fn test_table_to_csv_and_xml() {
+        let mut structure = DBTableStruct::new();
+        structure.fields.push(DbFieldStruct::new(0, "id", Value::Integer(0), true));
+        structure.fields.push(DbFieldStruct::new(1, "name", Value::String(String::new()), true));
+        structure.fields.push(DbFieldStruct::new(2, "addressId", Value::Integer(0), false));
+
+        let mut table = DBTable::new(&structure);
+        let mut row = DBTableRow::new();
+        row.add_cell(Value::Integer(1));
+        row.add_cell(Value::String("Smith, \"Jones\" & Co".to_string()));
+        row.add_cell(Value::Null);
+        table.add_row(row);
+
+        assert_eq!(table.to_csv(), "id,name,addressId\r\n1,\"Smith, \"\"Jones\"\" & Co\",\r\n");
+
+        let xml = table.to_xml("suppliers", "supplier");
+        assert!(xml.contains("<name>Smith, &quot;Jones&quot; &amp; Co</name>"));
+        assert!(xml.contains("<addressId/>"));
+    }
+}
+
+/// How a column is given its value as a row is added to its table
+#[derive(Debug, PartialEq)]
+pub enum Insert {
+    /// The value must be given with the row
+    Given,
+    /// The value may be given with the row, as a value of this type, or left to the database default
+    Defaulted(Value),
+    /// The value is generated by the database, or set once the row it refers to has been added
+    Generated,
+}
+
+/// Declares a column of a table or view, and the json key its value is given under
+#[derive(Debug)]
+pub struct Column {
+    pub key: &'static str,
+    pub name: &'static str,
+    pub field_type: Value,
+    pub not_null: bool,
+    pub insert: Insert,
+    pub lookup: Option<&'static str>,
+}
+
+impl Column {
+    pub const fn new(key: &'static str, name: &'static str, field_type: Value, not_null: bool, insert: Insert) -> Column {
+        Column { key, name, field_type, not_null, insert, lookup: None }
+    }
+
+    /// A column holding the id of the row of `table` its text is looked up in, rather than the text itself
+    pub const fn looked_up(key: &'static str, name: &'static str, table: &'static str, not_null: bool, insert: Insert) -> Column {
+        Column { key, name, field_type: Value::String(String::new()), not_null, insert, lookup: Some(table) }
+    }
+}
+
+/// Where the rows of a schema are read from
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Source {
+    Table(&'static str),
+    View(&'static str),
+    /// The rows are put together by a query rather than read from a table or view
+    Query,
+}
+
+impl Source {
+    /// The name of the table or view
+    pub fn name(&self) -> Option<&'static str> {
+        match self {
+            Source::Table(name) | Source::View(name) => Some(name),
+            Source::Query => None,
         }
     }
-}
\ No newline at end of file
+}
+
+/// A column of a table or view as the database holds it, with the type its values are read as
+#[derive(Debug, Clone, PartialEq)]
+pub struct DatabaseColumn {
+    pub name: String,
+    pub declared_type: String,
+    pub field_type: Value,
+    pub not_null: bool,
+}
+
+///
+/// Declares the columns of a table or view, in the order they are selected. The table structures
+/// used to read rows and check request bodies are made from it, so the columns sent with a new
+/// row are picked by how they are inserted rather than by their position.
+///
+#[derive(Debug)]
+pub struct Schema {
+    pub source: Source,
+    pub columns: &'static [Column],
+}
+
+impl Schema {
+    /// The structure of a row holding every column
+    pub fn select(&self) -> DBTableStruct {
+        self.structure(self.columns.iter().map(|column| (column, column.field_type.clone(), column.not_null)))
+    }
+
+    /// The structure of a row holding the named columns, in the order they are named
+    pub fn select_keys(&self, keys: &[&str]) -> DBTableStruct {
+        self.structure(keys.iter()
+            .map(|key| self.column(key).unwrap_or_else(|| panic!("No column with the key {}", key)))
+            .map(|column| (column, column.field_type.clone(), column.not_null)))
+    }
+
+    /// The structure of the values which may be sent to add a row, those with a default may be left out
+    pub fn insert(&self) -> DBTableStruct {
+        self.structure(self.columns.iter().filter_map(|column| match &column.insert {
+            Insert::Given => Some((column, column.field_type.clone(), column.not_null)),
+            Insert::Defaulted(field_type) => Some((column, field_type.clone(), false)),
+            Insert::Generated => None,
+        }))
+    }
+
+    /// The column with the given json key
+    pub fn column(&self, key: &str) -> Option<&'static Column> {
+        self.columns.iter().find(|column| column.key == key)
+    }
+
+    /// The names of every column, as written in a select statement
+    pub fn column_list(&self) -> String {
+        self.columns.iter().map(|column| column.name).collect::<Vec<&str>>().join(", ")
+    }
+
+    fn structure<'a>(&self, fields: impl Iterator<Item = (&'a Column, Value, bool)>) -> DBTableStruct {
+        let mut table = DBTableStruct::new();
+        for (index, (column, field_type, not_null)) in fields.enumerate() {
+            table.fields.push(DbFieldStruct::new(index, column.key, field_type, not_null));
+        }
+        table
+    }
+}
@@ -0,0 +1,80 @@
+use crate::errors::DatabaseError;
+use crate::server::api::query_types::{Query, ApiVersion, DeleteMode, FieldSelection};
+use crate::server::databases::data_structs::Value;
+
+use json::JsonValue;
+use crate::server::databases::backend::{Database, Backend, transaction};
+use crate::server::databases::{
+    get_processing,
+    util::{supplier_links, execute_with_values, remove_unused_address, remove_unused_contact, remove_unused_rep},
+    versions::{check_precondition, bump_supplier},
+};
+
+///
+/// Deletes or restores a resource. A soft deleted supplier is marked as inactive and can be
+/// restored, while a purged supplier is removed from the database along with its address,
+/// contact details and rep, where no other supplier makes use of them. `if_match` must hold the
+/// supplier's current ETag, see versions::check_precondition.
+///
+pub fn process_query(query: Query, version: ApiVersion, if_match: Option<&str>, database: &Database) -> Result<JsonValue, DatabaseError> {
+    database.connect(|connection| {
+        match query {
+            Query::DELETESupplier(id, mode) => {
+                let id = id as i64;
+                transaction(connection, || {
+                    // a supplier which does not exist can not be deleted
+                    supplier_links(id, connection)?;
+                    check_precondition(&query, if_match, connection)?;
+
+                    match mode {
+                        DeleteMode::Soft => set_supplier_active(id, false, connection),
+                        DeleteMode::Purge => purge_supplier(id, connection),
+                    }
+                })?;
+
+                Ok(json::object!{
+                    "code" => 204,
+                    "success" => true,
+                })
+            },
+            Query::POSTSupplierRestore(id) => {
+                transaction(connection, || {
+                    supplier_links(id as i64, connection)?;
+                    check_precondition(&query, if_match, connection)?;
+                    set_supplier_active(id as i64, true, connection)
+                })?;
+
+                get_processing::query_to_json(Query::GETSupplierFromId(id, FieldSelection::default()), version, connection)
+            },
+            _ => Err(DatabaseError::SubmissionError("Invalid query type".to_string())),
+        }
+    })
+}
+
+fn set_supplier_active(id: i64, active: bool, connection: &dyn Backend) -> Result<(), DatabaseError> {
+    let values = [Value::Boolean(active), Value::Integer(id)];
+    execute_with_values("UPDATE supplier SET active = ? WHERE id = ?", &values, "Failed to update supplier", connection)?;
+    bump_supplier(id, connection)
+}
+
+// Removes a supplier, then each row the supplier made use of which is no longer used by any other
+fn purge_supplier(id: i64, connection: &dyn Backend) -> Result<(), DatabaseError> {
+    let error_message = "Failed to purge supplier";
+    let links = supplier_links(id, connection)?;
+
+    execute_with_values("DELETE FROM supplier_supplies WHERE fk_supplier = ?", &[Value::Integer(id)], error_message, connection)?;
+    execute_with_values("DELETE FROM supplier WHERE id = ?", &[Value::Integer(id)], error_message, connection)?;
+
+    if let Some(address_id) = links.address_id {
+        remove_unused_address(address_id, connection)?;
+    }
+
+    if let Some(contact_id) = links.contact_id {
+        remove_unused_contact(contact_id, connection)?;
+    }
+
+    if let Some(rep_id) = links.rep_id {
+        remove_unused_rep(rep_id, connection)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,185 @@
+use std::collections::HashMap;
+
+use crate::errors::DatabaseError;
+use crate::server::api::query_types::Expansion;
+use crate::server::databases::{
+    data_structs::{DBTable, DBTableStruct, Type, Value},
+    backend::Backend,
+    records::{Address, ContactEmail, ContactNumber, Rep},
+    rows::ToRow,
+    tables,
+    config::data_keys,
+};
+use json::JsonValue;
+
+const ADDRESS_SQL: &str = r"SELECT id, Line1, Line2, Town, Council, Postcode
+    FROM address
+    WHERE id IN ({})";
+
+const SUPPLIER_EMAIL_SQL: &str = "SELECT supplierId, Email FROM view_suppliers_email WHERE supplierId IN ({})";
+const SUPPLIER_NUMBERS_SQL: &str = "SELECT supplierId, Number FROM view_suppliers_numbers WHERE supplierId IN ({})";
+
+const REP_SQL: &str = r"SELECT
+        sr.id,
+        (SELECT
+            title
+        FROM person_title
+        WHERE sr.fk_person_title = person_title.id
+        ) as Title,
+        sr.FirstName,
+        sr.LastName,
+        sr.fk_contact as ContactID
+    FROM supply_rep as sr
+    WHERE sr.id IN ({})";
+
+const REP_EMAIL_SQL: &str = "SELECT SupplyRepID, Email FROM view_supply_rep_email WHERE SupplyRepID IN ({})";
+const REP_NUMBERS_SQL: &str = "SELECT SupplyRepID, Number FROM view_supply_rep_numbers WHERE SupplyRepID IN ({})";
+
+///
+/// Embeds the related resources named by `expand` within each supplier of a json array of
+/// suppliers, each holding the `addressId` and `repId` of its supplier row. The resources of
+/// every supplier are loaded together, so the number of queries made does not grow with the
+/// number of suppliers. A supplier without the resource has it set to null.
+///
+pub fn expand_suppliers(suppliers: &mut JsonValue, expand: &[Expansion], connection: &dyn Backend) -> Result<(), DatabaseError> {
+
+    for expansion in expand {
+        let (key, resources) = match expansion {
+            Expansion::Address => {
+                let ids = ids_of(suppliers, data_keys::ADDRESS_ID);
+                (data_keys::ADDRESS_ID, addresses(&ids, connection)?)
+            },
+            Expansion::Contact => {
+                let ids = ids_of(suppliers, data_keys::ID);
+                let emails = emails_of(SUPPLIER_EMAIL_SQL, &ids, connection)?;
+                let numbers = numbers_of(SUPPLIER_NUMBERS_SQL, &ids, connection)?;
+                let contacts = ids.iter()
+                    .map(|id| (*id, contact(emails.get(id), numbers.get(id))))
+                    .collect();
+                (data_keys::ID, contacts)
+            },
+            Expansion::Rep => {
+                let ids = ids_of(suppliers, data_keys::REP_ID);
+                (data_keys::REP_ID, reps(&ids, connection)?)
+            },
+        };
+
+        for supplier in suppliers.members_mut() {
+            let resource = supplier[key].as_i64()
+                .and_then(|id| resources.get(&id))
+                .cloned()
+                .unwrap_or(JsonValue::Null);
+            supplier[expansion.name()] = resource;
+        }
+    }
+    Ok(())
+}
+
+// addresses keyed by their id, in the shape of a supplier's address which leaves out the id
+fn addresses(ids: &[i64], connection: &dyn Backend) -> Result<HashMap<i64, JsonValue>, DatabaseError> {
+    let table = rows_with_ids(ADDRESS_SQL, ids, tables::address_table(), connection)?;
+
+    let mut addresses = HashMap::new();
+    for address in table.records::<Address>()? {
+        let mut json = address.to_json();
+        json.remove(data_keys::ID);
+        addresses.insert(address.id, json);
+    }
+    Ok(addresses)
+}
+
+// reps keyed by their id, along with their contact details, in the shape of a supplier's rep
+fn reps(ids: &[i64], connection: &dyn Backend) -> Result<HashMap<i64, JsonValue>, DatabaseError> {
+    let table = rows_with_ids(REP_SQL, ids, tables::rep_table(), connection)?;
+    let emails = emails_of(REP_EMAIL_SQL, ids, connection)?;
+    let numbers = numbers_of(REP_NUMBERS_SQL, ids, connection)?;
+
+    let mut reps = HashMap::new();
+    for rep in table.records::<Rep>()? {
+        let id = match rep.id {
+            Some(id) => id,
+            None => continue,
+        };
+        let mut json = rep.to_json();
+        json.remove(data_keys::CONTACT_ID);
+        json.remove(data_keys::ID);
+
+        let contact = contact(emails.get(&id), numbers.get(&id));
+        if !contact.is_null() {
+            json[data_keys::CONTACT] = contact;
+        }
+        reps.insert(id, json);
+    }
+    Ok(reps)
+}
+
+// contact details hold only the kinds of detail which were found, and are null if none were
+fn contact(emails: Option<&Vec<JsonValue>>, numbers: Option<&Vec<JsonValue>>) -> JsonValue {
+    let mut contact = JsonValue::Null;
+    if let Some(emails) = emails {
+        contact[data_keys::EMAIL] = emails.clone().into();
+    }
+    if let Some(numbers) = numbers {
+        contact[data_keys::NUMBER] = numbers.clone().into();
+    }
+    contact
+}
+
+// the email addresses read by a query, grouped by the id of the supplier or rep they belong to
+fn emails_of(sql: &str, ids: &[i64], connection: &dyn Backend) -> Result<HashMap<i64, Vec<JsonValue>>, DatabaseError> {
+    let emails = rows_with_ids(sql, ids, tables::email_table(), connection)?.records::<ContactEmail>()?;
+    Ok(grouped(emails.into_iter().map(|email| (email.id, email.email))))
+}
+
+// the phone numbers read by a query, grouped by the id of the supplier or rep they belong to
+fn numbers_of(sql: &str, ids: &[i64], connection: &dyn Backend) -> Result<HashMap<i64, Vec<JsonValue>>, DatabaseError> {
+    let numbers = rows_with_ids(sql, ids, tables::numbers_table(), connection)?.records::<ContactNumber>()?;
+    Ok(grouped(numbers.into_iter().map(|number| (number.id, number.number))))
+}
+
+fn grouped(details: impl Iterator<Item = (i64, String)>) -> HashMap<i64, Vec<JsonValue>> {
+    let mut grouped: HashMap<i64, Vec<JsonValue>> = HashMap::new();
+    for (id, detail) in details {
+        grouped.entry(id).or_default().push(detail.into());
+    }
+    grouped
+}
+
+// runs a query whose "{}" is replaced by the ids, which are bound as a single json array so the
+// statement is the same however many ids there are, and can be kept prepared by the connection
+fn rows_with_ids(sql: &str, ids: &[i64], structure: DBTableStruct, connection: &dyn Backend) -> Result<DBTable, DatabaseError> {
+    if ids.is_empty() {
+        return Ok(DBTable::new(&structure));
+    }
+
+    let ids_of_array = match connection.database_type() {
+        Type::Sqlite => "SELECT value FROM json_each(?)",
+        Type::Postgres => "SELECT value::bigint FROM json_array_elements_text(?::text::json)",
+    };
+    let values = [Value::String(json::stringify(ids))];
+    connection.query(&sql.replace("{}", ids_of_array), &values, structure)
+        .map_err(|_| DatabaseError::QueryError("Failed to query related resources".to_string()))
+}
+
+// the distinct ids held under a key of each supplier
+fn ids_of(suppliers: &JsonValue, key: &str) -> Vec<i64> {
+    let mut ids: Vec<i64> = suppliers.members().filter_map(|supplier| supplier[key].as_i64()).collect();
+    ids.sort_unstable();
+    ids.dedup();
+    ids
+}
+
+
+#[cfg(test)]
+mod test {
+    use crate::server::databases::config::data_keys;
+
+    #[test]
+    fn test_contact_holds_the_details_found() {
+        let emails = vec![json::JsonValue::from("sales@acme.test")];
+        let contact = super::contact(Some(&emails), None);
+        assert_eq!(contact[data_keys::EMAIL][0], "sales@acme.test");
+        assert!(!contact.has_key(data_keys::NUMBER));
+        assert!(super::contact(None, None).is_null());
+    }
+}
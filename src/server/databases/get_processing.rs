@@ -0,0 +1,560 @@
+use crate::errors::DatabaseError;
+use crate::server::api::query_types::{Query, CollectionParams, PageCursor, ApiVersion, FieldSelection, Expansion};
+use crate::server::api::parsing::collection_link;
+use crate::server::databases::{
+    data_structs::{DBTable, Value, table_to_json_struct, JsonStructType},
+    backend::{Database, Backend},
+    records::{Address, ContactEmail, ContactNumber, Rep, Supplier},
+    rows::ToRow,
+    tables, get_sql_queries, search, expansion,
+    config::data_keys,
+};
+use json::JsonValue;
+
+
+///
+/// Processes query, submits query to database, and returns a json response
+/// with a payload in the shape used by the requested version of the api
+///
+pub fn process_query(query: Query, version: ApiVersion, database: &Database) -> Result<JsonValue, DatabaseError> {
+    database.connect(|connection| query_to_json(query, version, connection))
+}
+
+/// As process_query, using a connection the caller already holds
+pub fn query_to_json(query: Query, version: ApiVersion, connection: &dyn Backend) -> Result<JsonValue, DatabaseError> {
+ 
+    let mut json_object = json::object!{
+        "code": 200,
+        "success": false,
+    };
+
+    // Aids in debugging specific queries
+    /* 
+    let cloned_query = query.clone();
+    match cloned_query {
+        Query::GETStockSuppliers => println!("GETStockSuppliers"),
+        Query::GETStockSupplierId(id) => println!("GETStockSupplierId({})", id),
+        Query::GETStockSupplierName => println!("GETStockSupplierName"),
+        _ => println!("Invalid query")
+    }
+    */
+
+    match query {
+
+        Query::GETSuppliers(ref params) |
+        Query::GETSuppliersEmail(ref params) |
+        Query::GETSuppliersNumbers(ref params) => {
+
+            collection_to_json(&query, params, connection, &mut json_object)?;
+        },
+        Query::GETSupplierFromId(id, _) => {
+            let expand = query_expansions(&query)?;
+            check_fields(&query, &expand)?;
+            json_object["payload"] = expanded_supplier(id, &expand, connection)?
+                .ok_or_else(|| DatabaseError::NotFound(format!("There is no supplier with the id {}", id)))?;
+        },
+        Query::GETSupplierIdFromName(_) => {
+
+
+            let id = data_table_from_query(
+                &query, 
+                connection
+            )?;
+
+            if id.rows.len() == 0 {
+                return Ok(json_object);
+            }
+            
+            json_object["payload"] = table_to_json_struct(&id, JsonStructType::Object);
+
+        },
+
+        Query::GETSupplierNameFromId(_) => {
+            
+            let name = data_table_from_query(&query, connection)?;
+
+            if name.rows.len() == 0 {
+                return Ok(json_object);
+            }
+
+            json_object["payload"] = table_to_json_struct(&name, JsonStructType::Object);
+        },
+
+        Query::GETSupplierAddressFromId(_) => {
+
+            if let Some(address) = data_table_from_query(&query, connection)?.first::<Address>()? {
+                json_object["payload"] = address.to_json();
+            }
+        },
+
+        Query::GETSupplierRepFromId(_) => {
+
+            if let Some(rep) = data_table_from_query(&query, connection)?.first::<Rep>()? {
+                json_object["payload"] = rep_to_json(&rep, rep.id, connection)?;
+            }
+        },
+        Query::GETSuppliersCategories => {
+            
+            let categories = data_table_from_query(
+                &query, 
+                connection)?;
+
+            if categories.rows.len() == 0 {
+                return Ok(json_object);
+            }
+
+            json_object["payload"] = table_to_json_struct(&categories, JsonStructType::Table);
+        },
+        Query::GETSupplierCategoriesFromId(_) => {
+            
+            let supply_categories = data_table_from_query(
+                &query, 
+                connection)?;
+
+            if supply_categories.rows.len() == 0 {
+                return Ok(json_object);
+            }
+
+            json_object["payload"] = table_to_json_struct(&supply_categories, JsonStructType::Table);
+        },
+        Query::GETSupplyRepFromId(id) => {
+
+            if let Some(rep) = data_table_from_query(&query, connection)?.first::<Rep>()? {
+                json_object["payload"] = rep_to_json(&rep, Some(id as i64), connection)?;
+            }
+        },
+
+        Query::GETSearch(ref params) => {
+
+            let results = search::search_table(params, connection)?;
+            json_object["payload"] = search::search_to_json(&results);
+        },
+
+        Query::GETSupplyRepEmailFromId(_) => {
+
+            let emails = data_table_from_query(&query, connection)?.records::<ContactEmail>()?;
+            if !emails.is_empty() {
+                json_object["payload"] = email_list(&emails);
+            }
+        },
+        Query::GETSupplyRepPhoneNumbersFromId(_) => {
+
+            let numbers = data_table_from_query(&query, connection)?.records::<ContactNumber>()?;
+            if !numbers.is_empty() {
+                json_object["payload"] = number_list(&numbers);
+            }
+        },
+        _ => {
+            let error_message = format!("Query has not been implemented provided: {:?}", query);
+            return Err(DatabaseError::QueryError(error_message));
+        }
+
+    }
+    if !json_object["payload"].is_null() {
+        json_object["success"] = json::JsonValue::Boolean(true);
+        versioned_payload(&query, &mut json_object["payload"], version);
+
+        // the resources named by expand are kept whichever fields are chosen
+        if let Some(selection) = field_selection(&query) {
+            let mut fields = selection.fields.clone();
+            if !fields.is_empty() {
+                fields.extend(query_expansions(&query)?.iter().map(|expansion| expansion.name().to_string()));
+            }
+            select_fields(&mut json_object["payload"], &fields);
+        }
+    }
+
+    Ok(json_object)
+}
+
+///
+/// Processes a query which is rendered as a table rather than as json, e.g. as csv. The
+/// rows are those of the query's own table, so related records such as a supplier's
+/// address or contact details are left out, as are any fields not chosen by `fields`.
+///
+pub fn process_table_query(query: Query, version: ApiVersion, database: &Database) -> Result<DBTable, DatabaseError> {
+    database.connect(|connection| query_to_table(query, version, connection))
+}
+
+/// As process_table_query, using a connection the caller already holds
+pub fn query_to_table(query: Query, version: ApiVersion, connection: &dyn Backend) -> Result<DBTable, DatabaseError> {
+
+    let mut table = match query {
+        Query::GETSuppliers(ref params) |
+        Query::GETSuppliersEmail(ref params) |
+        Query::GETSuppliersNumbers(ref params) => {
+            check_fields(&query, &query_expansions(&query)?)?;
+            collection_page(&query, params, connection)?.table
+        },
+        Query::GETSearch(ref params) => {
+            search::search_table(params, connection)?
+        },
+        Query::GETSupplierFromId(id, _) => {
+            check_fields(&query, &query_expansions(&query)?)?;
+            let table = data_table_from_query(&query, connection)?;
+            if table.rows.is_empty() {
+                return Err(DatabaseError::NotFound(format!("There is no supplier with the id {}", id)));
+            }
+            table
+        },
+        Query::GETSupplierIdFromName(_) |
+        Query::GETSupplierNameFromId(_) |
+        Query::GETSupplierEmailFromId(_) |
+        Query::GETSupplierNumbersFromId(_) |
+        Query::GETSupplierAddressFromId(_) |
+        Query::GETSupplierRepFromId(_) |
+        Query::GETSuppliersCategories |
+        Query::GETSupplierCategoriesFromId(_) |
+        Query::GETSupplyRepFromId(_) |
+        Query::GETSupplyRepEmailFromId(_) |
+        Query::GETSupplyRepPhoneNumbersFromId(_) => {
+            data_table_from_query(&query, connection)?
+        },
+        _ => {
+            let error_message = format!("Query has not been implemented provided: {:?}", query);
+            return Err(DatabaseError::QueryError(error_message));
+        }
+    };
+
+    // as with the json payload, v2 gives a supplier's `active` value as a boolean
+    if version != ApiVersion::V1 && matches!(query, Query::GETSuppliers(_) | Query::GETSupplierFromId(_, _)) {
+        if let Some(index) = table.structure.fields.iter().position(|field| field.name == data_keys::ACTIVE) {
+            table.structure.fields[index].field_type = Value::Boolean(false);
+            for row in table.rows.iter_mut() {
+                if let Value::Integer(active) = row.cells[index] {
+                    row.cells[index] = Value::Boolean(active != 0);
+                }
+            }
+        }
+    }
+
+    if let Some(selection) = field_selection(&query) {
+        table.select_fields(&selection.fields);
+    }
+    Ok(table)
+}
+
+// The names of the xml elements which hold a table's rows, and each row within it
+pub fn xml_element_names(query: &Query) -> (&'static str, &'static str) {
+    match query {
+        Query::GETSuppliers(_) | Query::GETSupplierFromId(_, _) => ("suppliers", "supplier"),
+        Query::GETSuppliersEmail(_) | Query::GETSupplierEmailFromId(_) | Query::GETSupplyRepEmailFromId(_) => ("emails", "email"),
+        Query::GETSuppliersNumbers(_) | Query::GETSupplierNumbersFromId(_) | Query::GETSupplyRepPhoneNumbersFromId(_) => ("numbers", "number"),
+        Query::GETSupplierAddressFromId(_) => ("addresses", "address"),
+        Query::GETSupplierRepFromId(_) | Query::GETSupplyRepFromId(_) => ("reps", "rep"),
+        Query::GETSuppliersCategories | Query::GETSupplierCategoriesFromId(_) => ("categories", "category"),
+        Query::GETSearch(_) => ("results", "result"),
+        _ => ("rows", "row"),
+    }
+}
+
+///
+/// Builds the json object of a supplier, along with its contact details, address and rep, 
+/// in the shape used by version 1 of the api. None is returned if there is no such supplier.
+///
+pub fn supplier_to_json(id: u64, connection: &dyn Backend) -> Result<Option<JsonValue>, DatabaseError> {
+    expanded_supplier(id, &Expansion::ALL, connection)
+}
+
+// Builds the json object of a supplier holding only the related resources named by `expand`
+fn expanded_supplier(id: u64, expand: &[Expansion], connection: &dyn Backend) -> Result<Option<JsonValue>, DatabaseError> {
+
+    let supplier = match data_table_from_query(&Query::GETSupplierFromId(id, FieldSelection::default()), connection)?.first::<Supplier>()? {
+        Some(supplier) => supplier,
+        None => return Ok(None),
+    };
+
+    let mut rows = json::array![supplier.to_json()];
+    expansion::expand_suppliers(&mut rows, expand, connection)?;
+
+    let mut json = json::object! {
+        data_keys::ID => supplier.id,
+        data_keys::NAME => supplier.name,
+        data_keys::ACTIVE => supplier.active as i64,
+    };
+    for expansion in [Expansion::Contact, Expansion::Rep, Expansion::Address] {
+        if expand.contains(&expansion) {
+            json[expansion.name()] = rows[0][expansion.name()].clone();
+        }
+    }
+
+    Ok(Some(json))
+}
+
+// The json object of a rep, along with its contact details. The contact id is left out, and
+// the contact details are only read for a rep whose id is known.
+fn rep_to_json(rep: &Rep, id: Option<i64>, connection: &dyn Backend) -> Result<JsonValue, DatabaseError> {
+    let mut json = rep.to_json();
+    json.remove(data_keys::CONTACT_ID);
+
+    if let Some(id) = id {
+        let id = id as u64;
+        let emails = data_table_from_query(&Query::GETSupplyRepEmailFromId(id), connection)?.records::<ContactEmail>()?;
+        if !emails.is_empty() {
+            json[data_keys::CONTACT][data_keys::EMAIL] = email_list(&emails);
+        }
+
+        let numbers = data_table_from_query(&Query::GETSupplyRepPhoneNumbersFromId(id), connection)?.records::<ContactNumber>()?;
+        if !numbers.is_empty() {
+            json[data_keys::CONTACT][data_keys::NUMBER] = number_list(&numbers);
+        }
+    }
+    Ok(json)
+}
+
+fn email_list(emails: &[ContactEmail]) -> JsonValue {
+    emails.iter().map(|email| email.email.clone()).collect::<Vec<String>>().into()
+}
+
+fn number_list(numbers: &[ContactNumber]) -> JsonValue {
+    numbers.iter().map(|number| number.number.clone()).collect::<Vec<String>>().into()
+}
+
+///
+/// Payloads are built in the shape used by version 1 of the api, later versions change
+/// that shape as follows:
+/// 
+/// v2: a supplier's `active` value is a boolean rather than 0 or 1, and a supplier or rep
+///     always has a `contact` object holding `email` and `number` arrays, even when empty.
+///
+pub fn versioned_payload(query: &Query, payload: &mut JsonValue, version: ApiVersion) {
+    if version == ApiVersion::V1 {
+        return;
+    }
+
+    match query {
+        Query::GETSuppliers(_) => {
+            for supplier in payload.members_mut() {
+                active_to_boolean(supplier);
+                fill_expanded_contacts(supplier);
+            }
+        },
+        Query::GETSupplierFromId(_, _) => {
+            active_to_boolean(payload);
+            fill_expanded_contacts(payload);
+        },
+        Query::GETSupplierRepFromId(_) | Query::GETSupplyRepFromId(_) => {
+            fill_contact(payload);
+        },
+        _ => {}
+    }
+}
+
+fn active_to_boolean(supplier: &mut JsonValue) {
+    if let Some(active) = supplier[data_keys::ACTIVE].as_i64() {
+        supplier[data_keys::ACTIVE] = JsonValue::Boolean(active != 0);
+    }
+}
+
+// fills the contact details of a supplier and its rep, where they have been expanded
+fn fill_expanded_contacts(supplier: &mut JsonValue) {
+    if supplier.has_key(data_keys::CONTACT) {
+        fill_contact(supplier);
+    }
+    if supplier[data_keys::REP].is_object() {
+        fill_contact(&mut supplier[data_keys::REP]);
+    }
+}
+
+fn fill_contact(object: &mut JsonValue) {
+    if !object[data_keys::CONTACT].is_object() {
+        object[data_keys::CONTACT] = json::object!{};
+    }
+    for key in [data_keys::EMAIL, data_keys::NUMBER] {
+        if !object[data_keys::CONTACT][key].is_array() {
+            object[data_keys::CONTACT][key] = JsonValue::new_array();
+        }
+    }
+}
+
+// The fields and related resources chosen by the query string of a query, if it takes them
+fn field_selection(query: &Query) -> Option<&FieldSelection> {
+    match query {
+        Query::GETSuppliers(params) |
+        Query::GETSuppliersEmail(params) |
+        Query::GETSuppliersNumbers(params) => Some(&params.selection),
+        Query::GETSupplierFromId(_, selection) => Some(selection),
+        _ => None,
+    }
+}
+
+// The related resources embedded within the suppliers of a query. A single supplier embeds 
+// all of them unless the query names the ones it wants, either with `expand` or `fields`.
+fn query_expansions(query: &Query) -> Result<Vec<Expansion>, DatabaseError> {
+    match query {
+        Query::GETSuppliers(params) => Ok(params.selection.expand.clone().unwrap_or_default()),
+        Query::GETSupplierFromId(_, selection) => Ok(match &selection.expand {
+            Some(expand) => expand.clone(),
+            None if selection.fields.is_empty() => Expansion::ALL.to_vec(),
+            None => Expansion::ALL.into_iter()
+                .filter(|expansion| selection.fields.iter().any(|field| field == expansion.name()))
+                .collect(),
+        }),
+        _ => match field_selection(query).and_then(|selection| selection.expand.as_ref()) {
+            Some(_) => Err(DatabaseError::InvalidParameter("Only suppliers can be expanded".to_string())),
+            None => Ok(Vec::new()),
+        },
+    }
+}
+
+// Checks that each selected field is a field of the query's table or an embedded resource
+fn check_fields(query: &Query, expand: &[Expansion]) -> Result<(), DatabaseError> {
+    let fields = match field_selection(query) {
+        Some(selection) => &selection.fields,
+        None => return Ok(()),
+    };
+    let table = tables::get_tables(query.clone());
+
+    for field in fields {
+        let known = table.fields.iter().any(|table_field| table_field.name == *field) ||
+            expand.iter().any(|expansion| expansion.name() == field);
+        if !known {
+            return Err(DatabaseError::InvalidParameter(format!("Unknown field: {}", field)));
+        }
+    }
+    Ok(())
+}
+
+// Keeps only the selected fields of an object, or of each object in an array. Selecting no 
+// fields keeps all of them.
+fn select_fields(payload: &mut JsonValue, fields: &[String]) {
+    if fields.is_empty() {
+        return;
+    }
+    if payload.is_array() {
+        for item in payload.members_mut() {
+            select_fields(item, fields);
+        }
+        return;
+    }
+
+    let unselected: Vec<String> = payload.entries()
+        .map(|(key, _)| key.to_string())
+        .filter(|key| !fields.contains(key))
+        .collect();
+    for key in unselected {
+        payload.remove(&key);
+    }
+}
+
+/// A page of a collection along with the total number of items in the collection and the
+/// links to the pages either side of it
+struct CollectionPage {
+    table: DBTable,
+    total: i64,
+    next: Option<String>,
+    prev: Option<String>,
+}
+
+///
+/// Adds a page of a collection to the json response, along with the total number of items in the 
+/// collection and the links to the pages either side of it. Related resources named by the 
+/// query's `expand` option are embedded in each item.
+///
+fn collection_to_json(query: &Query, params: &CollectionParams, connection: &dyn Backend, json_object: &mut JsonValue) -> Result<(), DatabaseError> {
+
+    let expand = query_expansions(query)?;
+    check_fields(query, &expand)?;
+    let page = collection_page(query, params, connection)?;
+
+    json_object["meta"] = json::object! {
+        "total" => page.total,
+        "count" => page.table.rows.len(),
+        "limit" => params.limit,
+        "offset" => if params.cursor.is_none() { JsonValue::from(params.offset) } else { JsonValue::Null },
+    };
+    json_object["links"] = json::object! {
+        "self" => collection_link(params, params.offset, params.cursor.as_ref()),
+        "next" => page.next,
+        "prev" => page.prev,
+    };
+
+    if !page.table.rows.is_empty() {
+        json_object["payload"] = table_to_json_struct(&page.table, JsonStructType::Table);
+        expansion::expand_suppliers(&mut json_object["payload"], &expand, connection)?;
+    }
+    Ok(())
+}
+
+fn collection_page(query: &Query, params: &CollectionParams, connection: &dyn Backend) -> Result<CollectionPage, DatabaseError> {
+
+    let row_structure = tables::get_tables(query.clone());
+    let sql = get_sql_queries::collection_sql(query, &row_structure)?;
+    let source = get_sql_queries::collection_source(query).unwrap();
+
+    // count all the items matching the filters
+    let total = match connection.query_row(&sql.count, &sql.count_values, &[Value::Integer(0)])? {
+        Some(row) => if let Value::Integer(total) = row[0] { total } else { 0 },
+        None => return Err(DatabaseError::QueryError("Failed to count collection items".to_string())),
+    };
+
+    let mut table = connection.query(&sql.page, &sql.page_values, row_structure)?;
+
+    let mut next: Option<String> = None;
+    let mut prev: Option<String> = None;
+
+    match &params.cursor {
+        None => {
+            let count = table.rows.len() as u64;
+            if let Some(limit) = params.limit {
+                if params.offset + count < total as u64 {
+                    next = Some(collection_link(params, params.offset + limit, None));
+                }
+            }
+            if params.offset > 0 {
+                let offset = params.offset.saturating_sub(params.limit.unwrap_or(params.offset));
+                prev = Some(collection_link(params, offset, None));
+            }
+        },
+        Some(cursor) => {
+            let limit = params.limit.unwrap_or(0) as usize;
+            let has_more = table.rows.len() > limit;
+            table.rows.truncate(limit);
+
+            // a page before the cursor is read backwards, so put it back into order
+            let backwards = matches!(cursor, PageCursor::Before(_));
+            if backwards {
+                table.rows.reverse();
+            }
+
+            if !table.rows.is_empty() {
+                let first = PageCursor::Before(row_keys(&table, 0, &source.key_fields));
+                let last = PageCursor::After(row_keys(&table, table.rows.len() - 1, &source.key_fields));
+
+                if has_more || backwards {
+                    next = Some(collection_link(params, 0, Some(&last)));
+                }
+                if (has_more && backwards) || matches!(cursor, PageCursor::After(_)) {
+                    prev = Some(collection_link(params, 0, Some(&first)));
+                }
+            }
+        }
+    }
+
+    Ok(CollectionPage { table, total, next, prev })
+}
+
+// the values of the key fields of a row, used to build a page cursor
+fn row_keys(table: &DBTable, row: usize, key_fields: &[&str]) -> Vec<JsonValue> {
+    let mut keys = Vec::new();
+    for key in key_fields {
+        if let Some(index) = table.structure.fields.iter().position(|field| field.name == *key) {
+            keys.push(table.rows[row].cells[index].to_json());
+        }
+    }
+    keys
+}
+
+pub fn data_table_from_query(query_type: &Query, connection: &dyn Backend) -> Result<DBTable, DatabaseError> {
+
+    // try connect to and query the db
+    let query = get_sql_queries::get_sql(query_type);
+ 
+    // gets the table structure for this query
+    
+    let dbtable = tables::get_tables(query_type.clone()); 
+    let response_data = connection.query(&query.sql, &query.values, dbtable)?;
+ 
+    
+    Ok(response_data)
+ }
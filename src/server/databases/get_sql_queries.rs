@@ -0,0 +1,316 @@
+use crate::errors::DatabaseError;
+use crate::server::api::query_types::{Query, PageCursor};
+use crate::server::databases::{
+    data_structs::{DBTableStruct, Value},
+    util::BoundSql,
+    tables::{SUPPLIER, SUPPLIER_EMAILS, SUPPLIER_NUMBERS},
+    config::data_keys,
+};
+use json::JsonValue;
+
+
+// The columns of a supplier row, named so that columns added to the table such as its 
+// version are not returned
+fn supplier_columns() -> String {
+    SUPPLIER.column_list()
+}
+
+///
+/// The SQL statement for a query, the ids and names it is given are bound to the statement
+/// rather than written into it
+///
+pub fn get_sql(query: &Query) -> BoundSql {
+
+    match query {
+        // suppliers
+        Query::GETSuppliers(_) => {
+            BoundSql::new(&format!("SELECT {} FROM supplier", supplier_columns()), vec![])
+        },
+        Query::GETSuppliersEmail(_) => {
+            BoundSql::new("SELECT * FROM view_suppliers_email", vec![])
+        },
+        Query::GETSuppliersNumbers(_) => {
+            BoundSql::new("SELECT * FROM view_suppliers_numbers", vec![])
+        },
+        Query::GETSuppliersCategories => {
+            BoundSql::new("SELECT * FROM supply_categories", vec![])
+        },
+
+        // supplier by id
+        Query::GETSupplierNameFromId(id) => {
+            BoundSql::new("SELECT name FROM supplier WHERE id = ?", id_value(*id))
+        },
+        Query::GETSupplierFromId(id, _) => {
+            BoundSql::new(&format!("SELECT {} FROM supplier WHERE id = ?", supplier_columns()), id_value(*id))
+        },
+        Query::GETSupplierIdFromName(name) => {
+            BoundSql::new("SELECT id FROM supplier WHERE name = ?", vec![Value::String(name.clone())])
+        },
+        Query::GETSupplierEmailFromId(id) => {
+            BoundSql::new("SELECT * FROM view_suppliers_email WHERE supplierId = ?", id_value(*id))
+        },
+        Query::GETSupplierNumbersFromId(id) => {
+            BoundSql::new("SELECT * FROM view_suppliers_numbers WHERE supplierId = ?", id_value(*id))
+        },
+        Query::GETSupplierAddressFromId(id) => {
+            BoundSql::new(r"SELECT
+                address.id, 
+                address.Line1, 
+                address.Line2,
+                address.Town,
+                address.Council,
+                address.Postcode
+            FROM address, (
+                SELECT 
+                    supplier.fk_address as AddressID 
+                    FROM supplier 
+                    WHERE supplier.id = ?
+            ) as sa 
+            WHERE sa.AddressID = address.id; ", id_value(*id))
+        },
+
+        Query::GETSupplierCategoriesFromId(id) => {
+            BoundSql::new(r"SELECT 
+                s.fk_supply_category as CategoryID,
+                c.Type as Category
+            FROM supplier_supplies as s 
+            LEFT JOIN supply_categories as c 
+            ON s.fk_supply_category = c.id
+            WHERE s.fk_supplier = ?", id_value(*id))
+        },
+        Query::GETSupplierRepFromId(id) => {
+            BoundSql::new(r"SELECT
+                sr.id,
+                (SELECT 
+                    title 
+                FROM person_title 
+                WHERE sr.fk_person_title = person_title.id
+                ) as Title, 
+                sr.FirstName,
+                sr.LastName,
+                sr.fk_contact as ContactID
+            FROM supply_rep as sr,(
+                SELECT 
+                    supplier.fk_supply_rep as RepID 
+                FROM supplier 
+                WHERE supplier.id = ?
+            ) as s 
+            WHERE s.RepID = sr.id", id_value(*id))
+        },
+
+
+        // supplier rep
+        Query::GETSupplyRepFromId(id) => {
+            BoundSql::new(r"SELECT
+            (SELECT 
+                title 
+            FROM person_title 
+            WHERE sr.fk_person_title = person_title.id
+            ) as Title, 
+            sr.FirstName,
+            sr.LastName,
+            sr.fk_contact as ContactID
+        FROM supply_rep as sr
+        WHERE sr.id = ?", id_value(*id))
+        },
+        Query::GETSupplyRepPhoneNumbersFromId(id) => {
+            BoundSql::new(r"SELECT 
+                c.SupplyRepID, 
+                c.Number
+                FROM view_supply_rep_numbers as c
+                WHERE c.supplyRepID = ?", id_value(*id))
+        },
+        Query::GETSupplyRepEmailFromId(id) => {
+            BoundSql::new(r"SELECT 
+                c.SupplyRepID, 
+                c.Email
+                FROM view_supply_rep_email as c
+                WHERE c.supplyRepID = ?", id_value(*id))
+        },
+
+        _ => BoundSql::new("", vec![])
+    }
+}
+
+// the value bound for the id of a resource
+fn id_value(id: u64) -> Vec<Value> {
+    vec![Value::Integer(id as i64)]
+}
+
+
+/// Describes the table or view behind a collection endpoint. `columns` maps each json key
+/// to its column, in the order of the fields of the collection's DBTableStruct. `key_fields` 
+/// are the json keys which together give the rows of the collection a unique order, and 
+/// `supplier_column` is the column holding the id of the supplier each row belongs to.
+pub struct CollectionSource {
+    pub from: &'static str,
+    pub columns: Vec<(&'static str, &'static str)>,
+    pub key_fields: Vec<&'static str>,
+    pub supplier_column: &'static str,
+}
+
+pub fn collection_source(query: &Query) -> Option<CollectionSource> {
+    match query {
+        Query::GETSuppliers(_) => Some(CollectionSource {
+            from: "supplier",
+            columns: SUPPLIER.columns.iter().map(|column| (column.key, column.name)).collect(),
+            key_fields: vec![data_keys::ID],
+            supplier_column: "id",
+        }),
+        Query::GETSuppliersEmail(_) => Some(CollectionSource {
+            from: "view_suppliers_email",
+            columns: SUPPLIER_EMAILS.columns.iter().map(|column| (column.key, column.name)).collect(),
+            key_fields: vec![data_keys::ID, data_keys::EMAIL],
+            supplier_column: "supplierId",
+        }),
+        Query::GETSuppliersNumbers(_) => Some(CollectionSource {
+            from: "view_suppliers_numbers",
+            columns: SUPPLIER_NUMBERS.columns.iter().map(|column| (column.key, column.name)).collect(),
+            key_fields: vec![data_keys::ID, data_keys::NUMBER],
+            supplier_column: "supplierId",
+        }),
+        _ => None,
+    }
+}
+
+/// SQL statements, and the values to bind to them, for fetching a page of a collection 
+/// and counting all the rows of the collection which match the request filters.
+pub struct CollectionSql {
+    pub page: String,
+    pub page_values: Vec<Value>,
+    pub count: String,
+    pub count_values: Vec<Value>,
+}
+
+///
+/// Builds the SQL for a collection query using the pagination, sorting and filtering options
+/// held by the query. Filters and sort fields are checked against the collection's columns.
+/// 
+pub fn collection_sql(query: &Query, table: &DBTableStruct) -> Result<CollectionSql, DatabaseError> {
+
+    let params = match query {
+        Query::GETSuppliers(params) |
+        Query::GETSuppliersEmail(params) |
+        Query::GETSuppliersNumbers(params) => params,
+        _ => return Err(DatabaseError::QueryError(format!("Query is not a collection: {:?}", query))),
+    };
+    let source = collection_source(query).unwrap();
+
+    // filters, all of which must match
+    let mut conditions: Vec<String> = Vec::new();
+    let mut values: Vec<Value> = Vec::new();
+
+    for (key, raw_value) in &params.filters {
+        let field = table.fields.iter().find(|field| field.name == *key);
+
+        if let (Some(column), Some(field)) = (column_for(&source, key), field) {
+            conditions.push(format!("{} = ?", column));
+            values.push(filter_value(&field.field_type, raw_value, key)?);
+        }
+        else if key == data_keys::ACTIVE {
+            conditions.push(format!("{} IN (SELECT id FROM supplier WHERE active = ?)", source.supplier_column));
+            values.push(filter_value(&Value::Integer(0), raw_value, key)?);
+        }
+        else if key == data_keys::CATEGORY_TYPE {
+            conditions.push(format!(r"{} IN (SELECT s.fk_supplier 
+                FROM supplier_supplies as s 
+                JOIN supply_categories as c ON s.fk_supply_category = c.id 
+                WHERE c.Type = ?)", source.supplier_column));
+            values.push(Value::String(raw_value.clone()));
+        }
+        else if key == data_keys::CATEGORY_ID {
+            conditions.push(format!(r"{} IN (SELECT s.fk_supplier 
+                FROM supplier_supplies as s 
+                WHERE s.fk_supply_category = ?)", source.supplier_column));
+            values.push(filter_value(&Value::Integer(0), raw_value, key)?);
+        }
+        else {
+            return Err(DatabaseError::InvalidParameter(format!("Unknown filter field: {}", key)));
+        }
+    }
+
+    let count_where = if conditions.is_empty() { String::new() } else { format!(" WHERE {}", conditions.join(" AND ")) };
+    let count = format!("SELECT COUNT(*) FROM {}{}", source.from, count_where);
+    let count_values = values.clone();
+
+    // ordering, the key fields always come last so that the order of the rows is stable 
+    let mut order: Vec<String> = Vec::new();
+    for sort_key in &params.sort {
+        let column = column_for(&source, &sort_key.field)
+            .ok_or(DatabaseError::InvalidParameter(format!("Unknown sort field: {}", sort_key.field)))?;
+        order.push(format!("{} {}", column, if sort_key.descending { "DESC" } else { "ASC" }));
+    }
+
+    let key_columns: Vec<&str> = source.key_fields.iter().map(|key| column_for(&source, key).unwrap()).collect();
+    let backwards = matches!(params.cursor, Some(PageCursor::Before(_)));
+    for column in &key_columns {
+        if !params.sort.iter().any(|key| column_for(&source, &key.field) == Some(column)) {
+            order.push(format!("{} {}", column, if backwards { "DESC" } else { "ASC" }));
+        }
+    }
+
+    // a cursor page starts after (or ends before) the row holding the cursor's key values
+    match &params.cursor {
+        Some(PageCursor::After(keys)) | Some(PageCursor::Before(keys)) => {
+            if keys.len() != key_columns.len() {
+                return Err(DatabaseError::InvalidParameter("Cursor does not match the collection".to_string()));
+            }
+            let placeholders = vec!["?"; keys.len()].join(", ");
+            conditions.push(format!("({}) {} ({})", key_columns.join(", "), if backwards { "<" } else { ">" }, placeholders));
+            for key in keys {
+                values.push(cursor_value(key)?);
+            }
+        },
+        _ => {}
+    }
+
+    // when using a cursor one extra row is requested to find out if there is a further page.
+    // PostgreSQL does not take a negative limit, so no limit is given as the largest one.
+    let (limit, offset) = match (&params.cursor, params.limit) {
+        (Some(_), Some(limit)) => (limit as i64 + 1, 0),
+        (_, Some(limit)) => (limit as i64, params.offset as i64),
+        (_, None) => (i64::MAX, params.offset as i64),
+    };
+    values.push(Value::Integer(limit));
+    values.push(Value::Integer(offset));
+
+    let select: Vec<&str> = source.columns.iter().map(|(_, column)| *column).collect();
+    let page_where = if conditions.is_empty() { String::new() } else { format!(" WHERE {}", conditions.join(" AND ")) };
+    let page = format!("SELECT {} FROM {}{} ORDER BY {} LIMIT ? OFFSET ?", select.join(", "), source.from, page_where, order.join(", "));
+
+    Ok(CollectionSql {
+        page,
+        page_values: values,
+        count,
+        count_values,
+    })
+}
+
+fn column_for(source: &CollectionSource, key: &str) -> Option<&'static str> {
+    source.columns.iter().find(|(json_key, _)| *json_key == key).map(|(_, column)| *column)
+}
+
+// converts the query string value of a filter to the type held by the field being filtered
+fn filter_value(field_type: &Value, raw_value: &str, key: &str) -> Result<Value, DatabaseError> {
+    let value = match (field_type, raw_value) {
+        (Value::Integer(_), "true") | (Value::Boolean(_), "true") => Some(Value::Integer(1)),
+        (Value::Integer(_), "false") | (Value::Boolean(_), "false") => Some(Value::Integer(0)),
+        (Value::Integer(_), _) | (Value::Boolean(_), _) => raw_value.parse::<i64>().ok().map(Value::Integer),
+        (Value::Float(_), _) => raw_value.parse::<f64>().ok().map(Value::Float),
+        (Value::String(_), _) => Some(Value::String(raw_value.to_string())),
+        _ => None,
+    };
+    value.ok_or(DatabaseError::InvalidParameter(format!("Invalid value for filter {}: {}", key, raw_value)))
+}
+
+fn cursor_value(key: &JsonValue) -> Result<Value, DatabaseError> {
+    if let Some(value) = key.as_i64() {
+        Ok(Value::Integer(value))
+    }
+    else if let Some(value) = key.as_str() {
+        Ok(Value::String(value.to_string()))
+    }
+    else {
+        Err(DatabaseError::InvalidParameter("Invalid cursor".to_string()))
+    }
+}
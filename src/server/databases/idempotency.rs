@@ -0,0 +1,148 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::errors::DatabaseError;
+use crate::server::databases::{
+    backend::Database,
+    data_structs::Value,
+};
+use crate::config::{IDEMPOTENCY_RETENTION_SECS, IDEMPOTENCY_IN_FLIGHT_SECS};
+
+/// A response stored against an idempotency key, along with the fingerprint of its request
+pub struct StoredResponse {
+    pub fingerprint: String,
+    pub content: String,
+    pub content_type: String,
+    pub status_line: String,
+    /// The headers given with the response which are given to a retry too, e.g. its ETag
+    pub headers: Vec<(String, String)>,
+}
+
+/// What became of a request's claim on an idempotency key, see reserve
+pub enum Reservation {
+    /// The key is the request's own, so it is carried out
+    Reserved,
+    /// The key was claimed by a request which has not yet finished, whose fingerprint is held
+    InFlight(String),
+    /// The key was claimed by a request which finished with the stored response
+    Stored(StoredResponse),
+}
+
+///
+/// Claims an idempotency key for a request before it is carried out. The key is inserted in a
+/// single statement which does nothing if the key is held already, so of two requests sent with
+/// the same key at once only one is given it. Keys older than the retention window are removed
+/// first, so that they can be used again, as are keys held for longer than a request could take
+/// by a request which never finished, e.g. as the server was stopped while carrying it out.
+///
+pub fn reserve(key: &str, fingerprint: &str, database: &Database) -> Result<Reservation, DatabaseError> {
+    let error = || DatabaseError::QueryError("Failed to read the idempotency keys".to_string());
+
+    database.connect(|connection| {
+        let expired = now() - IDEMPOTENCY_RETENTION_SECS as i64;
+        let abandoned = now() - IDEMPOTENCY_IN_FLIGHT_SECS as i64;
+        let sql = "DELETE FROM idempotency_keys WHERE created_at < ? OR (status_line = '' AND created_at < ?)";
+        connection.execute(sql, &[Value::Integer(expired), Value::Integer(abandoned)]).map_err(|_| error())?;
+
+        let sql = r"INSERT INTO idempotency_keys
+            (key, fingerprint, status_line, content_type, content, created_at)
+            VALUES (?, ?, '', '', '', ?)
+            ON CONFLICT (key) DO NOTHING
+            RETURNING key";
+        let values = [Value::String(key.to_string()), Value::String(fingerprint.to_string()), Value::Integer(now())];
+        if connection.query_row(sql, &values, &[Value::String(String::new())]).map_err(|_| error())?.is_some() {
+            return Ok(Reservation::Reserved);
+        }
+
+        let sql = "SELECT fingerprint, content, content_type, status_line, headers FROM idempotency_keys WHERE key = ?";
+        let text = Value::String(String::new());
+        let row = connection.query_row(sql, &[Value::String(key.to_string())], &[text.clone(), text.clone(), text.clone(), text.clone(), text])
+            .map_err(|_| error())?
+            .ok_or_else(error)?;
+        let read = |index: usize| match &row[index] {
+            Value::String(value) => Ok(value.clone()),
+            _ => Err(error()),
+        };
+        let stored = StoredResponse {
+            fingerprint: read(0)?,
+            content: read(1)?,
+            content_type: read(2)?,
+            status_line: read(3)?,
+            headers: parse_headers(&read(4)?).ok_or_else(error)?,
+        };
+        match stored.status_line.is_empty() {
+            true => Ok(Reservation::InFlight(stored.fingerprint)),
+            false => Ok(Reservation::Stored(stored)),
+        }
+    })
+}
+
+/// Stores the response given to the request which reserved an idempotency key, a response
+/// stored already is kept
+pub fn store_response(key: &str, response: &StoredResponse, database: &Database) -> Result<(), DatabaseError> {
+    let error = || DatabaseError::QueryError("Failed to store the idempotency key".to_string());
+
+    let headers: Vec<_> = response.headers.iter().map(|(name, value)| json::array![name.as_str(), value.as_str()]).collect();
+    let sql = r"UPDATE idempotency_keys
+        SET fingerprint = ?, status_line = ?, content_type = ?, content = ?, headers = ?, created_at = ?
+        WHERE key = ? AND status_line = ''";
+    database.connect(|connection| {
+        connection.execute(sql, &[
+            Value::String(response.fingerprint.clone()),
+            Value::String(response.status_line.clone()),
+            Value::String(response.content_type.clone()),
+            Value::String(response.content.clone()),
+            Value::String(json::JsonValue::Array(headers).dump()),
+            Value::Integer(now()),
+            Value::String(key.to_string()),
+        ]).map_err(|_| error())
+    })
+}
+
+/// Gives up a key reserved by a request which failed, so that the request can be sent again
+pub fn release(key: &str, database: &Database) -> Result<(), DatabaseError> {
+    database.connect(|connection| {
+        connection.execute("DELETE FROM idempotency_keys WHERE key = ? AND status_line = ''", &[Value::String(key.to_string())])
+            .map_err(|_| DatabaseError::QueryError("Failed to release the idempotency key".to_string()))
+    })
+}
+
+///
+/// Fingerprints a request from the parts which make it what it is, e.g. its method, path and
+/// body. The fingerprint is a 64 bit FNV-1a hash, as hex.
+///
+pub fn fingerprint(parts: &[&str]) -> String {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for part in parts {
+        // the length of each part is hashed too, so parts can not run into each other
+        for byte in part.len().to_le_bytes().iter().chain(part.as_bytes()) {
+            hash ^= *byte as u64;
+            hash = hash.wrapping_mul(0x100000001b3);
+        }
+    }
+    format!("{:016x}", hash)
+}
+
+// The headers stored with a response, held as a json array of [name, value] pairs
+fn parse_headers(headers: &str) -> Option<Vec<(String, String)>> {
+    json::parse(headers).ok()?.members()
+        .map(|header| Some((header[0].as_str()?.to_string(), header[1].as_str()?.to_string())))
+        .collect()
+}
+
+fn now() -> i64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|time| time.as_secs() as i64).unwrap_or(0)
+}
+
+
+#[cfg(test)]
+mod test {
+
+    #[test]
+    fn test_fingerprint() {
+        let fingerprint = super::fingerprint(&["POST", "/api/suppliers", "{\"name\":\"Acme\"}"]);
+        assert_eq!(fingerprint.len(), 16);
+        assert_eq!(fingerprint, super::fingerprint(&["POST", "/api/suppliers", "{\"name\":\"Acme\"}"]));
+        assert_ne!(fingerprint, super::fingerprint(&["POST", "/api/suppliers", "{\"name\":\"Bolt\"}"]));
+        assert_ne!(super::fingerprint(&["ab", "c"]), super::fingerprint(&["a", "bc"]));
+    }
+}
@@ -0,0 +1,132 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::errors::DatabaseError;
+use crate::server::databases::{
+    backend::{Backend, transaction},
+    data_structs::{Type, Value},
+};
+
+// The migrations which have been applied to the database, each is recorded as it is applied
+const CREATE_SCHEMA_VERSION_TABLE: &str = r"
+    CREATE TABLE IF NOT EXISTS schema_version (
+        version INTEGER PRIMARY KEY,
+        name TEXT NOT NULL,
+        applied_at INTEGER NOT NULL
+    );
+";
+
+///
+/// A change to the schema of the database. Migrations are applied in order of their version,
+/// and each is applied once, so a migration is never changed once released, a further change
+/// is made by adding a migration after it.
+///
+pub struct Migration {
+    pub version: i64,
+    pub name: &'static str,
+    sql: &'static str,
+}
+
+// Every migration of a SQLite database, in order, each held in a file of sqlite/migrations/. The first
+// only adds what the database does not have, so a database made before the migrations were
+// recorded is brought up to date without loss.
+pub const MIGRATIONS: [Migration; 5] = [
+    Migration { version: 1, name: "initial schema", sql: include_str!("sqlite/migrations/0001_initial_schema.sql") },
+    Migration { version: 2, name: "row versions", sql: include_str!("sqlite/migrations/0002_row_versions.sql") },
+    Migration { version: 3, name: "search index", sql: include_str!("sqlite/migrations/0003_search_index.sql") },
+    Migration { version: 4, name: "idempotency keys", sql: include_str!("sqlite/migrations/0004_idempotency_keys.sql") },
+    Migration { version: 5, name: "idempotency headers", sql: include_str!("sqlite/migrations/0005_idempotency_headers.sql") },
+];
+
+// Every migration of a PostgreSQL database, in order. The first holds the tables as they are
+// once the SQLite migrations above have been applied.
+pub const POSTGRES_MIGRATIONS: [Migration; 2] = [
+    Migration { version: 1, name: "initial schema", sql: include_str!("postgres/migrations/0001_initial_schema.sql") },
+    Migration { version: 2, name: "idempotency headers", sql: include_str!("postgres/migrations/0002_idempotency_headers.sql") },
+];
+
+/// The migrations of a type of database
+pub fn migrations(database: Type) -> &'static [Migration] {
+    match database {
+        Type::Sqlite => &MIGRATIONS,
+        Type::Postgres => &POSTGRES_MIGRATIONS,
+    }
+}
+
+/// The migrations which have not yet been applied to the database
+pub fn pending(connection: &dyn Backend) -> Result<Vec<&'static Migration>, DatabaseError> {
+    create_schema_version_table(connection)?;
+    let mut pending = Vec::new();
+    for migration in migrations(connection.database_type()) {
+        if !is_applied(migration.version, connection)? {
+            pending.push(migration);
+        }
+    }
+    Ok(pending)
+}
+
+///
+/// Applies each migration the database does not yet have, returning those applied. Each is
+/// applied within a transaction of its own along with the record of it, so a migration which
+/// fails leaves the database as it was before it, and a server started alongside another
+/// does not apply a migration twice.
+///
+pub fn migrate(connection: &dyn Backend) -> Result<Vec<&'static Migration>, DatabaseError> {
+    create_schema_version_table(connection)?;
+    let mut applied = Vec::new();
+    for migration in migrations(connection.database_type()) {
+        let newly_applied = transaction(connection, || {
+            if is_applied(migration.version, connection)? {
+                return Ok(false);
+            }
+            connection.execute_batch(migration.sql).map_err(|error| {
+                DatabaseError::QueryError(format!("Migration {} ({}) failed: {}", migration.version, migration.name, error.message()))
+            })?;
+            record(migration, connection)?;
+            Ok(true)
+        })?;
+        if newly_applied {
+            applied.push(migration);
+        }
+    }
+    Ok(applied)
+}
+
+fn create_schema_version_table(connection: &dyn Backend) -> Result<(), DatabaseError> {
+    connection.execute_batch(CREATE_SCHEMA_VERSION_TABLE)
+        .map_err(|_| DatabaseError::QueryError("Failed to create the schema_version table".to_string()))
+}
+
+fn is_applied(version: i64, connection: &dyn Backend) -> Result<bool, DatabaseError> {
+    let row = connection.query_row("SELECT 1 FROM schema_version WHERE version = ?", &[Value::Integer(version)], &[Value::Integer(0)])
+        .map_err(|_| DatabaseError::QueryError("Failed to read the schema_version table".to_string()))?;
+    Ok(row.is_some())
+}
+
+fn record(migration: &Migration, connection: &dyn Backend) -> Result<(), DatabaseError> {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|time| time.as_secs() as i64).unwrap_or(0);
+    connection.execute("INSERT INTO schema_version (version, name, applied_at) VALUES (?, ?, ?)", &[
+        Value::Integer(migration.version),
+        Value::String(migration.name.to_string()),
+        Value::Integer(now),
+    ]).map_err(|_| DatabaseError::QueryError("Failed to record the migration".to_string()))
+}
+
+
+#[cfg(test)]
+mod test {
+    use crate::server::databases::{backend::Backend, sqlite::pool::SqliteConnection};
+
+    #[test]
+    fn test_migrate_applies_each_migration_once() {
+        let connection = SqliteConnection::new(sqlite::open(":memory:").unwrap());
+
+        assert_eq!(super::pending(&connection).unwrap().len(), super::MIGRATIONS.len());
+        assert_eq!(super::migrate(&connection).unwrap().len(), super::MIGRATIONS.len());
+        assert!(super::pending(&connection).unwrap().is_empty());
+        assert!(super::migrate(&connection).unwrap().is_empty());
+
+        // the migrated database holds the tables the server uses
+        connection.execute_batch("INSERT INTO supplier (name) VALUES ('Acme')").unwrap();
+        connection.execute_batch("SELECT version FROM supplier; SELECT * FROM search_index; SELECT * FROM idempotency_keys").unwrap();
+    }
+}
@@ -0,0 +1,279 @@
+use crate::errors::{DatabaseError, FieldError};
+use crate::server::api::query_types::{Query, Content, ApiVersion, FieldSelection};
+use crate::server::databases::data_structs::Value;
+
+use std::collections::HashMap;
+use json::JsonValue;
+use crate::server::databases::{
+    backend::{Database, Backend, transaction},
+    tables,
+    get_processing::{supplier_to_json, versioned_payload},
+    versions::{check_precondition, bump_supplier, bump_address, bump_rep},
+    util::{SupplierLinks, supplier_links, supply_rep_contact_id, execute_with_values, remove_contact_details, remove_unused_address, remove_unused_rep},
+    post_processing::{
+        validate_supplier, extract_json_to_table, insert_address, insert_contact,
+        insert_representative, update_supplier_address_id, update_supplier_rep_id,
+    },
+    config::data_keys,
+};
+
+// The fields of a supplier which are set by the database, and so can not be patched
+const READ_ONLY_FIELDS: [&str; 4] = [data_keys::ID, data_keys::ADDRESS_ID, data_keys::CONTACT_ID, data_keys::REP_ID];
+
+///
+/// Applies a json merge patch (RFC 7396) to a resource, and returns a json response holding
+/// the updated resource in the shape used by the requested version of the api. `if_match` must
+/// hold the resource's current ETag, see versions::check_precondition.
+///
+pub fn process_query(query: Query, patch: JsonValue, version: ApiVersion, if_match: Option<&str>, database: &Database) -> Result<JsonValue, DatabaseError> {
+    database.connect(|connection| {
+        match query {
+            Query::PATCHSupplier(id) => {
+                validate_patch(&patch)?;
+
+                // the supplier is read and written within the one transaction, so the patch is
+                // applied to the supplier as it is when it is written
+                transaction(connection, || {
+                    match supplier_to_json(id, connection)? {
+                        Some(supplier) => {
+                            check_precondition(&query, if_match, connection)?;
+                            patch_supplier(id as i64, &supplier, &patch, connection)
+                        },
+                        None => Err(DatabaseError::NotFound(format!("There is no supplier with the id {}", id))),
+                    }
+                })?;
+
+                let mut supplier = match supplier_to_json(id, connection)? {
+                    Some(supplier) => supplier,
+                    None => return Err(DatabaseError::NotFound(format!("There is no supplier with the id {}", id))),
+                };
+                versioned_payload(&Query::GETSupplierFromId(id, FieldSelection::default()), &mut supplier, version);
+
+                Ok(json::object!{
+                    "code" => 200,
+                    "success" => true,
+                    "payload" => supplier,
+                })
+            },
+            _ => Err(DatabaseError::SubmissionError("Invalid query type".to_string())),
+        }
+    })
+}
+
+// A supplier patch must be an object, and may not change any of the ids held by the supplier
+fn validate_patch(patch: &JsonValue) -> Result<(), DatabaseError> {
+    if !patch.is_object() {
+        let errors = vec![FieldError::new("", "invalid_type", "A merge patch must be a json object")];
+        return Err(DatabaseError::ValidationError("The merge patch is not valid".to_string(), errors));
+    }
+
+    let errors: Vec<FieldError> = READ_ONLY_FIELDS.iter()
+        .filter(|field| patch.has_key(field))
+        .map(|field| FieldError::new(field, "read_only", &format!("{} can not be changed", field)))
+        .collect();
+
+    if !errors.is_empty() {
+        let detail = format!("{} field(s) of the merge patch are not valid", errors.len());
+        return Err(DatabaseError::ValidationError(detail, errors));
+    }
+    Ok(())
+}
+
+///
+/// Applies a merge patch to a json value as described by RFC 7396. Members of the patch
+/// which are null are removed from the target, objects are merged member by member, and
+/// any other value, arrays included, replaces the value held by the target.
+///
+pub fn merge_patch(target: &JsonValue, patch: &JsonValue) -> JsonValue {
+    if !patch.is_object() {
+        return patch.clone();
+    }
+
+    let mut merged = if target.is_object() { target.clone() } else { json::object!{} };
+    for (key, value) in patch.entries() {
+        if value.is_null() {
+            merged.remove(key);
+        } else {
+            merged[key] = merge_patch(&merged[key], value);
+        }
+    }
+    merged
+}
+
+///
+/// Merges the patch into the supplier, checks the merged supplier is still valid, and then
+/// writes the parts of the supplier named by the patch to the database. Removing the address
+/// or rep of the supplier deletes it unless another supplier makes use of it, and any contact
+/// details given replace those held.
+///
+pub fn patch_supplier(id: i64, supplier: &JsonValue, patch: &JsonValue, connection: &dyn Backend) -> Result<(), DatabaseError> {
+    let merged = merge_patch(supplier, patch);
+    validate_supplier(&merged, "", connection)?;
+
+    let SupplierLinks { address_id, contact_id, rep_id } = supplier_links(id, connection)?;
+
+    // fields left out of the merged supplier keep the value held by the database
+    if patch.has_key(data_keys::NAME) || patch.has_key(data_keys::ACTIVE) {
+        let supplier_table = tables::post_tables(Query::POSTSupplier(Content::None));
+        let values = extract_json_to_table(&merged, supplier_table, "")?;
+        let mut columns: Vec<&str> = Vec::new();
+        let mut column_values: Vec<Value> = Vec::new();
+
+        for (key, column) in [(data_keys::NAME, "name"), (data_keys::ACTIVE, "active")] {
+            if let Some(value) = values.get(key) {
+                columns.push(column);
+                column_values.push(value.clone());
+            }
+        }
+        column_values.push(Value::Integer(id));
+
+        let sql = format!("UPDATE supplier SET {} WHERE id = ?", set_clause(&columns));
+        execute_with_values(&sql, &column_values, "Failed to update supplier", connection)?;
+    }
+
+    if patch.has_key(data_keys::ADDRESS) {
+        let address = &merged[data_keys::ADDRESS];
+        match address_id {
+            Some(address_id) if address.is_null() => {
+                execute_with_values("UPDATE supplier SET fk_address = NULL WHERE id = ?", &[Value::Integer(id)], "Failed to remove supplier address", connection)?;
+                remove_unused_address(address_id, connection)?;
+            },
+            Some(address_id) => {
+                let address_table = tables::post_tables(Query::POSTAddress(Content::None));
+                let values = extract_json_to_table(address, address_table, data_keys::ADDRESS)?;
+                let mut column_values = table_values(&values, &[
+                    data_keys::ADDRESS_LINE1, data_keys::ADDRESS_LINE2, data_keys::ADDRESS_TOWN,
+                    data_keys::ADDRESS_COUNCIL, data_keys::ADDRESS_POSTCODE,
+                ]);
+                column_values.push(Value::Integer(address_id));
+
+                let sql = format!("UPDATE address SET {} WHERE id = ?", set_clause(&["Line1", "Line2", "Town", "Council", "Postcode"]));
+                execute_with_values(&sql, &column_values, "Failed to update supplier address", connection)?;
+                bump_address(address_id, connection)?;
+            },
+            None if address.is_null() => {},
+            None => {
+                let address_id = insert_address(Query::POSTAddress(Content::None), address, connection)?;
+                update_supplier_address_id(id, address_id, connection)?;
+            },
+        }
+    }
+
+    if patch.has_key(data_keys::CONTACT) {
+        replace_contact(&patch[data_keys::CONTACT], &merged[data_keys::CONTACT], contact_id, connection)?;
+    }
+
+    if patch.has_key(data_keys::REP) {
+        let rep = &merged[data_keys::REP];
+        match rep_id {
+            Some(rep_id) if rep.is_null() => {
+                execute_with_values("UPDATE supplier SET fk_supply_rep = NULL WHERE id = ?", &[Value::Integer(id)], "Failed to remove supplier representative", connection)?;
+                remove_unused_rep(rep_id, connection)?;
+            },
+            Some(rep_id) => {
+                update_representative(rep_id, rep, data_keys::REP, connection)?;
+
+                if patch[data_keys::REP].has_key(data_keys::CONTACT) {
+                    let rep_contact_id = supply_rep_contact_id(rep_id, connection)?;
+                    replace_contact(&patch[data_keys::REP][data_keys::CONTACT], &rep[data_keys::CONTACT], rep_contact_id, connection)?;
+                }
+            },
+            None if rep.is_null() => {},
+            None => {
+                let rep_id = insert_representative(Query::POSTRep(Content::None), rep, connection)?;
+                update_supplier_rep_id(id, rep_id, connection)?;
+
+                if !rep[data_keys::CONTACT].is_null() {
+                    let rep_contact_id = supply_rep_contact_id(rep_id, connection)?;
+                    insert_contact(&rep[data_keys::CONTACT], rep_contact_id, connection)?;
+                }
+            },
+        }
+    }
+
+    // every change to a supplier, including its contact details, gives the supplier a new version
+    bump_supplier(id, connection)
+}
+
+///
+/// Writes the title and names of a rep, which has already been validated, to the rep's row,
+/// and gives the rep a new version. `path` is the path to the rep within the submitted json.
+///
+pub fn update_representative(rep_id: i64, rep: &JsonValue, path: &str, connection: &dyn Backend) -> Result<(), DatabaseError> {
+    let rep_table = tables::post_tables(Query::POSTRep(Content::None));
+    let values = extract_json_to_table(rep, rep_table, path)?;
+    let mut column_values = table_values(&values, &[data_keys::TITLE, data_keys::FIRST_NAME, data_keys::LAST_NAME]);
+    column_values.push(Value::Integer(rep_id));
+
+    // the title is held in its own table, so is stored as the id of that title
+    let sql = r"UPDATE supply_rep SET fk_person_title = (SELECT id FROM person_title WHERE title = ?),
+        FirstName = ?, LastName = ? WHERE id = ?";
+    execute_with_values(sql, &column_values, "Failed to update supplier representative", connection)?;
+    bump_rep(rep_id, connection)
+}
+
+///
+/// Replaces the email addresses and phone numbers named by a contact patch with those of the
+/// merged contact, a null patch replaces all of the contact details.
+///
+pub fn replace_contact(patch: &JsonValue, merged: &JsonValue, contact_id: Option<i64>, connection: &dyn Backend) -> Result<(), DatabaseError> {
+    let contact_id = match contact_id {
+        Some(id) => id,
+        None if merged.is_null() => return Ok(()),
+        None => return Err(DatabaseError::SubmissionError("No contact record to add the contact details to".to_string())),
+    };
+
+    for key in [data_keys::EMAIL, data_keys::NUMBER] {
+        if !patch.is_null() && !patch.has_key(key) {
+            continue;
+        }
+        remove_contact_details(key, contact_id, connection)?;
+
+        if !merged[key].is_null() {
+            insert_contact(&json::object!{ key => merged[key].clone() }, Some(contact_id), connection)?;
+        }
+    }
+    Ok(())
+}
+
+// the values of the given fields in order, a field without a value is null
+fn table_values(values: &HashMap<String, Value>, fields: &[&str]) -> Vec<Value> {
+    fields.iter().map(|field| values.get(*field).cloned().unwrap_or(Value::Null)).collect()
+}
+
+fn set_clause(columns: &[&str]) -> String {
+    columns.iter().map(|column| format!("{} = ?", column)).collect::<Vec<String>>().join(", ")
+}
+
+
+#[cfg(test)]
+mod test {
+
+    #[test]
+    fn test_merge_patch() {
+        let target = json::object!{
+            "title" => "Goodbye!",
+            "author" => json::object!{ "givenName" => "John", "familyName" => "Doe" },
+            "tags" => json::array!["example", "sample"],
+            "content" => "This will be unchanged",
+        };
+        let patch = json::object!{
+            "title" => "Hello!",
+            "phoneNumber" => "+01-123-456-7890",
+            "author" => json::object!{ "familyName" => json::JsonValue::Null },
+            "tags" => json::array!["example"],
+        };
+        let expected = json::object!{
+            "title" => "Hello!",
+            "author" => json::object!{ "givenName" => "John" },
+            "tags" => json::array!["example"],
+            "content" => "This will be unchanged",
+            "phoneNumber" => "+01-123-456-7890",
+        };
+        assert_eq!(super::merge_patch(&target, &patch), expected);
+
+        // a patch which is not an object replaces the target
+        assert_eq!(super::merge_patch(&json::object!{ "a" => "b" }, &json::array!["c"]), json::array!["c"]);
+        assert_eq!(super::merge_patch(&json::array!["a"], &json::object!{ "a" => "b" }), json::object!{ "a" => "b" });
+    }
+}
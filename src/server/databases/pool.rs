@@ -0,0 +1,92 @@
+use std::ops::{Deref, DerefMut};
+use std::sync::{Condvar, Mutex};
+use std::time::Duration;
+
+use crate::errors::DatabaseError;
+use crate::config::DB_POOL_WAIT_SECS;
+
+///
+/// A fixed number of connections to the database, opened together and lent out to requests
+/// in turn. A request waits for a connection when all of them are in use.
+///
+pub struct Pool<C> {
+    connections: Mutex<Vec<C>>,
+    returned: Condvar,
+}
+
+impl<C> Pool<C> {
+    pub fn new(connections: Vec<C>) -> Pool<C> {
+        Pool {
+            connections: Mutex::new(connections),
+            returned: Condvar::new(),
+        }
+    }
+
+    /// Borrows a connection, which goes back to the pool when it is dropped
+    pub fn get(&self) -> Result<PooledConnection<'_, C>, DatabaseError> {
+        let error = || DatabaseError::ConnectionError("No database connection was free".to_string());
+
+        let connections = self.connections.lock().map_err(|_| error())?;
+        let wait = Duration::from_secs(DB_POOL_WAIT_SECS);
+        let (mut connections, _) = self.returned.wait_timeout_while(connections, wait, |connections| connections.is_empty())
+            .map_err(|_| error())?;
+
+        match connections.pop() {
+            Some(connection) => Ok(PooledConnection { connection: Some(connection), pool: self }),
+            None => Err(error()),
+        }
+    }
+
+    // the number of connections which are not lent out
+    #[cfg(test)]
+    pub fn free(&self) -> usize {
+        self.connections.lock().unwrap().len()
+    }
+}
+
+/// A connection borrowed from a pool
+pub struct PooledConnection<'p, C> {
+    connection: Option<C>,
+    pool: &'p Pool<C>,
+}
+
+impl<C> Deref for PooledConnection<'_, C> {
+    type Target = C;
+
+    fn deref(&self) -> &C {
+        self.connection.as_ref().unwrap()
+    }
+}
+
+impl<C> DerefMut for PooledConnection<'_, C> {
+    fn deref_mut(&mut self) -> &mut C {
+        self.connection.as_mut().unwrap()
+    }
+}
+
+impl<C> Drop for PooledConnection<'_, C> {
+    fn drop(&mut self) {
+        if let (Some(connection), Ok(mut connections)) = (self.connection.take(), self.pool.connections.lock()) {
+            connections.push(connection);
+            self.pool.returned.notify_one();
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+
+    #[test]
+    fn test_pool_lends_each_connection_once() {
+        let pool = super::Pool::new(vec![1, 2]);
+        let first = pool.get().unwrap();
+        let second = pool.get().unwrap();
+        assert_ne!(*first, *second);
+        assert_eq!(pool.free(), 0);
+        drop(first);
+        assert_eq!(pool.free(), 1);
+        drop(second);
+        assert_eq!(pool.free(), 2);
+    }
+}
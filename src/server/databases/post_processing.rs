@@ -0,0 +1,558 @@
+use crate::errors::{DatabaseError, FieldError};
+use crate::server::api::query_types::{
+    Query, Content, BatchMode};
+use crate::server::databases::data_structs::{DBTableStruct, DbFieldStruct, Value};
+
+use std::collections::HashMap;
+use json::JsonValue;
+use crate::config::MAX_BATCH_SIZE;
+use crate::server::databases::{
+    backend::{Database, Backend, transaction, savepoint},
+    util::{BoundSql, execute_with_values, supplier_links, supply_rep_contact_id},
+    tables,
+    post_sql_queries,
+    config::data_keys,
+    
+};
+
+// use crate::server::databases::tables;
+
+pub fn process_query(query: Query, body_content: JsonValue, database: &Database) -> Result<JsonValue, DatabaseError> {
+    database.connect(|connection| {
+        let mut json_object = json::object!{
+            "code": 201,
+            "success": false,
+        };
+
+        match query {
+            Query::POSTSupplier(_) => {
+
+                // check the whole of the supplier, including any address, contact and rep details, 
+                // before anything is added to the database
+                validate_supplier(&body_content, "", connection)?;
+
+                // the supplier and its related rows are added as a unit, so a failure part way 
+                // through leaves nothing of the supplier behind
+                let supplier_id = transaction(connection, || insert_supplier(&body_content, connection))?;
+
+                json_object["success"] = json::JsonValue::Boolean(true);
+                json_object["payload"] = json::object!{ data_keys::ID => supplier_id };
+            },
+
+            Query::POSTSuppliersBatch(mode) => {
+                json_object = insert_supplier_batch(mode, &body_content, connection)?;
+            },
+
+            Query::POSTAddress(_) => {
+                insert_address(query, &body_content, connection)?;
+                json_object["success"] = json::JsonValue::Boolean(true);
+                json_object["message"] = json::JsonValue::String("Insertion success".to_string());
+            }
+            _ => {
+                return Err(DatabaseError::SubmissionError("Invalid query type".to_string()));
+            }
+        }
+
+        Ok(json_object)
+    })
+}
+
+///
+/// Checks a submitted supplier against the tables it will be stored in, along with its
+/// address, contact details and rep if they are given. Every field at fault is reported,
+/// `path` is the path to the supplier within the submitted json, e.g. "[2]" in a batch.
+///
+pub fn validate_supplier(body_content: &JsonValue, path: &str, connection: &dyn Backend) -> Result<(), DatabaseError> {
+    let mut errors: Vec<FieldError> = Vec::new();
+    let join = |key: &str| if path.is_empty() { key.to_string() } else { format!("{}.{}", path, key) };
+
+    let supplier_table = tables::post_tables(Query::POSTSupplier(Content::None));
+    collect_field_errors(extract_json_to_table(body_content, supplier_table, path), &mut errors)?;
+
+    if body_content.is_object() {
+        if !body_content[data_keys::ADDRESS].is_null() {
+            let address_table = tables::post_tables(Query::POSTAddress(Content::None));
+            let result = extract_json_to_table(&body_content[data_keys::ADDRESS], address_table, &join(data_keys::ADDRESS));
+            collect_field_errors(result, &mut errors)?;
+        }
+
+        validate_contact(&body_content[data_keys::CONTACT], &join(data_keys::CONTACT), &mut errors);
+
+        if !body_content[data_keys::REP].is_null() {
+            let rep_path = join(data_keys::REP);
+            let rep_table = tables::post_tables(Query::POSTRep(Content::None));
+            collect_field_errors(extract_json_to_table(&body_content[data_keys::REP], rep_table, &rep_path), &mut errors)?;
+            validate_title(&body_content[data_keys::REP], &rep_path, &mut errors, connection)?;
+            validate_contact(&body_content[data_keys::REP][data_keys::CONTACT], &format!("{}.{}", rep_path, data_keys::CONTACT), &mut errors);
+        }
+    }
+
+    if !errors.is_empty() {
+        let detail = format!("{} field(s) of the submitted data are not valid", errors.len());
+        return Err(DatabaseError::ValidationError(detail, errors));
+    }
+    Ok(())
+}
+
+// keeps the field errors of a validation error, any other error is passed on
+fn collect_field_errors<T>(result: Result<T, DatabaseError>, errors: &mut Vec<FieldError>) -> Result<(), DatabaseError> {
+    match result {
+        Ok(_) => Ok(()),
+        Err(DatabaseError::ValidationError(_, mut field_errors)) => {
+            errors.append(&mut field_errors);
+            Ok(())
+        },
+        Err(error) => Err(error),
+    }
+}
+
+///
+/// Checks the title of a rep is one of those held by the person_title table, as the rep is
+/// stored with the id of its title. A title which is missing or not a string is left to
+/// the checks made against the rep table.
+///
+pub fn validate_title(rep: &JsonValue, path: &str, errors: &mut Vec<FieldError>, connection: &dyn Backend) -> Result<(), DatabaseError> {
+    let title = match rep[data_keys::TITLE].as_str() {
+        Some(title) => title,
+        None => return Ok(()),
+    };
+
+    let mut structure = DBTableStruct::new();
+    structure.fields.push(DbFieldStruct::new(0, data_keys::TITLE, Value::String(String::new()), true));
+    let titles: Vec<String> = connection.query("SELECT title FROM person_title ORDER BY id", &[], structure)?
+        .rows.iter().map(|row| row.cells[0].to_text()).collect();
+
+    if !titles.iter().any(|known| known == title) {
+        let field = if path.is_empty() { data_keys::TITLE.to_string() } else { format!("{}.{}", path, data_keys::TITLE) };
+        errors.push(FieldError::new(&field, "invalid_value", &format!("{} must be one of {}", field, titles.join(", "))));
+    }
+    Ok(())
+}
+
+// contact details are optional, but each email address and phone number must be a string
+pub fn validate_contact(contact: &JsonValue, path: &str, errors: &mut Vec<FieldError>) {
+    if contact.is_null() {
+        return;
+    }
+    if !contact.is_object() {
+        errors.push(FieldError::new(path, "invalid_type", &format!("{} must be an object", path)));
+        return;
+    }
+    for key in [data_keys::EMAIL, data_keys::NUMBER] {
+        let field = format!("{}.{}", path, key);
+        let value = &contact[key];
+        let valid = value.is_null() || value.is_string() || (value.is_array() && value.members().all(|x| x.is_string()));
+        if !valid {
+            errors.push(FieldError::new(&field, "invalid_type", &format!("{} must be a string or an array of strings", field)));
+        }
+    }
+}
+
+///
+/// Adds a supplier, which has already been validated, to the database along with its address,
+/// contact details and rep. Returns the id of the new supplier. It is called within a transaction,
+/// so a supplier which can not be added in full leaves nothing behind.
+///
+pub fn insert_supplier(body_content: &JsonValue, connection: &dyn Backend) -> Result<i64, DatabaseError> {
+
+    // get the table structure of the supplier table which matches the sqlite db table
+    let supplier_table = tables::post_tables(Query::POSTSupplier(Content::None));
+    
+    // get the values needed to insert and entry into the supplier table
+    let value_map = extract_json_to_table(body_content, supplier_table, "")?;
+
+    // using those values build the SQL insert statement
+    let sql_insert_supplier = post_sql_queries::post_sql(Query::POSTSupplier(Content::None), &value_map);
+
+    // execute the SQL statement, which gives the id of the new supplier
+    let supplier_id = match sql_insert_supplier {
+        Some(sql) => sql.insert("Failed to insert supplier", connection)?,
+        None => {
+            return Err(DatabaseError::SubmissionError("Invalid SQL statement, could not add supplier".to_string()));
+        }
+    };
+
+    // the contact row is created by the database when the supplier is added, so may be missing
+    // from a database without that trigger
+    let contact_id = supplier_links(supplier_id, connection)?.contact_id;
+
+    if body_content[data_keys::ADDRESS] != JsonValue::Null {
+        let address_id = insert_address(Query::POSTAddress(Content::None), &body_content[data_keys::ADDRESS], connection)?;
+        update_supplier_address_id(supplier_id, address_id, connection)?;
+    }
+
+    if body_content[data_keys::CONTACT] != JsonValue::Null {
+        insert_contact(&body_content[data_keys::CONTACT], contact_id, connection)?;
+    }
+
+    if body_content[data_keys::REP] != JsonValue::Null {
+        let rep_id = insert_representative(Query::POSTRep(Content::None), &body_content[data_keys::REP], connection)?;
+        update_supplier_rep_id(supplier_id, rep_id, connection)?;
+
+        if body_content[data_keys::REP][data_keys::CONTACT] != JsonValue::Null {
+            let rep_contact_id = supply_rep_contact_id(rep_id, connection)?;
+            insert_contact(&body_content[data_keys::REP][data_keys::CONTACT], rep_contact_id, connection)?;
+        }
+    }
+
+    Ok(supplier_id)
+}
+
+///
+/// Adds each supplier of a batch, reporting the outcome for every item. In atomic mode the 
+/// batch is rejected if any item is not valid, and nothing is stored if any item fails to be 
+/// added. In best effort mode each item is added on its own, and only those which failed are
+/// left out.
+///
+fn insert_supplier_batch(mode: BatchMode, body_content: &JsonValue, connection: &dyn Backend) -> Result<JsonValue, DatabaseError> {
+
+    if !body_content.is_array() {
+        let errors = vec![FieldError::new("", "invalid_type", "A batch must be an array of suppliers")];
+        return Err(DatabaseError::ValidationError("The batch is not valid".to_string(), errors));
+    }
+    if body_content.len() > MAX_BATCH_SIZE {
+        let message = format!("A batch can hold at most {} suppliers", MAX_BATCH_SIZE);
+        let errors = vec![FieldError::new("", "too_many_items", &message)];
+        return Err(DatabaseError::ValidationError("The batch is not valid".to_string(), errors));
+    }
+
+    // validate every item before anything is added
+    let mut results: Vec<Result<i64, DatabaseError>> = body_content.members().enumerate()
+        .map(|(i, supplier)| validate_supplier(supplier, &format!("[{}]", i), connection).map(|_| 0))
+        .collect();
+
+    if mode == BatchMode::Atomic {
+        let mut errors: Vec<FieldError> = Vec::new();
+        for result in results.iter_mut() {
+            if let Err(DatabaseError::ValidationError(_, field_errors)) = result {
+                errors.append(field_errors);
+            }
+        }
+        if !errors.is_empty() {
+            let detail = format!("{} field(s) of the batch are not valid, no suppliers were added", errors.len());
+            return Err(DatabaseError::ValidationError(detail, errors));
+        }
+    }
+
+    // the batch is added within a single transaction, with a savepoint around each item so 
+    // that a failed item leaves nothing of itself behind
+    transaction(connection, || {
+        for (i, supplier) in body_content.members().enumerate() {
+            if results[i].is_err() {
+                continue;
+            }
+            results[i] = savepoint(connection, "batch_item", || insert_supplier(supplier, connection));
+
+            if let (Err(error), BatchMode::Atomic) = (&results[i], mode) {
+                let message = format!("Supplier [{}] could not be added, no suppliers were added: {}", i, error.message());
+                return Err(match error {
+                    DatabaseError::ConnectionError(_) => DatabaseError::ConnectionError(message),
+                    DatabaseError::QueryError(_) => DatabaseError::QueryError(message),
+                    _ => DatabaseError::SubmissionError(message),
+                });
+            }
+        }
+        Ok(())
+    })?;
+
+    // report the outcome of each item
+    let mut payload = JsonValue::new_array();
+    let mut created = 0;
+    for (i, result) in results.iter().enumerate() {
+        let item = match result {
+            Ok(id) => {
+                created += 1;
+                json::object!{ "index" => i, "success" => true, data_keys::ID => *id }
+            },
+            Err(error) => {
+                let mut errors = JsonValue::new_array();
+                for field_error in error.field_errors() {
+                    errors.push(field_error.to_json()).unwrap();
+                }
+                json::object!{
+                    "index" => i,
+                    "success" => false,
+                    "status" => error.status(),
+                    "detail" => error.message().clone(),
+                    "errors" => errors,
+                }
+            }
+        };
+        payload.push(item).unwrap();
+    }
+
+    Ok(json::object!{
+        "code" => 200,
+        "success" => created == results.len(),
+        "meta" => json::object!{
+            "mode" => mode.name(),
+            "total" => results.len(),
+            "created" => created,
+            "failed" => results.len() - created,
+        },
+        "payload" => payload,
+    })
+}
+
+// Adds an address, returning its id
+pub fn insert_address(query: Query, body_content: &JsonValue, connection: &dyn Backend) -> Result<i64, DatabaseError> {
+
+    let address_table = tables::post_tables(query);
+    let address_values = extract_json_to_table(body_content, address_table, data_keys::ADDRESS)?;
+    savepoint(connection, "insert_address", || insert_address_values(&address_values, connection))
+}
+
+fn insert_address_values(address_values: &HashMap<String, Value>, connection: &dyn Backend) -> Result<i64, DatabaseError> {
+
+    let sql_insert_address = post_sql_queries::post_sql(Query::POSTAddress(Content::None), address_values);
+
+    // insert the address details into the database
+    if let Some(sql) = sql_insert_address {
+        sql.insert("Failed to insert supplier address", connection)
+    } else {
+        Err(DatabaseError::SubmissionError("Invalid SQL statement, could not add address info".to_string()))
+    }
+
+}
+
+pub fn update_supplier_address_id(supplier_id: i64, address_id: i64, connection: &dyn Backend) -> Result<(), DatabaseError> {
+
+    // SQL statement to update the supplier table with the address id
+    let sql_statement = "UPDATE supplier SET fk_address = ? WHERE id = ?";
+    let values = [Value::Integer(address_id), Value::Integer(supplier_id)];
+    execute_with_values(sql_statement, &values, "Failed to update supplier address", connection)
+}
+
+// Adds the email addresses and phone numbers of a contact, each may be a single string or an array of them
+pub fn insert_contact(contact: &JsonValue, contact_id: Option<i64>, connection: &dyn Backend) -> Result<(), DatabaseError> {
+    if contact[data_keys::EMAIL].is_null() && contact[data_keys::NUMBER].is_null() {
+        return Ok(());
+    }
+    let contact_id = match contact_id {
+        Some(id) => id,
+        None => return Err(DatabaseError::SubmissionError("No contact record to add the contact details to".to_string())),
+    };
+
+    savepoint(connection, "insert_contact", || {
+        if contact[data_keys::EMAIL] != JsonValue::Null {
+            let email_map = insert_email_addresses(Query::POSTContactEmails(Content::None), contact, connection)?;
+            insert_email_contact(email_map, &contact_id, connection)?;
+        }
+        if contact[data_keys::NUMBER] != JsonValue::Null {
+            let numbers_map = insert_phone_numbers(Query::POSTContactPhoneNumbers(Content::None), contact, connection)?;
+            insert_phone_contact(numbers_map, &contact_id, connection)?;
+        }
+        Ok(())
+    })
+}
+
+// the string, or each string of an array, held by a json value
+fn json_strings(value: &JsonValue) -> Result<Vec<String>, DatabaseError> {
+    if let Some(value) = value.as_str() {
+        return Ok(vec![value.to_string()]);
+    }
+    if value.is_array() {
+        let mut strings = Vec::new();
+        for member in value.members() {
+            match member.as_str() {
+                Some(string) => strings.push(string.to_string()),
+                None => return Err(DatabaseError::SubmissionError("Expected an array of strings".to_string())),
+            }
+        }
+        return Ok(strings);
+    }
+    Err(DatabaseError::SubmissionError("Expected a string or an array of strings".to_string()))
+}
+
+fn insert_email_addresses(query: Query, body_content: &JsonValue, connection: &dyn Backend) -> Result<HashMap<i64, String>, DatabaseError> {
+    if !body_content.has_key(data_keys::EMAIL) {
+        return Err(DatabaseError::SubmissionError("No contact data".to_string()));
+    }
+    
+    // there is a chance that more than one contact email address will be submitted, each 
+    // is added in turn so the id given to it by the database is known, even if the same
+    // address has been added before
+    let mut email_map = HashMap::new();
+    for email in json_strings(&body_content[data_keys::EMAIL])? {
+        let mut emails: HashMap<String, Value> = HashMap::new();
+        emails.insert(0.to_string(), Value::String(email.clone()));
+
+        // submit email to the database 
+        let sql_statement = post_sql_queries::post_sql(query.clone(), &emails);
+
+        let id = match sql_statement {
+            Some(sql) => sql.insert("Failed to insert email addresses", connection)?,
+            None => return Err(DatabaseError::SubmissionError("Invalid SQL statement, could not add email info".to_string())),
+        };
+        email_map.insert(id, email);
+    }
+    Ok(email_map)
+}
+
+fn insert_email_contact(email_map: HashMap<i64, String>, contact_id: &i64, connection: &dyn Backend) -> Result<(), DatabaseError> {
+    let sql = link_contact_sql("INSERT INTO contact_email (fk_email_addresses, fk_contact) VALUES ", email_map.keys(), *contact_id);
+    execute_with_values(&sql.sql, &sql.values, "Failed to insert email contact", connection)
+}
+
+fn insert_phone_numbers(query: Query, body_content: &JsonValue, connection: &dyn Backend) -> Result<HashMap<i64, String>, DatabaseError> {
+    if !body_content.has_key(data_keys::NUMBER) {
+        return Err(DatabaseError::SubmissionError("No contact data".to_string()));
+    }
+    
+    // there is a chance that more than one contact phone number will be submitted, each 
+    // is added in turn so the id given to it by the database is known
+    let mut number_map = HashMap::new();
+    for number in json_strings(&body_content[data_keys::NUMBER])? {
+        let mut numbers: HashMap<String, Value> = HashMap::new();
+        numbers.insert(0.to_string(), Value::String(number.clone()));
+
+        // submit number to the database
+        let sql_statement = post_sql_queries::post_sql(query.clone(), &numbers);
+
+        let id = match sql_statement {
+            Some(sql) => sql.insert("Failed to insert phone numbers", connection)?,
+            None => return Err(DatabaseError::SubmissionError("Invalid SQL statement, could not add phone number info".to_string())),
+        };
+        number_map.insert(id, number);
+    }
+    Ok(number_map)
+
+}
+
+fn insert_phone_contact(number_map: HashMap<i64, String>, contact_id: &i64, connection: &dyn Backend) -> Result<(), DatabaseError> {
+    let sql = link_contact_sql("INSERT INTO contact_phone (fk_phone_number, fk_contact) VALUES ", number_map.keys(), *contact_id);
+    execute_with_values(&sql.sql, &sql.values, "Failed to insert phone contact", connection)
+}
+
+// links each of the ids to a contact, with a row of two parameters for each id
+fn link_contact_sql<'a>(insert: &str, ids: impl Iterator<Item = &'a i64>, contact_id: i64) -> BoundSql {
+    let mut values = Vec::new();
+    for id in ids {
+        values.push(Value::Integer(*id));
+        values.push(Value::Integer(contact_id));
+    }
+    let sql = format!("{}{}", insert, vec!["(?, ?)"; values.len() / 2].join(", "));
+    BoundSql::new(&sql, values)
+}
+
+// Adds a rep, returning its id
+pub fn insert_representative(query: Query, body_content: &JsonValue, connection: &dyn Backend) -> Result<i64, DatabaseError> {
+    let rep_table = tables::post_tables(query);
+    let rep_values = extract_json_to_table(body_content, rep_table, data_keys::REP)?;
+    savepoint(connection, "insert_representative", || insert_representative_values(&rep_values, connection))
+}
+
+fn insert_representative_values(rep_values: &HashMap<String, Value>, connection: &dyn Backend) -> Result<i64, DatabaseError> {
+
+    let sql_insert_rep = post_sql_queries::post_sql(Query::POSTRep(Content::None), rep_values);
+
+    // insert the address details into the database
+    if let Some(sql) = sql_insert_rep {
+        sql.insert("Failed to insert representative information", connection)
+    } else {
+        Err(DatabaseError::SubmissionError("Invalid SQL statement, could not add representative info".to_string()))
+    }
+
+}
+
+pub fn update_supplier_rep_id(supplier_id: i64, rep_id: i64, connection: &dyn Backend) -> Result<(), DatabaseError> {
+
+    // SQL statement to update the supplier table with the rep id
+    let sql_statement = "UPDATE supplier SET fk_supply_rep = ? WHERE id = ?";
+    let values = [Value::Integer(rep_id), Value::Integer(supplier_id)];
+    execute_with_values(sql_statement, &values, "Failed to update supplier representative", connection)
+}
+
+
+// booleans are also accepted as 0 or 1, the way they are stored by the database
+fn as_boolean(value: &JsonValue) -> Option<bool> {
+    match value.as_i64() {
+        Some(0) => Some(false),
+        Some(1) => Some(true),
+        _ => value.as_bool(),
+    }
+}
+
+
+
+
+// uses a json object to populate a hashmap that represents a the value for a entry on a SQL table.
+// Every field which is missing or has the wrong type is reported, named by its path from the root
+// of the submitted object, so `path` is the path to `data` e.g. "address", or "" for the root.
+pub fn extract_json_to_table(data: &JsonValue, table_ref: DBTableStruct, path: &str) -> Result<HashMap<String, Value>, DatabaseError> {
+
+
+    let mut table = HashMap::new();
+    let mut errors: Vec<FieldError> = Vec::new();
+
+    if !data.is_object() {
+        let field = if path.is_empty() { "" } else { path };
+        errors.push(FieldError::new(field, "invalid_type", "Expected a json object"));
+        return Err(DatabaseError::ValidationError("The submitted data is not valid".to_string(), errors));
+    }
+    
+    // loop through the fields in the table structure, they hold the expected type and name of a SQL field
+    for field in table_ref.fields {
+        let field_name = field.name.clone();
+        let field_path = if path.is_empty() { field_name.clone() } else { format!("{}.{}", path, field_name) };
+
+        // check if the json object has a valid field 
+        if !data.has_key(&field_name) || data[&field_name].is_null() {
+            if field.not_null {
+                errors.push(FieldError::new(&field_path, "required", &format!("{} is required", field_path)));
+            } else if data.has_key(&field_name) {
+                table.insert(field_name, Value::Null);
+            }
+            continue;
+        }
+
+        // get the value of the field from the json object and parse it into the expected type
+        let value = &data[&field_name];
+        let (parsed, type_name) = match field.field_type {
+            Value::Integer(_) => (value.as_i64().map(Value::Integer), "an integer"),
+            Value::String(_) => (value.as_str().map(|x| Value::String(x.to_string())), "a string"),
+            Value::Binary(_) => (value.as_str().map(|x| Value::Binary(x.as_bytes().to_vec())), "a string"),
+            Value::Boolean(_) => (as_boolean(value).map(Value::Boolean), "a boolean"),
+            Value::Float(_) => (value.as_f64().map(Value::Float), "a number"),
+            Value::Null => (Some(Value::Null), "null"),
+        };
+
+        match parsed {
+            Some(parsed) => {
+                table.insert(field_name, parsed);
+            },
+            None => {
+                errors.push(FieldError::new(&field_path, "invalid_type", &format!("{} must be {}", field_path, type_name)));
+            }
+        }
+    } 
+
+    if !errors.is_empty() {
+        let detail = format!("{} field(s) of the submitted data are not valid", errors.len());
+        return Err(DatabaseError::ValidationError(detail, errors));
+    }
+    Ok(table)   
+}
+
+
+#[cfg(test)]
+mod test {
+    use crate::server::databases::tables;
+    use crate::server::databases::config::data_keys;
+
+    #[test]
+    fn test_extract_json_names_invalid_fields() {
+        let address = json::object!{ data_keys::ADDRESS_LINE1 => 12, data_keys::ADDRESS_TOWN => "Glasgow" };
+        let error = match super::extract_json_to_table(&address, tables::address_table(), data_keys::ADDRESS) {
+            Err(error) => error,
+            Ok(_) => panic!("invalid address was accepted"),
+        };
+
+        let errors = error.field_errors();
+        let line1 = format!("{}.{}", data_keys::ADDRESS, data_keys::ADDRESS_LINE1);
+        let postcode = format!("{}.{}", data_keys::ADDRESS, data_keys::ADDRESS_POSTCODE);
+        assert!(errors.iter().any(|x| x.field == line1 && x.code == "invalid_type"));
+        assert!(errors.iter().any(|x| x.field == postcode && x.code == "required"));
+        assert!(!errors.iter().any(|x| x.field.ends_with(data_keys::ADDRESS_TOWN)));
+    }
+}
@@ -0,0 +1,207 @@
+use crate::server::{api::query_types::Query, databases::data_structs::Value};
+use std::collections::HashMap;
+use crate::server::databases::{config::data_keys, util::BoundSql};
+
+
+///
+/// The insert statement for a query, built from the values to be inserted. Every value is bound
+/// to a parameter of the statement, so none are written into the SQL. Each statement returns 
+/// the id of every row it adds.
+///
+pub fn post_sql(query: Query, values: &HashMap<String, Value>) -> Option<BoundSql> {
+    match query {
+        Query::POSTSupplier(_) => {
+
+            let name = values.get(data_keys::NAME).expect("No name field in post query");
+            let name = if let Value::String(n) = name  {
+                n
+            } else {
+                return None;
+            };
+
+            // the database gives a supplier without an active value the default of active
+            let sql = match values.get(data_keys::ACTIVE) {
+                Some(Value::Boolean(active)) => {
+                    BoundSql::new("INSERT INTO supplier (name, active) VALUES (?, ?) RETURNING id", vec![
+                        Value::String(name.clone()), 
+                        Value::Boolean(*active),
+                    ])
+                },
+                Some(Value::Null) | None => {
+                    BoundSql::new("INSERT INTO supplier (name) VALUES (?) RETURNING id", vec![Value::String(name.clone())])
+                },
+                Some(_) => return None,
+            };
+            Some(sql)
+        },
+        Query::POSTAddress(_) => {
+
+            let mut address_values: Vec<Value> = Vec::new();
+            for key in [
+                data_keys::ADDRESS_LINE1, 
+                data_keys::ADDRESS_LINE2, 
+                data_keys::ADDRESS_TOWN, 
+                data_keys::ADDRESS_COUNCIL, 
+                data_keys::ADDRESS_POSTCODE,
+            ] {
+                match values.get(key) {
+                    Some(Value::String(a)) => address_values.push(Value::String(a.clone())),
+                    _ => address_values.push(Value::Null),
+                }
+            }
+
+            // TODO: this check might be placed before this function is called
+            if address_values[0] == Value::Null || address_values[2] == Value::Null || address_values[4] == Value::Null {
+                return None;
+            }
+
+            let sql = "INSERT INTO address (Line1, Line2, Town, Council, Postcode) VALUES (?, ?, ?, ?, ?) RETURNING id";
+            Some(BoundSql::new(sql, address_values))
+
+        }
+        Query::POSTContactEmails(_) => {
+            strings_insert("INSERT INTO emails (Email) VALUES ", values)
+        },
+        Query::POSTContactPhoneNumbers(_) => {
+            strings_insert("INSERT INTO phone_numbers (Number) VALUES ", values)
+        },
+        Query::POSTRep(_) => {
+
+            let mut rep_values: Vec<Value> = Vec::new();
+            for key in [data_keys::TITLE, data_keys::FIRST_NAME, data_keys::LAST_NAME] {
+                if let Some(Value::String(value)) = values.get(key) {
+                    rep_values.push(Value::String(value.clone()));
+                } else {
+                    return None;
+                }
+            }
+
+            // the title is held in its own table, so is stored as the id of that title
+            let sql = r"INSERT INTO supply_rep (fk_person_title, FirstName, LastName) 
+                VALUES ((SELECT id FROM person_title WHERE title = ?), ?, ?) RETURNING id";
+            Some(BoundSql::new(sql, rep_values))
+        },
+        _ => {
+            panic!("Invalid query type passed to post_query");
+        }
+    }
+
+}
+
+// Inserts a row for each string value, the values are keyed by their position so are bound in that order
+fn strings_insert(insert: &str, values: &HashMap<String, Value>) -> Option<BoundSql> {
+    let mut keyed: Vec<(usize, &Value)> = Vec::new();
+    for (key, value) in values {
+        match (key.parse::<usize>(), value) {
+            (Ok(position), Value::String(_)) => keyed.push((position, value)),
+            _ => return None,
+        }
+    }
+    if keyed.is_empty() {
+        return None;
+    }
+    keyed.sort_by_key(|(position, _)| *position);
+
+    let sql = format!("{}{} RETURNING id", insert, vec!["(?)"; keyed.len()].join(", "));
+    Some(BoundSql::new(&sql, keyed.into_iter().map(|(_, value)| value.clone()).collect()))
+}
+
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+    use crate::server::api::query_types::{Query, Content};
+    use crate::server::databases::{backend::Backend, data_structs::Value, config::data_keys, util::BoundSql, get_sql_queries, sqlite::pool::SqliteConnection};
+    use sqlite::State;
+
+    // values which broke, or could be used to change, statements which had them written in
+    const HOSTILE: [&str; 6] = [
+        "O'Brien & Sons",
+        "'; DROP TABLE supplier; --",
+        "x' OR '1'='1",
+        "Robert\"); DELETE FROM emails; --",
+        ":name $1 ?1 @x",
+        "line\nbreak \\ 'quoted' ''",
+    ];
+
+    const TABLES: &str = r"
+        CREATE TABLE supplier (id INTEGER PRIMARY KEY, name TEXT NOT NULL, active INTEGER NOT NULL DEFAULT 1);
+        CREATE TABLE address (id INTEGER PRIMARY KEY, Line1 TEXT, Line2 TEXT, Town TEXT, Council TEXT, Postcode TEXT);
+        CREATE TABLE emails (id INTEGER PRIMARY KEY, Email TEXT);
+        CREATE TABLE phone_numbers (id INTEGER PRIMARY KEY, Number TEXT);
+        CREATE TABLE person_title (id INTEGER PRIMARY KEY, title TEXT);
+        CREATE TABLE supply_rep (id INTEGER PRIMARY KEY, fk_person_title INTEGER, FirstName TEXT, LastName TEXT);
+    ";
+
+    fn strings(pairs: &[(&str, &str)]) -> HashMap<String, Value> {
+        pairs.iter().map(|(key, value)| (key.to_string(), Value::String(value.to_string()))).collect()
+    }
+
+    // every statement built from a hostile value, which must only ever be bound
+    fn statements(hostile: &str) -> Vec<BoundSql> {
+        let supplier = strings(&[(data_keys::NAME, hostile)]);
+        let address = strings(&[
+            (data_keys::ADDRESS_LINE1, hostile),
+            (data_keys::ADDRESS_TOWN, hostile),
+            (data_keys::ADDRESS_POSTCODE, hostile),
+        ]);
+        let contact = strings(&[("0", hostile), ("1", "second")]);
+        let rep = strings(&[(data_keys::TITLE, hostile), (data_keys::FIRST_NAME, hostile), (data_keys::LAST_NAME, hostile)]);
+
+        vec![
+            super::post_sql(Query::POSTSupplier(Content::None), &supplier).unwrap(),
+            super::post_sql(Query::POSTAddress(Content::None), &address).unwrap(),
+            super::post_sql(Query::POSTContactEmails(Content::None), &contact).unwrap(),
+            super::post_sql(Query::POSTContactPhoneNumbers(Content::None), &contact).unwrap(),
+            super::post_sql(Query::POSTRep(Content::None), &rep).unwrap(),
+        ]
+    }
+
+    fn count(sql: &str, connection: &sqlite::Connection) -> i64 {
+        let mut statement = connection.prepare(sql).unwrap();
+        assert_eq!(statement.next().unwrap(), State::Row);
+        statement.read::<i64, _>(0).unwrap()
+    }
+
+    #[test]
+    fn test_hostile_values_are_bound() {
+        for hostile in HOSTILE {
+            for statement in statements(hostile) {
+                assert!(!statement.sql.contains(hostile), "{:?} was written into {}", hostile, statement.sql);
+                assert!(statement.values.contains(&Value::String(hostile.to_string())));
+                assert_eq!(statement.sql.matches('?').count(), statement.values.len());
+            }
+        }
+    }
+
+    #[test]
+    fn test_hostile_values_are_stored_as_given() {
+        let connection = SqliteConnection::new(sqlite::open(":memory:").unwrap());
+        connection.execute_batch(TABLES).unwrap();
+
+        for (i, hostile) in HOSTILE.iter().enumerate() {
+            for statement in statements(hostile) {
+                connection.execute(&statement.sql, &statement.values).unwrap();
+            }
+
+            // the supplier can be found by its name, which is bound in the same way
+            let sql = get_sql_queries::get_sql(&Query::GETSupplierIdFromName(hostile.to_string()));
+            let found = connection.query_row(&sql.sql, &sql.values, &[Value::Integer(0)]).unwrap();
+            assert_eq!(found, Some(vec![Value::Integer(i as i64 + 1)]));
+        }
+
+        // each statement added only its own rows, and no table was dropped or emptied
+        let rows = HOSTILE.len() as i64;
+        assert_eq!(count("SELECT COUNT(*) FROM supplier", &connection), rows);
+        assert_eq!(count("SELECT COUNT(*) FROM address", &connection), rows);
+        assert_eq!(count("SELECT COUNT(*) FROM emails", &connection), rows * 2);
+        assert_eq!(count("SELECT COUNT(*) FROM phone_numbers", &connection), rows * 2);
+        assert_eq!(count("SELECT COUNT(*) FROM supply_rep", &connection), rows);
+
+        let mut names = connection.prepare("SELECT name FROM supplier ORDER BY id").unwrap();
+        for hostile in HOSTILE {
+            assert_eq!(names.next().unwrap(), State::Row);
+            assert_eq!(names.read::<String, _>(0).unwrap(), hostile);
+        }
+    }
+}
@@ -0,0 +1,328 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use crate::errors::DatabaseError;
+use crate::server::databases::{
+    backend::{Backend, column_structure},
+    data_structs::{DBTable, DBTableStruct, DBTableRow, DatabaseColumn, Type, Value},
+    pool::Pool,
+};
+use postgres::{Client, NoTls, Row, Statement, types::{ToSql, Type as PgType}};
+
+// Transactions which write take this lock as they begin, so that as with SQLite only one
+// transaction writes at a time, and rows read within it can not be changed by another
+const WRITE_LOCK: i64 = 0x706f73;
+
+///
+/// A connection to a PostgreSQL database, which keeps the statements it prepares. A prepared
+/// statement is owned by the connection, so the statements are kept for as long as the
+/// connection is open.
+///
+pub struct PostgresConnection {
+    client: RefCell<Client>,
+    statements: RefCell<HashMap<String, Statement>>,
+}
+
+impl PostgresConnection {
+    pub fn open(url: &str) -> Result<PostgresConnection, DatabaseError> {
+        let client = Client::connect(url, NoTls).map_err(|error| {
+            println!("Error: {}", error);
+            DatabaseError::ConnectionError("Failed to connect to db".to_string())
+        })?;
+        Ok(PostgresConnection {
+            client: RefCell::new(client),
+            statements: RefCell::new(HashMap::new()),
+        })
+    }
+
+    // prepares a statement written with "?" parameters, or takes it from the cache
+    fn prepare_cached(&self, sql: &str) -> Result<Statement, DatabaseError> {
+        if let Some(statement) = self.statements.borrow().get(sql) {
+            return Ok(statement.clone());
+        }
+        let statement = self.client.borrow_mut().prepare(&numbered_parameters(sql))
+            .map_err(|_| DatabaseError::QueryError("Failed to prepare sql statement".to_string()))?;
+        self.statements.borrow_mut().insert(sql.to_string(), statement.clone());
+        Ok(statement)
+    }
+
+    // runs a statement with the values converted to the types of its parameters
+    fn run(&self, sql: &str, values: &[Value]) -> Result<(Statement, Vec<Row>), DatabaseError> {
+        let statement = self.prepare_cached(sql)?;
+        if statement.params().len() != values.len() {
+            return Err(DatabaseError::QueryError("Failed to bind value to sql statement".to_string()));
+        }
+        let params = values.iter().zip(statement.params())
+            .map(|(value, param)| to_sql(value, param))
+            .collect::<Result<Vec<_>, _>>()?;
+        let params: Vec<&(dyn ToSql + Sync)> = params.iter().map(|param| param.as_ref()).collect();
+
+        let rows = self.client.borrow_mut().query(&statement, &params).map_err(|error| statement_error(&error))?;
+        Ok((statement, rows))
+    }
+}
+
+impl Backend for PostgresConnection {
+    fn database_type(&self) -> Type {
+        Type::Postgres
+    }
+
+    fn query(&self, sql: &str, values: &[Value], structure: DBTableStruct) -> Result<DBTable, DatabaseError> {
+        let (statement, rows) = self.run(sql, values)?;
+        if statement.columns().len() != structure.fields.len() {
+            return Err(DatabaseError::QueryError("Number of columns in the statement does not match the number of fields in the db table row".to_string()));
+        }
+
+        let mut table = DBTable::new(&structure);
+        for row in rows {
+            let mut table_row = DBTableRow::new();
+            for field in &structure.fields {
+                // as with SQLite, a null is kept whether or not the field may be null
+                let value = read_cell(&row, field.index)?;
+                table_row.add_cell(cell_as(value, &field.field_type));
+            }
+            table.add_row(table_row);
+        }
+        Ok(table)
+    }
+
+    fn execute(&self, sql: &str, values: &[Value]) -> Result<(), DatabaseError> {
+        self.run(sql, values).map(|_| ())
+    }
+
+    fn execute_batch(&self, sql: &str) -> Result<(), DatabaseError> {
+        self.client.borrow_mut().batch_execute(sql)
+            .map_err(|_| DatabaseError::QueryError("Failed to execute sql statements".to_string()))
+    }
+
+    fn begin(&self) -> Result<(), DatabaseError> {
+        self.execute_batch(&format!("BEGIN; SELECT pg_advisory_xact_lock({});", WRITE_LOCK))
+    }
+
+    fn commit(&self) -> Result<(), DatabaseError> {
+        self.execute_batch("COMMIT")
+    }
+
+    fn rollback(&self) -> Result<(), DatabaseError> {
+        self.execute_batch("ROLLBACK")
+    }
+
+    fn columns(&self, source: &str) -> Result<Vec<DatabaseColumn>, DatabaseError> {
+        let sql = r"SELECT a.attname::text, format_type(a.atttypid, a.atttypmod), a.attnotnull
+            FROM pg_attribute AS a
+            WHERE a.attrelid = to_regclass(?) AND a.attnum > 0 AND NOT a.attisdropped
+            ORDER BY a.attnum";
+        let rows = self.query(sql, &[Value::String(source.to_string())], column_structure())?;
+        Ok(rows.rows.into_iter().map(|row| {
+            let declared_type = row.cells[1].to_text();
+            DatabaseColumn {
+                name: row.cells[0].to_text(),
+                field_type: declared_type_value(&declared_type),
+                declared_type,
+                not_null: row.cells[2] == Value::Boolean(true),
+            }
+        }).collect())
+    }
+}
+
+/// Opens a pool of connections to the database
+pub fn open(url: &str, size: usize) -> Result<Pool<PostgresConnection>, DatabaseError> {
+    let mut connections = Vec::with_capacity(size);
+    for _ in 0..size {
+        connections.push(PostgresConnection::open(url)?);
+    }
+    Ok(Pool::new(connections))
+}
+
+///
+/// Numbers the "?" parameters of a statement as PostgreSQL expects, e.g. "$1", a parameter
+/// already numbered such as "?1" becomes "$1". A "?" within a string, a quoted name or a
+/// comment is left as it is.
+///
+pub fn numbered_parameters(sql: &str) -> String {
+    let mut numbered = String::with_capacity(sql.len());
+    let mut chars = sql.chars().peekable();
+    let mut count = 0;
+
+    while let Some(c) = chars.next() {
+        numbered.push(c);
+        match c {
+            // a numbered parameter, e.g. "?1", keeps its number
+            '?' if chars.peek().is_some_and(|next| next.is_ascii_digit()) => {
+                numbered.pop();
+                numbered.push('$');
+            },
+            '?' => {
+                numbered.pop();
+                count += 1;
+                numbered.push_str(&format!("${}", count));
+            },
+            '\'' | '"' => {
+                for next in chars.by_ref() {
+                    numbered.push(next);
+                    if next == c {
+                        break;
+                    }
+                }
+            },
+            '-' if chars.peek() == Some(&'-') => {
+                for next in chars.by_ref() {
+                    numbered.push(next);
+                    if next == '\n' {
+                        break;
+                    }
+                }
+            },
+            _ => {},
+        }
+    }
+    numbered
+}
+
+// Converts a value to the type of the parameter it is bound to, e.g. an integer bound to a
+// boolean column, as SQLite stores booleans as integers
+fn to_sql(value: &Value, param: &PgType) -> Result<Box<dyn ToSql + Sync>, DatabaseError> {
+    let error = || DatabaseError::QueryError(format!("A value can not be bound to a parameter of type {}", param));
+    let integer = match value {
+        Value::Integer(value) => Some(*value),
+        Value::Boolean(value) => Some(*value as i64),
+        _ => None,
+    };
+
+    let converted: Box<dyn ToSql + Sync> = match (param.clone(), value) {
+        (_, Value::Null) => null_of(param),
+        (PgType::BOOL, _) => Box::new(integer.ok_or_else(error)? != 0),
+        (PgType::INT8, _) => Box::new(integer.ok_or_else(error)?),
+        (PgType::INT4, _) => Box::new(i32::try_from(integer.ok_or_else(error)?).map_err(|_| error())?),
+        (PgType::INT2, _) => Box::new(i16::try_from(integer.ok_or_else(error)?).map_err(|_| error())?),
+        (PgType::FLOAT8, Value::Float(value)) => Box::new(*value),
+        (PgType::FLOAT4, Value::Float(value)) => Box::new(*value as f32),
+        (PgType::FLOAT8, _) => Box::new(integer.ok_or_else(error)? as f64),
+        (PgType::FLOAT4, _) => Box::new(integer.ok_or_else(error)? as f32),
+        (PgType::BYTEA, Value::Binary(value)) => Box::new(value.clone()),
+        (PgType::BYTEA, _) => return Err(error()),
+        (_, value) => Box::new(value.to_text()),
+    };
+    Ok(converted)
+}
+
+// A statement refused by a constraint of the database, one of the integrity constraint violations
+// of class 23, was given data which is not valid, so is a SubmissionError, while any other failure
+// is a QueryError
+fn statement_error(error: &postgres::Error) -> DatabaseError {
+    match error.code() {
+        Some(state) if state.code().starts_with("23") => DatabaseError::SubmissionError("The data breaks a constraint of the database".to_string()),
+        _ => DatabaseError::QueryError("Failed to execute sql statement".to_string()),
+    }
+}
+
+// a null of the type of a parameter
+fn null_of(param: &PgType) -> Box<dyn ToSql + Sync> {
+    match *param {
+        PgType::BOOL => Box::new(None::<bool>),
+        PgType::INT8 => Box::new(None::<i64>),
+        PgType::INT4 => Box::new(None::<i32>),
+        PgType::INT2 => Box::new(None::<i16>),
+        PgType::FLOAT8 => Box::new(None::<f64>),
+        PgType::FLOAT4 => Box::new(None::<f32>),
+        PgType::BYTEA => Box::new(None::<Vec<u8>>),
+        _ => Box::new(None::<String>),
+    }
+}
+
+// reads a cell as the value of its column's type
+fn read_cell(row: &Row, index: usize) -> Result<Value, DatabaseError> {
+    let error = |_| DatabaseError::QueryError("Failed to read a value returned by the database".to_string());
+    let column_type = row.columns()[index].type_().clone();
+
+    let value = match column_type {
+        PgType::BOOL => row.try_get::<_, Option<bool>>(index).map_err(error)?.map(Value::Boolean),
+        PgType::INT8 => row.try_get::<_, Option<i64>>(index).map_err(error)?.map(Value::Integer),
+        PgType::INT4 => row.try_get::<_, Option<i32>>(index).map_err(error)?.map(|value| Value::Integer(value as i64)),
+        PgType::INT2 => row.try_get::<_, Option<i16>>(index).map_err(error)?.map(|value| Value::Integer(value as i64)),
+        PgType::FLOAT8 => row.try_get::<_, Option<f64>>(index).map_err(error)?.map(Value::Float),
+        PgType::FLOAT4 => row.try_get::<_, Option<f32>>(index).map_err(error)?.map(|value| Value::Float(value as f64)),
+        PgType::BYTEA => row.try_get::<_, Option<Vec<u8>>>(index).map_err(error)?.map(Value::Binary),
+        _ => row.try_get::<_, Option<String>>(index).map_err(error)?.map(Value::String),
+    };
+    Ok(value.unwrap_or(Value::Null))
+}
+
+// The type a column's values are read as, as read_cell reads them
+fn declared_type_value(declared_type: &str) -> Value {
+    match declared_type {
+        "boolean" => Value::Boolean(false),
+        "bigint" | "integer" | "smallint" => Value::Integer(0),
+        "double precision" | "real" => Value::Float(0.0),
+        "bytea" => Value::Binary(Vec::new()),
+        _ => Value::String(String::new()),
+    }
+}
+
+// Converts a cell to the type of its field, in the way the SQLite backend does, so a boolean
+// column read into an integer field is 0 or 1. A cell which can not be converted is null.
+fn cell_as(value: Value, field_type: &Value) -> Value {
+    match (field_type, value) {
+        (Value::Null, _) => Value::Null,
+        (Value::Boolean(_), Value::Boolean(value)) => Value::Boolean(value),
+        (Value::Boolean(_), Value::Integer(value)) => Value::Boolean(value != 0),
+        (Value::Integer(_), Value::Integer(value)) => Value::Integer(value),
+        (Value::Integer(_), Value::Boolean(value)) => Value::Integer(value as i64),
+        (Value::Float(_), Value::Float(value)) => Value::Float(value),
+        (Value::Float(_), Value::Integer(value)) => Value::Float(value as f64),
+        (Value::String(_), Value::String(value)) => Value::String(value),
+        (Value::Binary(_), Value::Binary(value)) => Value::Binary(value),
+        (Value::Binary(_), Value::String(value)) => Value::Binary(value.into_bytes()),
+        _ => Value::Null,
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use crate::server::databases::{backend::Backend, data_structs::Value, migrations};
+
+    #[test]
+    fn test_numbered_parameters() {
+        assert_eq!(super::numbered_parameters("SELECT id FROM supplier WHERE name = ? AND id > ?"),
+            "SELECT id FROM supplier WHERE name = $1 AND id > $2");
+        assert_eq!(super::numbered_parameters("SELECT '?', \"a?\" -- why?\nFROM t WHERE a = ?"),
+            "SELECT '?', \"a?\" -- why?\nFROM t WHERE a = $1");
+        assert_eq!(super::numbered_parameters("DELETE FROM contact WHERE id = ?1 AND id != ?1"),
+            "DELETE FROM contact WHERE id = $1 AND id != $1");
+    }
+
+    // Runs against the PostgreSQL database named by POS_TEST_POSTGRES_URL, so it is only run when
+    // asked for, e.g. POS_TEST_POSTGRES_URL="host=localhost user=postgres dbname=pos_test" cargo
+    // test -- --ignored. The database should be empty, its tables are dropped once the test ends.
+    #[test]
+    #[ignore]
+    fn test_postgres_backend() {
+        let url = std::env::var("POS_TEST_POSTGRES_URL").expect("POS_TEST_POSTGRES_URL must hold the connection string of the database");
+        let connection = super::PostgresConnection::open(&url).unwrap();
+        connection.execute_batch("DROP SCHEMA public CASCADE; CREATE SCHEMA public;").unwrap();
+        assert_eq!(migrations::migrate(&connection).unwrap().len(), migrations::POSTGRES_MIGRATIONS.len());
+        assert!(migrations::pending(&connection).unwrap().is_empty());
+
+        let id = connection.query_row("INSERT INTO supplier (name, active) VALUES (?, ?) RETURNING id",
+            &[Value::String("Acme's ? Ltd".to_string()), Value::Integer(0)], &[Value::Integer(0)]).unwrap();
+        assert_eq!(id, Some(vec![Value::Integer(1)]));
+
+        let row = connection.query_row("SELECT name, active, active, fk_address FROM supplier WHERE id = ?", &[Value::Integer(1)],
+            &[Value::String(String::new()), Value::Integer(0), Value::Boolean(false), Value::Integer(0)]).unwrap();
+        assert_eq!(row, Some(vec![Value::String("Acme's ? Ltd".to_string()), Value::Integer(0), Value::Boolean(false), Value::Null]));
+
+        // the contact row is added as the supplier is, and a failed transaction leaves nothing behind
+        let row = connection.query_row("SELECT COUNT(*) FROM contact", &[], &[Value::Integer(0)]).unwrap();
+        assert_eq!(row, Some(vec![Value::Integer(1)]));
+        let result = crate::server::databases::backend::transaction(&connection, || {
+            connection.execute("INSERT INTO supplier (name) VALUES (?)", &[Value::String("Bolt".to_string())])?;
+            connection.execute("INSERT INTO supplier (name) VALUES (?)", &[Value::String("Bolt".to_string())])
+        });
+        assert!(result.is_err());
+        let row = connection.query_row("SELECT COUNT(*) FROM supplier", &[], &[Value::Integer(0)]).unwrap();
+        assert_eq!(row, Some(vec![Value::Integer(1)]));
+
+        connection.execute_batch("DROP SCHEMA public CASCADE; CREATE SCHEMA public;").unwrap();
+    }
+}
@@ -0,0 +1,180 @@
+-- The tables, views, row versions, search index and idempotency keys of a PostgreSQL database,
+-- which hold the same data as those built up by the SQLite migrations. Foreign keys are checked
+-- as a transaction commits, as they are for SQLite.
+
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+
+CREATE TABLE IF NOT EXISTS contact (
+    id BIGINT GENERATED BY DEFAULT AS IDENTITY PRIMARY KEY
+);
+
+CREATE TABLE IF NOT EXISTS address (
+    id BIGINT GENERATED BY DEFAULT AS IDENTITY PRIMARY KEY,
+    Line1 TEXT NOT NULL,
+    Line2 TEXT,
+    Town TEXT NOT NULL,
+    Council TEXT,
+    Postcode TEXT NOT NULL,
+    version BIGINT NOT NULL DEFAULT 1
+);
+
+CREATE TABLE IF NOT EXISTS person_title (
+    id BIGINT GENERATED BY DEFAULT AS IDENTITY PRIMARY KEY,
+    title TEXT NOT NULL UNIQUE
+);
+
+INSERT INTO person_title (title) VALUES ('Mr'), ('Mrs'), ('Miss'), ('Ms'), ('Dr') ON CONFLICT (title) DO NOTHING;
+
+CREATE TABLE IF NOT EXISTS supply_rep (
+    id BIGINT GENERATED BY DEFAULT AS IDENTITY PRIMARY KEY,
+    fk_person_title BIGINT REFERENCES person_title(id) DEFERRABLE INITIALLY DEFERRED,
+    FirstName TEXT NOT NULL,
+    LastName TEXT NOT NULL,
+    fk_contact BIGINT REFERENCES contact(id) DEFERRABLE INITIALLY DEFERRED,
+    version BIGINT NOT NULL DEFAULT 1
+);
+
+CREATE TABLE IF NOT EXISTS supplier (
+    id BIGINT GENERATED BY DEFAULT AS IDENTITY PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE,
+    active BOOLEAN NOT NULL DEFAULT TRUE,
+    fk_address BIGINT REFERENCES address(id) DEFERRABLE INITIALLY DEFERRED,
+    fk_contact BIGINT REFERENCES contact(id) DEFERRABLE INITIALLY DEFERRED,
+    fk_supply_rep BIGINT REFERENCES supply_rep(id) DEFERRABLE INITIALLY DEFERRED,
+    version BIGINT NOT NULL DEFAULT 1
+);
+
+CREATE TABLE IF NOT EXISTS emails (
+    id BIGINT GENERATED BY DEFAULT AS IDENTITY PRIMARY KEY,
+    Email TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS phone_numbers (
+    id BIGINT GENERATED BY DEFAULT AS IDENTITY PRIMARY KEY,
+    Number TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS contact_email (
+    fk_email_addresses BIGINT NOT NULL REFERENCES emails(id) DEFERRABLE INITIALLY DEFERRED,
+    fk_contact BIGINT NOT NULL REFERENCES contact(id) DEFERRABLE INITIALLY DEFERRED
+);
+
+CREATE TABLE IF NOT EXISTS contact_phone (
+    fk_phone_number BIGINT NOT NULL REFERENCES phone_numbers(id) DEFERRABLE INITIALLY DEFERRED,
+    fk_contact BIGINT NOT NULL REFERENCES contact(id) DEFERRABLE INITIALLY DEFERRED
+);
+
+CREATE TABLE IF NOT EXISTS supply_categories (
+    id BIGINT GENERATED BY DEFAULT AS IDENTITY PRIMARY KEY,
+    Type TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS supplier_supplies (
+    fk_supplier BIGINT NOT NULL REFERENCES supplier(id) DEFERRABLE INITIALLY DEFERRED,
+    fk_supply_category BIGINT NOT NULL REFERENCES supply_categories(id) DEFERRABLE INITIALLY DEFERRED
+);
+
+-- suppliers and reps are each given a row of their own in the contact table as they are added
+CREATE OR REPLACE FUNCTION add_contact() RETURNS trigger AS $$
+BEGIN
+    IF NEW.fk_contact IS NULL THEN
+        INSERT INTO contact DEFAULT VALUES RETURNING id INTO NEW.fk_contact;
+    END IF;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE TRIGGER supplier_contact BEFORE INSERT ON supplier
+    FOR EACH ROW EXECUTE FUNCTION add_contact();
+CREATE OR REPLACE TRIGGER supply_rep_contact BEFORE INSERT ON supply_rep
+    FOR EACH ROW EXECUTE FUNCTION add_contact();
+
+CREATE OR REPLACE VIEW view_suppliers AS
+    SELECT * FROM supplier;
+
+CREATE OR REPLACE VIEW view_suppliers_email AS
+    SELECT s.id AS supplierId, e.Email
+    FROM supplier AS s
+    JOIN contact_email AS ce ON ce.fk_contact = s.fk_contact
+    JOIN emails AS e ON e.id = ce.fk_email_addresses;
+
+CREATE OR REPLACE VIEW view_suppliers_numbers AS
+    SELECT s.id AS supplierId, p.Number
+    FROM supplier AS s
+    JOIN contact_phone AS cp ON cp.fk_contact = s.fk_contact
+    JOIN phone_numbers AS p ON p.id = cp.fk_phone_number;
+
+CREATE OR REPLACE VIEW view_supply_rep_email AS
+    SELECT r.id AS SupplyRepID, e.Email
+    FROM supply_rep AS r
+    JOIN contact_email AS ce ON ce.fk_contact = r.fk_contact
+    JOIN emails AS e ON e.id = ce.fk_email_addresses;
+
+CREATE OR REPLACE VIEW view_supply_rep_numbers AS
+    SELECT r.id AS SupplyRepID, p.Number
+    FROM supply_rep AS r
+    JOIN contact_phone AS cp ON cp.fk_contact = r.fk_contact
+    JOIN phone_numbers AS p ON p.id = cp.fk_phone_number;
+
+-- The search index holds a row for each supplier name, rep name, email address, phone number
+-- and postcode, matched on any part of their text through the trigram index
+CREATE TABLE IF NOT EXISTS search_index (
+    content TEXT NOT NULL,
+    entity TEXT NOT NULL,
+    entity_id BIGINT NOT NULL,
+    PRIMARY KEY (entity, entity_id)
+);
+
+CREATE INDEX IF NOT EXISTS search_index_content ON search_index USING gin (content gin_trgm_ops);
+
+-- keeps the search index in step with a table it indexes, the text indexed is given as the
+-- trigger's first argument and the type of entity as its second
+CREATE OR REPLACE FUNCTION update_search_index() RETURNS trigger AS $$
+DECLARE
+    content TEXT;
+BEGIN
+    IF TG_OP <> 'INSERT' THEN
+        DELETE FROM search_index WHERE entity = TG_ARGV[1] AND entity_id = OLD.id;
+    END IF;
+    IF TG_OP <> 'DELETE' THEN
+        EXECUTE format('SELECT ($1).%s', TG_ARGV[0]) INTO content USING NEW;
+        INSERT INTO search_index (content, entity, entity_id) VALUES (content, TG_ARGV[1], NEW.id);
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE TRIGGER search_supplier AFTER INSERT OR UPDATE OF name OR DELETE ON supplier
+    FOR EACH ROW EXECUTE FUNCTION update_search_index('name', 'supplier');
+CREATE OR REPLACE TRIGGER search_email AFTER INSERT OR UPDATE OF Email OR DELETE ON emails
+    FOR EACH ROW EXECUTE FUNCTION update_search_index('Email', 'email');
+CREATE OR REPLACE TRIGGER search_number AFTER INSERT OR UPDATE OF Number OR DELETE ON phone_numbers
+    FOR EACH ROW EXECUTE FUNCTION update_search_index('Number', 'number');
+CREATE OR REPLACE TRIGGER search_address AFTER INSERT OR UPDATE OF Postcode OR DELETE ON address
+    FOR EACH ROW EXECUTE FUNCTION update_search_index('Postcode', 'address');
+
+-- a rep is found by their full name
+CREATE OR REPLACE FUNCTION update_rep_search_index() RETURNS trigger AS $$
+BEGIN
+    IF TG_OP <> 'INSERT' THEN
+        DELETE FROM search_index WHERE entity = 'rep' AND entity_id = OLD.id;
+    END IF;
+    IF TG_OP <> 'DELETE' THEN
+        INSERT INTO search_index (content, entity, entity_id) VALUES (NEW.FirstName || ' ' || NEW.LastName, 'rep', NEW.id);
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE TRIGGER search_rep AFTER INSERT OR UPDATE OF FirstName, LastName OR DELETE ON supply_rep
+    FOR EACH ROW EXECUTE FUNCTION update_rep_search_index();
+
+-- Each key sent in an Idempotency-Key header, with a fingerprint of its request and the response given to it
+CREATE TABLE IF NOT EXISTS idempotency_keys (
+    key TEXT PRIMARY KEY,
+    fingerprint TEXT NOT NULL,
+    status_line TEXT NOT NULL,
+    content_type TEXT NOT NULL,
+    content TEXT NOT NULL,
+    created_at BIGINT NOT NULL
+);
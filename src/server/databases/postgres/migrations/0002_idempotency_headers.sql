@@ -0,0 +1,4 @@
+-- The ETag and Location headers given with a response stored against an idempotency key, held
+-- as a json array of [name, value] pairs, so that a retry is given them too.
+
+ALTER TABLE idempotency_keys ADD COLUMN headers TEXT NOT NULL DEFAULT '[]';
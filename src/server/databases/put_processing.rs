@@ -0,0 +1,180 @@
+use crate::errors::{DatabaseError, FieldError};
+use crate::server::api::query_types::{Query, Content, ApiVersion, FieldSelection};
+use crate::server::databases::data_structs::DBTableStruct;
+
+use json::JsonValue;
+use crate::server::databases::{
+    backend::{Database, Backend, transaction},
+    tables,
+    get_processing::{self, supplier_to_json},
+    post_processing::{validate_supplier, validate_contact, validate_title, extract_json_to_table},
+    patch_processing::{patch_supplier, update_representative, replace_contact},
+    util::supply_rep_contact_id,
+    versions::check_precondition,
+    config::data_keys,
+};
+
+///
+/// Replaces a stored resource with the submitted one, and returns a json response holding
+/// the resource as it is now stored, in the shape used by the requested version of the api.
+/// The submitted resource is validated as it would be for a POST request, and `if_match` must
+/// hold the stored resource's current ETag, see versions::check_precondition.
+///
+pub fn process_query(query: Query, body_content: JsonValue, version: ApiVersion, if_match: Option<&str>, database: &Database) -> Result<JsonValue, DatabaseError> {
+    database.connect(|connection| {
+        match query {
+            Query::PUTSupplier(id) => {
+                validate_id(&body_content, id, "supplier")?;
+                validate_supplier(&body_content, "", connection)?;
+
+                // a replacement is applied as a merge patch which names every part of the supplier
+                let patch = replacement_supplier(&body_content);
+                transaction(connection, || {
+                    match supplier_to_json(id, connection)? {
+                        Some(supplier) => {
+                            check_precondition(&query, if_match, connection)?;
+                            patch_supplier(id as i64, &supplier, &patch, connection)
+                        },
+                        None => Err(supplier_not_found(id)),
+                    }
+                })?;
+
+                get_processing::query_to_json(Query::GETSupplierFromId(id, FieldSelection::default()), version, connection)
+            },
+            Query::PUTSupplierAddress(id) => {
+                let address_table = tables::post_tables(Query::POSTAddress(Content::None));
+                extract_json_to_table(&body_content, address_table.clone(), "")?;
+
+                let patch = json::object!{ data_keys::ADDRESS => replacement_object(&body_content, &address_table) };
+                transaction(connection, || {
+                    match supplier_to_json(id, connection)? {
+                        Some(supplier) => {
+                            check_precondition(&query, if_match, connection)?;
+                            patch_supplier(id as i64, &supplier, &patch, connection)
+                        },
+                        None => Err(supplier_not_found(id)),
+                    }
+                })?;
+
+                get_processing::query_to_json(Query::GETSupplierAddressFromId(id), version, connection)
+            },
+            Query::PUTSupplyRep(id) => {
+                validate_id(&body_content, id, "supplier representative")?;
+                validate_rep(&body_content, connection)?;
+
+                transaction(connection, || {
+                    let contact_id = supply_rep_contact_id(id as i64, connection)?;
+                    check_precondition(&query, if_match, connection)?;
+                    update_representative(id as i64, &body_content, "", connection)?;
+                    replace_contact(&JsonValue::Null, &body_content[data_keys::CONTACT], contact_id, connection)
+                })?;
+
+                get_processing::query_to_json(Query::GETSupplyRepFromId(id), version, connection)
+            },
+            _ => Err(DatabaseError::SubmissionError("Invalid query type".to_string())),
+        }
+    })
+}
+
+fn supplier_not_found(id: u64) -> DatabaseError {
+    DatabaseError::NotFound(format!("There is no supplier with the id {}", id))
+}
+
+// the id of a replacement may be left out, but if given must be that of the resource it replaces
+fn validate_id(body_content: &JsonValue, id: u64, resource: &str) -> Result<(), DatabaseError> {
+    let given = &body_content[data_keys::ID];
+    if given.is_null() || given.as_u64() == Some(id) {
+        return Ok(());
+    }
+    let message = format!("{} does not match the id of the {}", data_keys::ID, resource);
+    let errors = vec![FieldError::new(data_keys::ID, "invalid_value", &message)];
+    Err(DatabaseError::ValidationError("The submitted data is not valid".to_string(), errors))
+}
+
+// a rep is checked against the rep table and the titles a rep can have, along with its contact
+// details if they are given
+fn validate_rep(body_content: &JsonValue, connection: &dyn Backend) -> Result<(), DatabaseError> {
+    let rep_table = tables::post_tables(Query::POSTRep(Content::None));
+    let mut errors = match extract_json_to_table(body_content, rep_table, "") {
+        Ok(_) => Vec::new(),
+        Err(DatabaseError::ValidationError(_, errors)) => errors,
+        Err(error) => return Err(error),
+    };
+    if body_content.is_object() {
+        validate_title(body_content, "", &mut errors, connection)?;
+        validate_contact(&body_content[data_keys::CONTACT], data_keys::CONTACT, &mut errors);
+    }
+
+    if !errors.is_empty() {
+        let detail = format!("{} field(s) of the submitted data are not valid", errors.len());
+        return Err(DatabaseError::ValidationError(detail, errors));
+    }
+    Ok(())
+}
+
+// The merge patch which replaces every part of a supplier with that of the submitted supplier,
+// parts which are left out are removed. A supplier is active unless stated otherwise, as it
+// is when the supplier is added.
+fn replacement_supplier(supplier: &JsonValue) -> JsonValue {
+    let mut patch = json::object!{
+        data_keys::NAME => supplier[data_keys::NAME].clone(),
+        data_keys::ACTIVE => supplier[data_keys::ACTIVE].clone(),
+        data_keys::ADDRESS => JsonValue::Null,
+        data_keys::CONTACT => replacement_contact(&supplier[data_keys::CONTACT]),
+        data_keys::REP => JsonValue::Null,
+    };
+    if patch[data_keys::ACTIVE].is_null() {
+        patch[data_keys::ACTIVE] = true.into();
+    }
+
+    if supplier[data_keys::ADDRESS].is_object() {
+        let address_table = tables::post_tables(Query::POSTAddress(Content::None));
+        patch[data_keys::ADDRESS] = replacement_object(&supplier[data_keys::ADDRESS], &address_table);
+    }
+    if supplier[data_keys::REP].is_object() {
+        let rep_table = tables::post_tables(Query::POSTRep(Content::None));
+        patch[data_keys::REP] = replacement_object(&supplier[data_keys::REP], &rep_table);
+        patch[data_keys::REP][data_keys::CONTACT] = replacement_contact(&supplier[data_keys::REP][data_keys::CONTACT]);
+    }
+    patch
+}
+
+// names every field of the table, so fields which are left out are removed by the patch
+fn replacement_object(object: &JsonValue, table: &DBTableStruct) -> JsonValue {
+    let mut replacement = json::object!{};
+    for field in &table.fields {
+        replacement[field.name.as_str()] = object[field.name.as_str()].clone();
+    }
+    replacement
+}
+
+fn replacement_contact(contact: &JsonValue) -> JsonValue {
+    if !contact.is_object() {
+        return JsonValue::Null;
+    }
+    json::object!{
+        data_keys::EMAIL => contact[data_keys::EMAIL].clone(),
+        data_keys::NUMBER => contact[data_keys::NUMBER].clone(),
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use crate::server::databases::config::data_keys;
+
+    #[test]
+    fn test_replacement_names_every_field() {
+        let supplier = json::object!{
+            data_keys::NAME => "Acme",
+            data_keys::REP => json::object!{ data_keys::TITLE => "Mr", data_keys::FIRST_NAME => "John", data_keys::LAST_NAME => "Smith" },
+        };
+        let patch = super::replacement_supplier(&supplier);
+
+        assert_eq!(patch[data_keys::ACTIVE], true);
+        assert!(patch.has_key(data_keys::ADDRESS) && patch[data_keys::ADDRESS].is_null());
+        assert!(patch.has_key(data_keys::CONTACT) && patch[data_keys::CONTACT].is_null());
+        assert_eq!(patch[data_keys::REP][data_keys::LAST_NAME], "Smith");
+        assert!(patch[data_keys::REP].has_key(data_keys::CONTACT));
+    }
+}
@@ -0,0 +1,221 @@
+use crate::errors::DatabaseError;
+use crate::server::databases::{
+    config::data_keys,
+    data_structs::Value,
+    rows::{FromRow, Row, ToRow},
+};
+
+// Records read from the tables and views of the database. Each is read from a row by the json
+// keys of its fields, see tables, and written back out as json in the same shape.
+
+/// A row of the supplier table
+#[derive(Debug, Clone, PartialEq)]
+pub struct Supplier {
+    pub id: i64,
+    pub name: String,
+    pub active: bool,
+    pub address_id: Option<i64>,
+    pub contact_id: Option<i64>,
+    pub rep_id: Option<i64>,
+}
+
+impl FromRow for Supplier {
+    fn from_row(row: &Row) -> Result<Self, DatabaseError> {
+        Ok(Supplier {
+            id: row.integer(data_keys::ID)?,
+            name: row.text(data_keys::NAME)?,
+            active: row.boolean(data_keys::ACTIVE)?,
+            address_id: row.optional_integer(data_keys::ADDRESS_ID),
+            contact_id: row.optional_integer(data_keys::CONTACT_ID),
+            rep_id: row.optional_integer(data_keys::REP_ID),
+        })
+    }
+}
+
+// active is held as 0 or 1, as it is given by version 1 of the api
+impl ToRow for Supplier {
+    fn to_row(&self) -> Vec<(&'static str, Value)> {
+        vec![
+            (data_keys::ID, Value::Integer(self.id)),
+            (data_keys::NAME, Value::String(self.name.clone())),
+            (data_keys::ACTIVE, Value::Integer(self.active as i64)),
+            (data_keys::ADDRESS_ID, optional_integer(self.address_id)),
+            (data_keys::CONTACT_ID, optional_integer(self.contact_id)),
+            (data_keys::REP_ID, optional_integer(self.rep_id)),
+        ]
+    }
+}
+
+/// A row of the address table
+#[derive(Debug, Clone, PartialEq)]
+pub struct Address {
+    pub id: i64,
+    pub line1: String,
+    pub line2: Option<String>,
+    pub town: String,
+    pub council: Option<String>,
+    pub postcode: String,
+}
+
+impl FromRow for Address {
+    fn from_row(row: &Row) -> Result<Self, DatabaseError> {
+        Ok(Address {
+            id: row.integer(data_keys::ID)?,
+            line1: row.text(data_keys::ADDRESS_LINE1)?,
+            line2: row.optional_text(data_keys::ADDRESS_LINE2),
+            town: row.text(data_keys::ADDRESS_TOWN)?,
+            council: row.optional_text(data_keys::ADDRESS_COUNCIL),
+            postcode: row.text(data_keys::ADDRESS_POSTCODE)?,
+        })
+    }
+}
+
+impl ToRow for Address {
+    fn to_row(&self) -> Vec<(&'static str, Value)> {
+        vec![
+            (data_keys::ID, Value::Integer(self.id)),
+            (data_keys::ADDRESS_LINE1, Value::String(self.line1.clone())),
+            (data_keys::ADDRESS_LINE2, optional_text(&self.line2)),
+            (data_keys::ADDRESS_TOWN, Value::String(self.town.clone())),
+            (data_keys::ADDRESS_COUNCIL, optional_text(&self.council)),
+            (data_keys::ADDRESS_POSTCODE, Value::String(self.postcode.clone())),
+        ]
+    }
+}
+
+/// A supplier's rep, with the title looked up from its id. The id is left out by queries
+/// which are given it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rep {
+    pub id: Option<i64>,
+    pub title: String,
+    pub first_name: String,
+    pub last_name: String,
+    pub contact_id: Option<i64>,
+}
+
+impl FromRow for Rep {
+    fn from_row(row: &Row) -> Result<Self, DatabaseError> {
+        Ok(Rep {
+            id: row.optional_integer(data_keys::ID),
+            title: row.text(data_keys::TITLE)?,
+            first_name: row.text(data_keys::FIRST_NAME)?,
+            last_name: row.text(data_keys::LAST_NAME)?,
+            contact_id: row.optional_integer(data_keys::CONTACT_ID),
+        })
+    }
+}
+
+impl ToRow for Rep {
+    fn to_row(&self) -> Vec<(&'static str, Value)> {
+        let mut row = Vec::new();
+        if let Some(id) = self.id {
+            row.push((data_keys::ID, Value::Integer(id)));
+        }
+        row.push((data_keys::TITLE, Value::String(self.title.clone())));
+        row.push((data_keys::FIRST_NAME, Value::String(self.first_name.clone())));
+        row.push((data_keys::LAST_NAME, Value::String(self.last_name.clone())));
+        row.push((data_keys::CONTACT_ID, optional_integer(self.contact_id)));
+        row
+    }
+}
+
+/// An email address of a supplier or rep, along with the id of the supplier or rep
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContactEmail {
+    pub id: i64,
+    pub email: String,
+}
+
+impl FromRow for ContactEmail {
+    fn from_row(row: &Row) -> Result<Self, DatabaseError> {
+        Ok(ContactEmail {
+            id: row.integer(data_keys::ID)?,
+            email: row.text(data_keys::EMAIL)?,
+        })
+    }
+}
+
+impl ToRow for ContactEmail {
+    fn to_row(&self) -> Vec<(&'static str, Value)> {
+        vec![
+            (data_keys::ID, Value::Integer(self.id)),
+            (data_keys::EMAIL, Value::String(self.email.clone())),
+        ]
+    }
+}
+
+/// A phone number of a supplier or rep, along with the id of the supplier or rep
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContactNumber {
+    pub id: i64,
+    pub number: String,
+}
+
+impl FromRow for ContactNumber {
+    fn from_row(row: &Row) -> Result<Self, DatabaseError> {
+        Ok(ContactNumber {
+            id: row.integer(data_keys::ID)?,
+            number: row.text(data_keys::NUMBER)?,
+        })
+    }
+}
+
+impl ToRow for ContactNumber {
+    fn to_row(&self) -> Vec<(&'static str, Value)> {
+        vec![
+            (data_keys::ID, Value::Integer(self.id)),
+            (data_keys::NUMBER, Value::String(self.number.clone())),
+        ]
+    }
+}
+
+fn optional_integer(value: Option<i64>) -> Value {
+    value.map(Value::Integer).unwrap_or(Value::Null)
+}
+
+fn optional_text(value: &Option<String>) -> Value {
+    value.clone().map(Value::String).unwrap_or(Value::Null)
+}
+
+
+#[cfg(test)]
+mod test {
+    use crate::server::databases::{
+        config::data_keys,
+        data_structs::{DBTable, DBTableRow, DBTableStruct, DbFieldStruct, Value},
+        rows::ToRow,
+    };
+    use super::{Rep, Supplier};
+
+    #[test]
+    fn test_rows_are_read_by_field_name() {
+        // the columns of the row are not in the order of the supplier table
+        let mut structure = DBTableStruct::new();
+        structure.fields.push(DbFieldStruct::new(0, data_keys::NAME, Value::String(String::new()), true));
+        structure.fields.push(DbFieldStruct::new(1, data_keys::REP_ID, Value::Integer(0), false));
+        structure.fields.push(DbFieldStruct::new(2, data_keys::ACTIVE, Value::Integer(0), true));
+        structure.fields.push(DbFieldStruct::new(3, data_keys::ID, Value::Integer(0), true));
+
+        let mut table = DBTable::new(&structure);
+        let mut row = DBTableRow::new();
+        for cell in [Value::String("Acme Tools".to_string()), Value::Null, Value::Integer(1), Value::Integer(7)] {
+            row.add_cell(cell);
+        }
+        table.add_row(row);
+
+        let supplier: Supplier = table.first().unwrap().unwrap();
+        assert_eq!(supplier, Supplier {
+            id: 7,
+            name: "Acme Tools".to_string(),
+            active: true,
+            address_id: None,
+            contact_id: None,
+            rep_id: None,
+        });
+        assert_eq!(supplier.to_json().dump(), r#"{"id":7,"name":"Acme Tools","active":1,"addressId":null,"contactId":null,"repId":null}"#);
+
+        // a row without a field which can not be null is not read
+        assert!(table.records::<Rep>().is_err());
+    }
+}
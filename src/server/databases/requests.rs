@@ -0,0 +1,94 @@
+use crate::server::api::query_types::{Content, Query, ApiVersion, OutputFormat};
+use crate::errors::DatabaseError;
+use crate::server::databases::{
+    backend::Database,
+    get_processing, post_processing, patch_processing, put_processing, delete_processing, versions, migrations,
+};
+use json::{self, JsonValue};
+
+
+///
+/// Readies the database for use by the server, applying the migrations it does not yet have.
+/// A database migrated by hand (see POS_AUTO_MIGRATE) is only checked, and the migrations it
+/// is missing are listed.
+///
+pub fn prepare_database(auto_migrate: bool, database: &Database) -> Result<(), DatabaseError> {
+    database.connect(|connection| {
+        if !auto_migrate {
+            for migration in migrations::pending(connection)? {
+                println!("Warning: migration {} ({}) has not been applied", migration.version, migration.name);
+            }
+            return Ok(());
+        }
+        for migration in migrations::migrate(connection)? {
+            println!("Applied migration {} ({})", migration.version, migration.name);
+        }
+        Ok(())
+    })
+}
+
+pub fn get_request(query: Query, version: ApiVersion, format: OutputFormat, database: &Database) -> Result<String, DatabaseError> {
+    match format {
+        OutputFormat::Json => {
+            let json_response = get_processing::process_query(query, version, database)?;
+            Ok(json_response.dump())
+        },
+        OutputFormat::Csv => {
+            let table = get_processing::process_table_query(query, version, database)?;
+            Ok(table.to_csv())
+        },
+        OutputFormat::Xml => {
+            let (root_name, row_name) = get_processing::xml_element_names(&query);
+            let table = get_processing::process_table_query(query, version, database)?;
+            Ok(table.to_xml(root_name, row_name))
+        },
+    }
+}
+
+pub fn post_request(query: Query, body: Content, database: &Database) -> Result<String, DatabaseError> {
+    let json_response: JsonValue;
+    match body {
+        Content::Json(content) => {
+            json_response = post_processing::process_query(query, content, database)?;
+        },
+        _ => {
+            return Err(DatabaseError::SubmissionError("Invalid body content type".to_string()));
+        }
+    }
+
+
+    Ok(json_response.dump())
+}
+
+pub fn patch_request(query: Query, body: Content, version: ApiVersion, if_match: Option<&str>, database: &Database) -> Result<String, DatabaseError> {
+    match body {
+        Content::Json(patch) => {
+            let json_response = patch_processing::process_query(query, patch, version, if_match, database)?;
+            Ok(json_response.dump())
+        },
+        _ => Err(DatabaseError::SubmissionError("Invalid body content type".to_string())),
+    }
+}
+
+pub fn put_request(query: Query, body: Content, version: ApiVersion, if_match: Option<&str>, database: &Database) -> Result<String, DatabaseError> {
+    match body {
+        Content::Json(replacement) => {
+            let json_response = put_processing::process_query(query, replacement, version, if_match, database)?;
+            Ok(json_response.dump())
+        },
+        _ => Err(DatabaseError::SubmissionError("Invalid body content type".to_string())),
+    }
+}
+
+// Deletes a resource, or restores one which was soft deleted
+pub fn delete_request(query: Query, version: ApiVersion, if_match: Option<&str>, database: &Database) -> Result<String, DatabaseError> {
+    let json_response = delete_processing::process_query(query, version, if_match, database)?;
+    Ok(json_response.dump())
+}
+
+// The ETag of the current version of the resource named by a query, if it is versioned and exists
+pub fn resource_etag(query: &Query, database: &Database) -> Result<Option<String>, DatabaseError> {
+    database.connect(|connection| {
+        Ok(versions::current_version(query, connection)?.map(versions::etag))
+    })
+}
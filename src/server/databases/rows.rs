@@ -0,0 +1,99 @@
+use crate::errors::DatabaseError;
+use crate::server::databases::data_structs::{DBTable, DBTableRow, DBTableStruct, Value};
+use json::JsonValue;
+
+/// A value read from a row of a table, see Row
+pub trait FromRow: Sized {
+    fn from_row(row: &Row) -> Result<Self, DatabaseError>;
+}
+
+/// A value written as the cells of a row, each named by the json key of its field
+pub trait ToRow {
+    fn to_row(&self) -> Vec<(&'static str, Value)>;
+
+    /// The value as a json object, holding a member for each cell of its row in order
+    fn to_json(&self) -> JsonValue {
+        let mut object = json::object!{};
+        for (key, value) in self.to_row() {
+            object[key] = value.to_json();
+        }
+        object
+    }
+}
+
+///
+/// A row of a table, whose cells are found by the json key of their field rather than by
+/// their position, so a row is read in the same way whichever columns a query selects and
+/// in whatever order.
+///
+pub struct Row<'t> {
+    structure: &'t DBTableStruct,
+    row: &'t DBTableRow,
+}
+
+impl<'t> Row<'t> {
+    pub fn new(structure: &'t DBTableStruct, row: &'t DBTableRow) -> Self {
+        Row { structure, row }
+    }
+
+    /// The cell of the named field, None if the row does not have the field
+    pub fn get(&self, key: &str) -> Option<&'t Value> {
+        let index = self.structure.fields.iter().position(|field| field.name == key)?;
+        self.row.cells.get(index)
+    }
+
+    pub fn integer(&self, key: &str) -> Result<i64, DatabaseError> {
+        self.optional_integer(key).ok_or_else(|| missing(key))
+    }
+
+    /// The integer held by the named field, None if the field is null or the row does not have it
+    pub fn optional_integer(&self, key: &str) -> Option<i64> {
+        match self.get(key) {
+            Some(Value::Integer(value)) => Some(*value),
+            Some(Value::Boolean(value)) => Some(*value as i64),
+            _ => None,
+        }
+    }
+
+    pub fn boolean(&self, key: &str) -> Result<bool, DatabaseError> {
+        match self.get(key) {
+            Some(Value::Boolean(value)) => Ok(*value),
+            Some(Value::Integer(value)) => Ok(*value != 0),
+            _ => Err(missing(key)),
+        }
+    }
+
+    pub fn text(&self, key: &str) -> Result<String, DatabaseError> {
+        self.optional_text(key).ok_or_else(|| missing(key))
+    }
+
+    /// The text held by the named field, None if the field is null or the row does not have it
+    pub fn optional_text(&self, key: &str) -> Option<String> {
+        match self.get(key) {
+            Some(Value::String(value)) => Some(value.clone()),
+            _ => None,
+        }
+    }
+}
+
+// A field which can not be null was not read, or was not of its type
+fn missing(key: &str) -> DatabaseError {
+    DatabaseError::QueryError(format!("The row read from the database has no {} value", key))
+}
+
+impl DBTable {
+    /// The row at `index`, whose cells are found by name
+    pub fn row(&self, index: usize) -> Option<Row<'_>> {
+        self.rows.get(index).map(|row| Row::new(&self.structure, row))
+    }
+
+    /// Reads each row of the table as a T
+    pub fn records<T: FromRow>(&self) -> Result<Vec<T>, DatabaseError> {
+        self.rows.iter().map(|row| T::from_row(&Row::new(&self.structure, row))).collect()
+    }
+
+    /// Reads the first row of the table as a T, None if the table has no rows
+    pub fn first<T: FromRow>(&self) -> Result<Option<T>, DatabaseError> {
+        self.row(0).map(|row| T::from_row(&row)).transpose()
+    }
+}
@@ -0,0 +1,152 @@
+use std::fmt;
+
+use crate::errors::DatabaseError;
+use crate::server::databases::{
+    backend::Backend,
+    data_structs::{DatabaseColumn, Insert, Schema, Source, Value},
+};
+
+///
+/// A difference between a declared schema and the table or view held by the database, which
+/// would make the rows read from it fail, or be read wrongly
+///
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mismatch {
+    pub source: &'static str,
+    pub detail: String,
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.source, self.detail)
+    }
+}
+
+///
+/// Compares each table and view of `schemas` with the one held by the database, returning
+/// every mismatch found. A column must exist and hold values which can be read as its type,
+/// and the columns of a table must be null where the schema allows it and nowhere else. A
+/// view is read with all of its columns, so it must have those of its schema in the same order.
+///
+pub fn check(connection: &dyn Backend, schemas: &[&Schema]) -> Result<Vec<Mismatch>, DatabaseError> {
+    let mut mismatches = Vec::new();
+
+    for schema in schemas {
+        let source = match schema.source.name() {
+            Some(source) => source,
+            None => continue,
+        };
+        let mut mismatch = |detail: String| mismatches.push(Mismatch { source, detail });
+
+        let columns = connection.columns(source)?;
+        if columns.is_empty() {
+            mismatch("does not exist".to_string());
+            continue;
+        }
+
+        for column in schema.columns {
+            let found = match columns.iter().find(|found| found.name.eq_ignore_ascii_case(column.name)) {
+                Some(found) => found,
+                None => {
+                    mismatch(format!("the column {} does not exist", column.name));
+                    continue;
+                },
+            };
+
+            // a looked up column holds the id of the row its value is read from
+            let field_type = match column.lookup {
+                Some(_) => &Value::Integer(0),
+                None => &column.field_type,
+            };
+            if !readable_as(&found.field_type, field_type) {
+                mismatch(format!("the column {} is {}, which can not be read as {}", column.name, declared_type(found), type_name(field_type)));
+            }
+
+            if matches!(schema.source, Source::Table(_)) && column.lookup.is_none() {
+                if column.not_null && !found.not_null {
+                    mismatch(format!("the column {} may be null, but is read as a value which can not be", column.name));
+                }
+                if !column.not_null && found.not_null && column.insert != Insert::Generated {
+                    mismatch(format!("the column {} can not be null, but a row may be sent without it", column.name));
+                }
+            }
+        }
+
+        if matches!(schema.source, Source::View(_)) {
+            let held: Vec<&str> = columns.iter().map(|column| column.name.as_str()).collect();
+            let declared: Vec<&str> = schema.columns.iter().map(|column| column.name).collect();
+            let same = held.len() == declared.len() && held.iter().zip(&declared).all(|(held, declared)| held.eq_ignore_ascii_case(declared));
+            if !same {
+                mismatch(format!("the view has the columns ({}), but ({}) are read from it", held.join(", "), declared.join(", ")));
+            }
+        }
+    }
+    Ok(mismatches)
+}
+
+// Whether a value held by a column can be read as a field of the given type, as the backends
+// convert an integer to a boolean or a float, a boolean to an integer and text to binary
+fn readable_as(held: &Value, field_type: &Value) -> bool {
+    matches!((field_type, held),
+        (Value::Boolean(_), Value::Boolean(_) | Value::Integer(_))
+        | (Value::Integer(_), Value::Integer(_) | Value::Boolean(_))
+        | (Value::Float(_), Value::Float(_) | Value::Integer(_))
+        | (Value::String(_), Value::String(_))
+        | (Value::Binary(_), Value::Binary(_) | Value::String(_))
+        | (Value::Null, _))
+}
+
+fn declared_type(column: &DatabaseColumn) -> &str {
+    match column.declared_type.as_str() {
+        "" => "declared without a type",
+        declared_type => declared_type,
+    }
+}
+
+fn type_name(field_type: &Value) -> &'static str {
+    match field_type {
+        Value::Boolean(_) => "a boolean",
+        Value::Binary(_) => "binary",
+        Value::Float(_) => "a float",
+        Value::Integer(_) => "an integer",
+        Value::String(_) => "text",
+        Value::Null => "null",
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use crate::server::databases::{
+        backend::Database,
+        migrations, tables::SCHEMAS,
+    };
+
+    #[test]
+    fn test_every_mismatch_is_reported() {
+        let database = Database::sqlite(":memory:", 1).unwrap();
+        database.connect(|connection| {
+            migrations::migrate(connection)?;
+
+            // a migrated database holds the tables and views as they are declared
+            assert_eq!(super::check(connection, SCHEMAS)?, vec![]);
+
+            connection.execute_batch(r"
+                DROP VIEW view_supply_rep_numbers;
+                CREATE VIEW view_supply_rep_numbers AS SELECT id AS SupplyRepID, FirstName, LastName FROM supply_rep;
+                ALTER TABLE address RENAME COLUMN Town TO City;
+                ALTER TABLE address DROP COLUMN Council;
+                ALTER TABLE address ADD COLUMN Council BLOB NOT NULL DEFAULT '';
+            ")?;
+            let report: Vec<String> = super::check(connection, SCHEMAS)?.iter().map(|mismatch| mismatch.to_string()).collect();
+            assert_eq!(report, vec![
+                "address: the column Town does not exist",
+                "address: the column Council is BLOB, which can not be read as text",
+                "address: the column Council can not be null, but a row may be sent without it",
+                "view_supply_rep_numbers: the column Number does not exist",
+                "view_supply_rep_numbers: the view has the columns (SupplyRepID, FirstName, LastName), but (SupplyRepID, Number) are read from it",
+            ]);
+            Ok(())
+        }).unwrap();
+    }
+}
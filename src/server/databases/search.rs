@@ -0,0 +1,199 @@
+use crate::errors::DatabaseError;
+use crate::server::api::query_types::{Query, SearchParams};
+use crate::server::databases::{
+    backend::Backend,
+    data_structs::{DBTable, Type, Value},
+    tables,
+    config::data_keys,
+};
+
+use json::JsonValue;
+
+// Finds the best matches of each type, along with the supplier and rep each match belongs to.
+// Email addresses and phone numbers belong to the supplier or rep holding the contact they are part of.
+// The matches, "{}", are found in the way of each database, see SQLITE_MATCHES and POSTGRES_MATCHES.
+const SEARCH_SQL: &str = r"
+    SELECT entity, entity_id, content,
+        CASE entity
+            WHEN 'supplier' THEN entity_id
+            WHEN 'address' THEN (SELECT id FROM supplier WHERE fk_address = entity_id LIMIT 1)
+            WHEN 'rep' THEN (SELECT id FROM supplier WHERE fk_supply_rep = entity_id LIMIT 1)
+            WHEN 'email' THEN (SELECT s.id FROM supplier s
+                JOIN contact_email ce ON ce.fk_contact = s.fk_contact
+                WHERE ce.fk_email_addresses = entity_id LIMIT 1)
+            WHEN 'number' THEN (SELECT s.id FROM supplier s
+                JOIN contact_phone cp ON cp.fk_contact = s.fk_contact
+                WHERE cp.fk_phone_number = entity_id LIMIT 1)
+        END AS supplierId,
+        CASE entity
+            WHEN 'rep' THEN entity_id
+            WHEN 'email' THEN (SELECT r.id FROM supply_rep r
+                JOIN contact_email ce ON ce.fk_contact = r.fk_contact
+                WHERE ce.fk_email_addresses = entity_id LIMIT 1)
+            WHEN 'number' THEN (SELECT r.id FROM supply_rep r
+                JOIN contact_phone cp ON cp.fk_contact = r.fk_contact
+                WHERE cp.fk_phone_number = entity_id LIMIT 1)
+        END AS repId,
+        rank
+    FROM (
+        SELECT entity, entity_id, content, rank,
+            row_number() OVER (PARTITION BY entity ORDER BY rank) AS position
+        FROM ({}) AS matches
+    ) AS ranked
+    WHERE position <= ?
+    ORDER BY entity, rank
+";
+
+// The fts5 index is given a match expression, see match_expression
+const SQLITE_MATCHES: &str = "SELECT entity, entity_id, content, rank FROM search_index WHERE search_index MATCH ?";
+
+// The trigram index of PostgreSQL is used by ILIKE, with a pattern for each term, and the
+// matches most similar to the search text are ranked first
+const POSTGRES_MATCHES: &str = "SELECT entity, entity_id, content, -similarity(content, ?)::float8 AS rank FROM search_index WHERE {}";
+
+// The groups results are returned in, keyed by the type of result
+pub const RESULT_GROUPS: [(&str, &str); 5] = [
+    ("supplier", "suppliers"),
+    ("rep", "reps"),
+    ("email", "emails"),
+    ("number", "numbers"),
+    ("address", "addresses"),
+];
+
+///
+/// Searches the index for rows holding every term of the search text, the best matches of each
+/// type are returned first. Terms are matched anywhere within the text, so each must be at least
+/// three characters long.
+///
+pub fn search_table(params: &SearchParams, connection: &dyn Backend) -> Result<DBTable, DatabaseError> {
+    let match_expression = match_expression(&params.text)?;
+
+    let (matches, mut values) = match connection.database_type() {
+        Type::Sqlite => (SQLITE_MATCHES.to_string(), vec![Value::String(match_expression)]),
+        Type::Postgres => {
+            let terms: Vec<&str> = params.text.split_whitespace().collect();
+            let conditions = vec!["content ILIKE ?"; terms.len()].join(" AND ");
+            let mut values = vec![Value::String(params.text.clone())];
+            values.extend(terms.iter().map(|term| Value::String(like_pattern(term))));
+            (POSTGRES_MATCHES.replace("{}", &conditions), values)
+        },
+    };
+    values.push(Value::Integer(params.limit as i64));
+
+    connection.query(&SEARCH_SQL.replace("{}", &matches), &values, tables::get_tables(Query::GETSearch(params.clone())))
+        .map_err(|_| DatabaseError::QueryError("Failed to search, the search index may be missing".to_string()))
+}
+
+// Each term of the search text is quoted, so the text can not be read as the fts5 query syntax
+fn match_expression(text: &str) -> Result<String, DatabaseError> {
+    let terms: Vec<&str> = text.split_whitespace().collect();
+    if terms.is_empty() {
+        return Err(DatabaseError::InvalidParameter("The q parameter must hold the text to search for".to_string()));
+    }
+    if terms.iter().any(|term| term.chars().count() < 3) {
+        return Err(DatabaseError::InvalidParameter("Each term of the search text must be at least 3 characters long".to_string()));
+    }
+
+    let quoted: Vec<String> = terms.iter().map(|term| format!("\"{}\"", term.replace('"', "\"\""))).collect();
+    Ok(quoted.join(" "))
+}
+
+// A pattern matching text which holds the term, the characters LIKE treats as wildcards are escaped
+fn like_pattern(term: &str) -> String {
+    let escaped = term.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_");
+    format!("%{}%", escaped)
+}
+
+///
+/// Groups the rows of a search by their type, e.g. `{"suppliers": [...], "emails": [...]}`,
+/// each group holds its best matches first
+///
+pub fn search_to_json(table: &DBTable) -> JsonValue {
+    let mut groups = json::object!{};
+    for (_, group) in RESULT_GROUPS {
+        groups[group] = JsonValue::new_array();
+    }
+
+    let results = table.to_json();
+    for result in results.members() {
+        let group = RESULT_GROUPS.iter()
+            .find(|(entity, _)| result[data_keys::SEARCH_TYPE] == *entity)
+            .map(|(_, group)| *group);
+
+        if let Some(group) = group {
+            let mut result = result.clone();
+            result.remove(data_keys::SEARCH_TYPE);
+            groups[group].push(result).unwrap();
+        }
+    }
+    groups
+}
+
+
+#[cfg(test)]
+mod test {
+    use json::JsonValue;
+    use crate::errors::DatabaseError;
+    use crate::server::api::query_types::SearchParams;
+    use crate::server::databases::{backend::{Backend, Database}, data_structs::Value, migrations};
+
+    fn search(text: &str, connection: &dyn Backend) -> Result<JsonValue, DatabaseError> {
+        let params = SearchParams { text: text.to_string(), limit: 10 };
+        Ok(super::search_to_json(&super::search_table(&params, connection)?))
+    }
+
+    fn texts(results: &JsonValue) -> Vec<String> {
+        results.members().map(|result| result["text"].to_string()).collect()
+    }
+
+    #[test]
+    fn test_the_index_is_kept_in_step_with_the_indexed_tables() {
+        let database = Database::sqlite(":memory:", 1).unwrap();
+        database.connect(|connection| {
+            migrations::migrate(connection)?;
+            connection.execute_batch(r"
+                INSERT INTO address (Line1, Town, Postcode) VALUES ('1 High St', 'Leeds', 'LS1 1AA');
+                INSERT INTO supplier (name, fk_address) VALUES ('Acme Toolworks Ltd', 1);
+                INSERT INTO supplier (name) VALUES ('Acme Tools');
+                INSERT INTO emails (Email) VALUES ('sales@acme.test');
+                INSERT INTO contact_email (fk_email_addresses, fk_contact) VALUES (1, (SELECT fk_contact FROM supplier WHERE id = 2));
+            ")?;
+
+            // the rows held as the index is made are added to it, so the index is made again over them
+            let mut drop_index = String::from("DROP TABLE search_index; DELETE FROM schema_version WHERE version = 3;");
+            for entity in ["supplier", "rep", "email", "number", "address"] {
+                for event in ["insert", "update", "delete"] {
+                    drop_index.push_str(&format!("DROP TRIGGER search_{}_{};", entity, event));
+                }
+            }
+            connection.execute_batch(&drop_index)?;
+            migrations::migrate(connection)?;
+            assert_eq!(connection.query_row("SELECT COUNT(*) FROM search_index", &[], &[Value::Integer(0)])?, Some(vec![Value::Integer(4)]));
+
+            // the results are grouped by type, the closest match of each type first
+            let results = search("acme", connection)?;
+            assert_eq!(texts(&results["suppliers"]), vec!["Acme Tools", "Acme Toolworks Ltd"]);
+            assert_eq!(results["emails"][0]["text"], "sales@acme.test");
+            assert_eq!(results["emails"][0]["supplierId"], 2);
+            assert!(results["reps"].is_empty());
+            assert_eq!(search("LS1", connection)?["addresses"][0]["supplierId"], 1);
+
+            // a renamed supplier is found by its new name only, and a removed one is not found
+            connection.execute_batch("UPDATE supplier SET name = 'Zenith Tools' WHERE id = 2")?;
+            assert_eq!(texts(&search("acme", connection)?["suppliers"]), vec!["Acme Toolworks Ltd"]);
+            assert_eq!(search("zenith", connection)?["suppliers"][0]["id"], 2);
+            connection.execute_batch("UPDATE supplier SET fk_address = NULL WHERE id = 1; DELETE FROM supplier WHERE id = 1; DELETE FROM address;")?;
+            assert!(search("acme", connection)?["suppliers"].is_empty());
+            assert!(search("LS1", connection)?["addresses"].is_empty());
+            Ok(())
+        }).unwrap();
+    }
+
+    #[test]
+    fn test_match_expression_quotes_terms() {
+        assert_eq!(super::match_expression("acme  \"ltd\" NOT").unwrap(), "\"acme\" \"\"\"ltd\"\"\" \"NOT\"");
+        assert!(super::match_expression("   ").is_err());
+        assert!(super::match_expression("acme ab").is_err());
+        assert_eq!(super::like_pattern("50%_o\\ff"), "%50\\%\\_o\\\\ff%");
+    }
+}
@@ -0,0 +1,109 @@
+-- The tables and views the server reads and writes. Each statement leaves an object which
+-- already exists alone, so a database made before migrations were added is brought up to date.
+
+CREATE TABLE IF NOT EXISTS contact (
+    id INTEGER PRIMARY KEY
+);
+
+CREATE TABLE IF NOT EXISTS address (
+    id INTEGER PRIMARY KEY,
+    Line1 TEXT NOT NULL,
+    Line2 TEXT,
+    Town TEXT NOT NULL,
+    Council TEXT,
+    Postcode TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS person_title (
+    id INTEGER PRIMARY KEY,
+    title TEXT NOT NULL UNIQUE
+);
+
+INSERT OR IGNORE INTO person_title (title) VALUES ('Mr'), ('Mrs'), ('Miss'), ('Ms'), ('Dr');
+
+CREATE TABLE IF NOT EXISTS supply_rep (
+    id INTEGER PRIMARY KEY,
+    fk_person_title INTEGER REFERENCES person_title(id),
+    FirstName TEXT NOT NULL,
+    LastName TEXT NOT NULL,
+    fk_contact INTEGER REFERENCES contact(id)
+);
+
+CREATE TABLE IF NOT EXISTS supplier (
+    id INTEGER PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE,
+    active INTEGER NOT NULL DEFAULT 1,
+    fk_address INTEGER REFERENCES address(id),
+    fk_contact INTEGER REFERENCES contact(id),
+    fk_supply_rep INTEGER REFERENCES supply_rep(id)
+);
+
+CREATE TABLE IF NOT EXISTS emails (
+    id INTEGER PRIMARY KEY,
+    Email TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS phone_numbers (
+    id INTEGER PRIMARY KEY,
+    Number TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS contact_email (
+    fk_email_addresses INTEGER NOT NULL REFERENCES emails(id),
+    fk_contact INTEGER NOT NULL REFERENCES contact(id)
+);
+
+CREATE TABLE IF NOT EXISTS contact_phone (
+    fk_phone_number INTEGER NOT NULL REFERENCES phone_numbers(id),
+    fk_contact INTEGER NOT NULL REFERENCES contact(id)
+);
+
+CREATE TABLE IF NOT EXISTS supply_categories (
+    id INTEGER PRIMARY KEY,
+    Type TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS supplier_supplies (
+    fk_supplier INTEGER NOT NULL REFERENCES supplier(id),
+    fk_supply_category INTEGER NOT NULL REFERENCES supply_categories(id)
+);
+
+-- suppliers and reps are each given a row of their own in the contact table as they are added
+CREATE TRIGGER IF NOT EXISTS supplier_contact AFTER INSERT ON supplier WHEN NEW.fk_contact IS NULL
+BEGIN
+    INSERT INTO contact (id) VALUES (NULL);
+    UPDATE supplier SET fk_contact = last_insert_rowid() WHERE id = NEW.id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS supply_rep_contact AFTER INSERT ON supply_rep WHEN NEW.fk_contact IS NULL
+BEGIN
+    INSERT INTO contact (id) VALUES (NULL);
+    UPDATE supply_rep SET fk_contact = last_insert_rowid() WHERE id = NEW.id;
+END;
+
+CREATE VIEW IF NOT EXISTS view_suppliers AS
+    SELECT * FROM supplier;
+
+CREATE VIEW IF NOT EXISTS view_suppliers_email AS
+    SELECT s.id AS supplierId, e.Email
+    FROM supplier AS s
+    JOIN contact_email AS ce ON ce.fk_contact = s.fk_contact
+    JOIN emails AS e ON e.id = ce.fk_email_addresses;
+
+CREATE VIEW IF NOT EXISTS view_suppliers_numbers AS
+    SELECT s.id AS supplierId, p.Number
+    FROM supplier AS s
+    JOIN contact_phone AS cp ON cp.fk_contact = s.fk_contact
+    JOIN phone_numbers AS p ON p.id = cp.fk_phone_number;
+
+CREATE VIEW IF NOT EXISTS view_supply_rep_email AS
+    SELECT r.id AS SupplyRepID, e.Email
+    FROM supply_rep AS r
+    JOIN contact_email AS ce ON ce.fk_contact = r.fk_contact
+    JOIN emails AS e ON e.id = ce.fk_email_addresses;
+
+CREATE VIEW IF NOT EXISTS view_supply_rep_numbers AS
+    SELECT r.id AS SupplyRepID, p.Number
+    FROM supply_rep AS r
+    JOIN contact_phone AS cp ON cp.fk_contact = r.fk_contact
+    JOIN phone_numbers AS p ON p.id = cp.fk_phone_number;
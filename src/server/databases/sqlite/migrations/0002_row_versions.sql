@@ -0,0 +1,6 @@
+-- Each supplier, address and rep is given a version, which is raised each time the row is changed,
+-- so a change can be made to the version the client read. The rows already held start at version 1.
+
+ALTER TABLE supplier ADD COLUMN version INTEGER NOT NULL DEFAULT 1;
+ALTER TABLE address ADD COLUMN version INTEGER NOT NULL DEFAULT 1;
+ALTER TABLE supply_rep ADD COLUMN version INTEGER NOT NULL DEFAULT 1;
@@ -0,0 +1,68 @@
+-- The search index holds a row for each supplier name, rep name, email address, phone number
+-- and postcode. Rows are matched on any part of their text, as the trigram tokenizer indexes
+-- every three characters of the text.
+
+CREATE VIRTUAL TABLE search_index USING fts5(content, entity UNINDEXED, entity_id UNINDEXED, tokenize = 'trigram');
+
+-- The rows already held by the indexed tables
+INSERT INTO search_index (content, entity, entity_id) SELECT name, 'supplier', id FROM supplier;
+INSERT INTO search_index (content, entity, entity_id) SELECT FirstName || ' ' || LastName, 'rep', id FROM supply_rep;
+INSERT INTO search_index (content, entity, entity_id) SELECT Email, 'email', id FROM emails;
+INSERT INTO search_index (content, entity, entity_id) SELECT Number, 'number', id FROM phone_numbers;
+INSERT INTO search_index (content, entity, entity_id) SELECT Postcode, 'address', id FROM address;
+
+-- The triggers keep the search index in step with the tables it indexes, however they are written to
+CREATE TRIGGER search_supplier_insert AFTER INSERT ON supplier BEGIN
+    INSERT INTO search_index (content, entity, entity_id) VALUES (NEW.name, 'supplier', NEW.id);
+END;
+CREATE TRIGGER search_supplier_update AFTER UPDATE OF name ON supplier BEGIN
+    DELETE FROM search_index WHERE entity = 'supplier' AND entity_id = OLD.id;
+    INSERT INTO search_index (content, entity, entity_id) VALUES (NEW.name, 'supplier', NEW.id);
+END;
+CREATE TRIGGER search_supplier_delete AFTER DELETE ON supplier BEGIN
+    DELETE FROM search_index WHERE entity = 'supplier' AND entity_id = OLD.id;
+END;
+
+CREATE TRIGGER search_rep_insert AFTER INSERT ON supply_rep BEGIN
+    INSERT INTO search_index (content, entity, entity_id) VALUES (NEW.FirstName || ' ' || NEW.LastName, 'rep', NEW.id);
+END;
+CREATE TRIGGER search_rep_update AFTER UPDATE OF FirstName, LastName ON supply_rep BEGIN
+    DELETE FROM search_index WHERE entity = 'rep' AND entity_id = OLD.id;
+    INSERT INTO search_index (content, entity, entity_id) VALUES (NEW.FirstName || ' ' || NEW.LastName, 'rep', NEW.id);
+END;
+CREATE TRIGGER search_rep_delete AFTER DELETE ON supply_rep BEGIN
+    DELETE FROM search_index WHERE entity = 'rep' AND entity_id = OLD.id;
+END;
+
+CREATE TRIGGER search_email_insert AFTER INSERT ON emails BEGIN
+    INSERT INTO search_index (content, entity, entity_id) VALUES (NEW.Email, 'email', NEW.id);
+END;
+CREATE TRIGGER search_email_update AFTER UPDATE OF Email ON emails BEGIN
+    DELETE FROM search_index WHERE entity = 'email' AND entity_id = OLD.id;
+    INSERT INTO search_index (content, entity, entity_id) VALUES (NEW.Email, 'email', NEW.id);
+END;
+CREATE TRIGGER search_email_delete AFTER DELETE ON emails BEGIN
+    DELETE FROM search_index WHERE entity = 'email' AND entity_id = OLD.id;
+END;
+
+CREATE TRIGGER search_number_insert AFTER INSERT ON phone_numbers BEGIN
+    INSERT INTO search_index (content, entity, entity_id) VALUES (NEW.Number, 'number', NEW.id);
+END;
+CREATE TRIGGER search_number_update AFTER UPDATE OF Number ON phone_numbers BEGIN
+    DELETE FROM search_index WHERE entity = 'number' AND entity_id = OLD.id;
+    INSERT INTO search_index (content, entity, entity_id) VALUES (NEW.Number, 'number', NEW.id);
+END;
+CREATE TRIGGER search_number_delete AFTER DELETE ON phone_numbers BEGIN
+    DELETE FROM search_index WHERE entity = 'number' AND entity_id = OLD.id;
+END;
+
+CREATE TRIGGER search_address_insert AFTER INSERT ON address BEGIN
+    INSERT INTO search_index (content, entity, entity_id) VALUES (NEW.Postcode, 'address', NEW.id);
+END;
+CREATE TRIGGER search_address_update AFTER UPDATE OF Postcode ON address BEGIN
+    DELETE FROM search_index WHERE entity = 'address' AND entity_id = OLD.id;
+    INSERT INTO search_index (content, entity, entity_id) VALUES (NEW.Postcode, 'address', NEW.id);
+END;
+CREATE TRIGGER search_address_delete AFTER DELETE ON address BEGIN
+    DELETE FROM search_index WHERE entity = 'address' AND entity_id = OLD.id;
+END;
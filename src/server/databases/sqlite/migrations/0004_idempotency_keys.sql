@@ -0,0 +1,12 @@
+-- Each key sent in an Idempotency-Key header is stored with a fingerprint of the request it was
+-- sent with, and the response given to that request. A key is held with an empty status line
+-- while its request is being carried out.
+
+CREATE TABLE idempotency_keys (
+    key TEXT PRIMARY KEY,
+    fingerprint TEXT NOT NULL,
+    status_line TEXT NOT NULL,
+    content_type TEXT NOT NULL,
+    content TEXT NOT NULL,
+    created_at INTEGER NOT NULL
+);
@@ -0,0 +1,310 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::ops::{Deref, DerefMut};
+
+use crate::errors::DatabaseError;
+use crate::config::{SQLITE_BUSY_TIMEOUT_MS, SQLITE_STATEMENT_CACHE_SIZE};
+use crate::server::databases::{
+    backend::{Backend, column_structure},
+    data_structs::{DBTable, DBTableStruct, DatabaseColumn, Type, Value},
+    pool::Pool,
+    sqlite::util::{open_connection, bind_values, db_data_into_table, declared_type_value, statement_error},
+};
+use sqlite::{Connection, State, Statement};
+
+///
+/// Readies a connection for use by the server. The write ahead log lets connections read while
+/// another writes, and a connection waits for a lock held by another rather than failing straight
+/// away with SQLITE_BUSY.
+///
+pub fn configure(connection: &mut Connection) -> Result<(), DatabaseError> {
+    connection.set_busy_timeout(SQLITE_BUSY_TIMEOUT_MS)
+        .map_err(|_| DatabaseError::ConnectionError("Failed to set the busy timeout of the database connection".to_string()))?;
+    connection.execute("PRAGMA journal_mode = WAL; PRAGMA foreign_keys = ON;")
+        .map_err(|_| DatabaseError::ConnectionError("Failed to configure the database connection".to_string()))
+}
+
+/// Opens a pool of connections to the database, each configured for use by the server
+pub fn open(database_path: &str, size: usize) -> Result<Pool<SqliteConnection>, DatabaseError> {
+    let mut connections = Vec::with_capacity(size);
+    for _ in 0..size {
+        let mut connection = open_connection(database_path)?;
+        configure(&mut connection)?;
+        connections.push(SqliteConnection::new(connection));
+    }
+    Ok(Pool::new(connections))
+}
+
+///
+/// A connection to a SQLite database, which keeps the statements it prepares, so a statement
+/// run again, such as the insert of each supplier of a batch or the same query sent by another
+/// request, is not prepared again. The statements of the most recently used SQL are kept for
+/// as long as the connection is open, see StatementCache.
+///
+pub struct SqliteConnection {
+    // declared before the connection, so the statements are finalized before it is closed
+    statements: RefCell<StatementCache>,
+    connection: Connection,
+}
+
+// A statement is tied to the connection which prepared it rather than to a thread, and the
+// statements are only used through the connection, which is lent to one request at a time, so
+// the connection can be handed to another thread along with its statements
+unsafe impl Send for SqliteConnection {}
+
+impl SqliteConnection {
+    pub fn new(connection: Connection) -> Self {
+        SqliteConnection {
+            statements: RefCell::new(StatementCache::new(SQLITE_STATEMENT_CACHE_SIZE)),
+            connection,
+        }
+    }
+
+    /// Prepares a statement, or takes the statement prepared for the same SQL from the cache
+    pub fn prepare_cached(&self, sql: &str) -> Result<CachedStatement<'_>, DatabaseError> {
+        let cached = self.statements.borrow_mut().take(sql);
+        let statement = match cached {
+            Some(statement) => statement,
+            None => {
+                let statement = self.connection.prepare(sql)
+                    .map_err(|_| DatabaseError::QueryError("Failed to prepare sql statement".to_string()))?;
+                // the statement borrows the connection, which the cache is dropped before, see SqliteConnection
+                unsafe { std::mem::transmute::<Statement<'_>, Statement<'static>>(statement) }
+            },
+        };
+        Ok(CachedStatement {
+            sql: sql.to_string(),
+            statement: Some(statement),
+            cache: self,
+        })
+    }
+}
+
+impl Deref for SqliteConnection {
+    type Target = Connection;
+
+    fn deref(&self) -> &Connection {
+        &self.connection
+    }
+}
+
+impl Backend for SqliteConnection {
+    fn database_type(&self) -> Type {
+        Type::Sqlite
+    }
+
+    fn query(&self, sql: &str, values: &[Value], structure: DBTableStruct) -> Result<DBTable, DatabaseError> {
+        let mut statement = self.prepare_cached(sql)?;
+        bind_values(&mut statement, values)?;
+        db_data_into_table(&mut statement, structure)
+    }
+
+    fn execute(&self, sql: &str, values: &[Value]) -> Result<(), DatabaseError> {
+        let mut statement = self.prepare_cached(sql)?;
+        bind_values(&mut statement, values)?;
+        loop {
+            match statement.next() {
+                Ok(State::Done) => return Ok(()),
+                Ok(State::Row) => continue,
+                Err(error) => return Err(statement_error(&error)),
+            }
+        }
+    }
+
+    fn execute_batch(&self, sql: &str) -> Result<(), DatabaseError> {
+        self.connection.execute(sql)
+            .map_err(|_| DatabaseError::QueryError("Failed to execute sql statements".to_string()))
+    }
+
+    // the transaction takes the write lock as it begins, so no other connection can write
+    // between a read and a write made within it
+    fn begin(&self) -> Result<(), DatabaseError> {
+        self.execute_batch("BEGIN IMMEDIATE; PRAGMA defer_foreign_keys = ON;")
+    }
+
+    fn commit(&self) -> Result<(), DatabaseError> {
+        self.execute_batch("COMMIT")
+    }
+
+    fn rollback(&self) -> Result<(), DatabaseError> {
+        self.execute_batch("ROLLBACK")
+    }
+
+    // an INTEGER PRIMARY KEY column is the rowid, which can not be null though it is not declared not null
+    fn columns(&self, source: &str) -> Result<Vec<DatabaseColumn>, DatabaseError> {
+        let sql = r#"SELECT name, type, "notnull" OR pk > 0 FROM pragma_table_info(?) ORDER BY cid"#;
+        let rows = self.query(sql, &[Value::String(source.to_string())], column_structure())?;
+        Ok(rows.rows.into_iter().map(|row| {
+            let declared_type = row.cells[1].to_text();
+            DatabaseColumn {
+                name: row.cells[0].to_text(),
+                field_type: declared_type_value(&declared_type),
+                declared_type,
+                not_null: row.cells[2] == Value::Boolean(true),
+            }
+        }).collect())
+    }
+}
+
+///
+/// The statements prepared by a connection, keyed by their SQL. The statements of at most
+/// `capacity` SQL are kept, once another is put in the cache the statements of the SQL used
+/// least recently are finalized, so SQL built from the values of a request can not fill it.
+///
+struct StatementCache {
+    capacity: usize,
+    // the statements of each SQL, along with when the SQL was last used
+    entries: HashMap<String, (u64, Vec<Statement<'static>>)>,
+    uses: u64,
+}
+
+impl StatementCache {
+    fn new(capacity: usize) -> Self {
+        StatementCache {
+            capacity,
+            entries: HashMap::new(),
+            uses: 0,
+        }
+    }
+
+    // takes a statement prepared for the SQL out of the cache, it is put back once it has been run
+    fn take(&mut self, sql: &str) -> Option<Statement<'static>> {
+        self.uses += 1;
+        let (used, statements) = self.entries.get_mut(sql)?;
+        *used = self.uses;
+        statements.pop()
+    }
+
+    fn put(&mut self, sql: String, statement: Statement<'static>) {
+        self.uses += 1;
+        if !self.entries.contains_key(&sql) && self.entries.len() >= self.capacity {
+            let least_recent = self.entries.iter().min_by_key(|(_, (used, _))| *used).map(|(sql, _)| sql.clone());
+            if let Some(least_recent) = least_recent {
+                self.entries.remove(&least_recent);
+            }
+        }
+        let (used, statements) = self.entries.entry(sql).or_insert_with(|| (0, Vec::new()));
+        *used = self.uses;
+        statements.push(statement);
+    }
+
+    // the number of statements held for the SQL
+    #[cfg(test)]
+    fn held(&self, sql: &str) -> usize {
+        self.entries.get(sql).map_or(0, |(_, statements)| statements.len())
+    }
+}
+
+/// A statement taken from a cache, which is reset and put back in the cache when it is dropped
+pub struct CachedStatement<'a> {
+    sql: String,
+    statement: Option<Statement<'static>>,
+    cache: &'a SqliteConnection,
+}
+
+impl Deref for CachedStatement<'_> {
+    type Target = Statement<'static>;
+
+    fn deref(&self) -> &Statement<'static> {
+        self.statement.as_ref().unwrap()
+    }
+}
+
+impl DerefMut for CachedStatement<'_> {
+    fn deref_mut(&mut self) -> &mut Statement<'static> {
+        self.statement.as_mut().unwrap()
+    }
+}
+
+impl Drop for CachedStatement<'_> {
+    fn drop(&mut self) {
+        if let Some(mut statement) = self.statement.take() {
+            // a statement which can not be reset is left to be finalized
+            if statement.reset().is_ok() {
+                self.cache.statements.borrow_mut().put(std::mem::take(&mut self.sql), statement);
+            }
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use sqlite::State;
+    use crate::errors::DatabaseError;
+    use crate::server::databases::data_structs::Value;
+
+    #[test]
+    fn test_connections_are_configured_as_they_are_opened() {
+        let pool = super::open(":memory:", 1).unwrap();
+        let connection = pool.get().unwrap();
+        let mut statement = connection.prepare("PRAGMA foreign_keys").unwrap();
+        assert_eq!(statement.next().unwrap(), State::Row);
+        assert_eq!(statement.read::<i64, _>(0).unwrap(), 1);
+    }
+
+    #[test]
+    fn test_statements_are_kept_with_the_pooled_connection() {
+        let pool = super::open(":memory:", 1).unwrap();
+        let sql = "SELECT 1";
+        for _ in 0..2 {
+            let connection = pool.get().unwrap();
+            let mut statement = connection.prepare_cached(sql).unwrap();
+            assert_eq!(statement.next().unwrap(), State::Row);
+        }
+
+        // the statement prepared while the connection was first borrowed is still held
+        assert_eq!(pool.get().unwrap().statements.borrow().held(sql), 1);
+    }
+
+    #[test]
+    fn test_cached_statements_are_reused() {
+        let connection = super::SqliteConnection::new(sqlite::open(":memory:").unwrap());
+        connection.execute("CREATE TABLE supplier (id INTEGER PRIMARY KEY, name TEXT)").unwrap();
+        let sql = "INSERT INTO supplier (name) VALUES ('Acme')";
+
+        for _ in 0..3 {
+            let mut statement = connection.prepare_cached(sql).unwrap();
+            assert_eq!(statement.next().unwrap(), State::Done);
+        }
+        assert_eq!(connection.statements.borrow().held(sql), 1);
+
+        // a statement in use is not handed out twice
+        let first = connection.prepare_cached(sql).unwrap();
+        let second = connection.prepare_cached(sql).unwrap();
+        drop(first);
+        drop(second);
+        assert_eq!(connection.statements.borrow().held(sql), 2);
+    }
+
+    #[test]
+    fn test_the_statements_of_the_sql_used_least_recently_are_finalized() {
+        let connection = super::SqliteConnection::new(sqlite::open(":memory:").unwrap());
+        *connection.statements.borrow_mut() = super::StatementCache::new(2);
+        let run = |sql: &str| assert_eq!(connection.prepare_cached(sql).unwrap().next().unwrap(), State::Row);
+
+        run("SELECT 1");
+        run("SELECT 2");
+        run("SELECT 1");
+        run("SELECT 3");
+        let statements = connection.statements.borrow();
+        assert_eq!((statements.held("SELECT 1"), statements.held("SELECT 2"), statements.held("SELECT 3")), (1, 0, 1));
+    }
+
+    #[test]
+    fn test_a_statement_refused_by_a_constraint_is_a_submission_error() {
+        let connection = super::SqliteConnection::new(sqlite::open(":memory:").unwrap());
+        connection.execute("CREATE TABLE supplier (id INTEGER PRIMARY KEY, name TEXT NOT NULL UNIQUE)").unwrap();
+        let insert = "INSERT INTO supplier (name) VALUES (?)";
+        super::Backend::execute(&connection, insert, &[Value::String("Acme".to_string())]).unwrap();
+
+        let duplicate = super::Backend::execute(&connection, insert, &[Value::String("Acme".to_string())]);
+        assert!(matches!(duplicate, Err(DatabaseError::SubmissionError(_))));
+        let missing = super::Backend::execute(&connection, insert, &[Value::Null]);
+        assert!(matches!(missing, Err(DatabaseError::SubmissionError(_))));
+
+        // a failure which has nothing to do with the data sent is the server's
+        let unknown = super::Backend::execute(&connection, "INSERT INTO supplier (name) SELECT name FROM missing", &[]);
+        assert!(matches!(unknown, Err(DatabaseError::QueryError(_))));
+    }
+}
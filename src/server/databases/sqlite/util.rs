@@ -1,5 +1,8 @@
-use sqlite::Connection;
+use sqlite::{Connection, State, Statement};
 use crate::errors::DatabaseError;
+use crate::server::databases::{
+    data_structs::{DBTable, DBTableStruct, DBTableRow, Value},
+};
 
 
 pub fn open_connection(database_path: &str) -> Result<Connection, DatabaseError> {
@@ -11,3 +14,170 @@ pub fn open_connection(database_path: &str) -> Result<Connection, DatabaseError>
         
     }
 }
+
+
+// converts a cell value into the value type used by the sqlite crate
+pub fn to_sqlite_value(value: &Value) -> sqlite::Value {
+    match value {
+        Value::Boolean(value) => sqlite::Value::Integer(if *value { 1 } else { 0 }),
+        Value::Binary(value) => sqlite::Value::Binary(value.clone()),
+        Value::Float(value) => sqlite::Value::Float(*value),
+        Value::Integer(value) => sqlite::Value::Integer(*value),
+        Value::String(value) => sqlite::Value::String(value.clone()),
+        Value::Null => sqlite::Value::Null,
+    }
+}
+
+// binds values, in order, to the "?" parameters of a prepared statement
+pub fn bind_values(statement: &mut Statement, values: &[Value]) -> Result<(), DatabaseError> {
+    for (i, value) in values.iter().enumerate() {
+        if statement.bind((i + 1, to_sqlite_value(value))).is_err() {
+            return Err(DatabaseError::QueryError("Failed to bind value to sql statement".to_string()));
+        }
+    }
+    Ok(())
+}
+
+// The result code SQLite gives a statement which breaks a constraint, e.g. a NOT NULL or UNIQUE column
+const SQLITE_CONSTRAINT: isize = 19;
+
+// A statement refused by a constraint of the database was given data which is not valid, so is
+// a SubmissionError, while any other failure is a QueryError
+pub fn statement_error(error: &sqlite::Error) -> DatabaseError {
+    match error.code {
+        // extended result codes hold the primary code in their low byte
+        Some(code) if code & 0xff == SQLITE_CONSTRAINT => DatabaseError::SubmissionError("The data breaks a constraint of the database".to_string()),
+        _ => DatabaseError::QueryError("Failed to execute sql statement".to_string()),
+    }
+}
+
+// The type a column's values are read as, found from its declared type in the way SQLite
+// finds the affinity of a column
+pub fn declared_type_value(declared_type: &str) -> Value {
+    let declared_type = declared_type.to_uppercase();
+    if declared_type.contains("INT") {
+        Value::Integer(0)
+    } else if ["CHAR", "CLOB", "TEXT"].iter().any(|name| declared_type.contains(name)) {
+        Value::String(String::new())
+    } else if declared_type.is_empty() || declared_type.contains("BLOB") {
+        Value::Binary(Vec::new())
+    } else if ["REAL", "FLOA", "DOUB"].iter().any(|name| declared_type.contains(name)) {
+        Value::Float(0.0)
+    } else {
+        Value::Integer(0)
+    }
+}
+
+// places values returned by db query into a DBTable
+pub fn db_data_into_table(statement: &mut Statement, row_structure: DBTableStruct) -> Result<DBTable, DatabaseError> {
+
+    if statement.column_count() != row_structure.fields.len() {
+        println!("statement columns {}, row_structure.fields.len() {}", statement.column_count(), row_structure.fields.len());
+        return Err(DatabaseError::QueryError("Number of columns in the statement does not match the number of fields in the db table row".to_string()));
+    }
+
+    let mut db_table = DBTable::new(&row_structure);
+
+    while let Ok(State::Row) = statement.next() { 
+
+        let mut db_row = DBTableRow::new();
+
+        // using the row structure as a guide, we can iterate through the required fields in the row
+
+        for field in row_structure.fields.iter() {
+
+            let field_type = &field.field_type;
+
+            // read a value from a cell within a row using the index of the cell
+
+            let value: sqlite::Value = statement.read(field.index).unwrap();  
+
+            // a null is read as Value::Null whether or not the field may be null, so the cells
+            // of the row stay in line with its fields, see Row
+
+            // knowing that the value should be of a certain type, the next step is to convert it 
+            // to that type and add it to a the DBTableRow struct
+
+            match field_type {
+                Value::Boolean(_) => {
+                    if let Ok(value) = TryFrom::try_from(&value) {
+                        let value: i64 = value;
+                        if value == 0 {
+                            db_row.add_cell(Value::Boolean(false));
+                        }
+                        else {
+                            db_row.add_cell(Value::Boolean(true));
+                        }
+                    } 
+                    else {
+                        db_row.add_cell(Value::Null);
+                    }             
+
+                }, 
+                Value::Float(_) => {
+                    if let Ok(value) = TryFrom::try_from(&value) {
+                        let value: f64 = value;
+                        db_row.add_cell(Value::Float(value));
+                    }
+                    else {
+                        db_row.add_cell(Value::Null);
+                    }
+
+                },
+                Value::Integer(_) => {
+                    if let Ok(value) = TryFrom::try_from(&value) {
+                        let value: i64 = value;
+                        db_row.add_cell(Value::Integer(value));
+                    }
+                    else {
+                        db_row.add_cell(Value::Null);
+                    }
+                },
+                Value::String(_) => { 
+                    if let Ok(value) = TryFrom::try_from(value) {
+                        let value: String = value;
+                        db_row.add_cell(Value::String(value));
+                    }
+                    else {                        
+                        db_row.add_cell(Value::Null);
+                    }
+                },
+                Value::Binary(_) => {
+                    if let Ok(value) = TryFrom::try_from(value) {
+                        let value: Vec<u8> = value;
+                        db_row.add_cell(Value::Binary(value));
+                    }
+                    else {
+                        db_row.add_cell(Value::Null);
+                    }
+                },
+                Value::Null => {
+
+                    db_row.add_cell(Value::Null);
+                }
+            }
+        }
+        db_table.add_row(db_row);
+    }
+    Ok(db_table)
+}
+
+
+#[cfg(test)]
+mod test {
+    use crate::server::databases::sqlite::pool::SqliteConnection;
+
+    #[test]
+    fn test_a_null_keeps_the_cells_of_its_row_in_line() {
+        use crate::server::databases::{backend::Backend, data_structs::{DBTableStruct, DbFieldStruct, Value}};
+
+        let connection = SqliteConnection::new(sqlite::open(":memory:").unwrap());
+
+        // the title can not be null, but a null read from the database is kept in its place
+        let mut structure = DBTableStruct::new();
+        structure.fields.push(DbFieldStruct::new(0, "title", Value::String(String::new()), true));
+        structure.fields.push(DbFieldStruct::new(1, "firstName", Value::String(String::new()), true));
+        let table = connection.query("SELECT NULL, 'Al'", &[], structure).unwrap();
+        assert_eq!(table.rows[0].cells, vec![Value::Null, Value::String("Al".to_string())]);
+    }
+}
@@ -0,0 +1,261 @@
+use crate::server::databases::data_structs::{Column, DBTableStruct, Insert, Schema, Source};
+use crate::server::databases::data_structs::Value;
+use crate::server::api::query_types::Query;
+
+use crate::server::databases::config::data_keys;
+
+pub fn post_tables(for_query: Query) -> DBTableStruct {
+    match for_query {
+        Query::POSTSupplier(_) => SUPPLIER.insert(),
+        Query::POSTAddress(_) => ADDRESS.insert(),
+        Query::POSTContactEmails(_) => EMAIL.insert(),
+        Query::POSTContactPhoneNumbers(_) => NUMBERS.insert(),
+        Query::POSTRep(_) => REP.insert(),
+        _ => DBTableStruct::new()
+    }
+}
+
+
+
+// Get the correct table structure for GET requests
+pub fn get_tables(for_query: Query) -> DBTableStruct {
+
+    match for_query {
+        Query::GETSuppliers(_) | Query::GETSupplierFromId(_, _) => {
+            supplier_table()
+        },
+        Query::GETSuppliersEmail(_) | Query::GETSupplierEmailFromId(_) => {
+            email_table()
+        },
+        Query::GETSuppliersNumbers(_) | Query::GETSupplierNumbersFromId(_) => {
+            numbers_table()
+        },
+        Query::GETSupplierIdFromName(_) => {
+            id_table()
+        },
+        Query::GETSupplierNameFromId(_) => {
+            supplier_name_table()
+        },
+    
+        Query::GETSupplierAddressFromId(_) => {
+            address_table()
+        },
+
+        Query::GETSupplierRepFromId(_) => {
+            rep_table()
+        },
+        Query::GETSuppliersCategories => {
+            categories_table()
+
+        },
+        Query::GETSupplyRepFromId(_) => {
+            REP.select_keys(&[data_keys::TITLE, data_keys::FIRST_NAME, data_keys::LAST_NAME, data_keys::CONTACT_ID])
+        },
+        Query::GETSupplierCategoriesFromId(_) => {
+            categories_table()
+        },
+        Query::GETSupplyRepPhoneNumbersFromId(_) => {
+            numbers_table()
+        },
+        Query::GETSupplyRepEmailFromId(_) => {
+            email_table()
+        },
+        Query::GETSearch(_) => {
+            search_table()
+        },
+    
+    _ => DBTableStruct::new()
+    }
+}
+
+// Tables of the database, are declared by the Schema's below.
+//
+// They represent the tables and views that can be called from the database
+// Each schema has a set of columns, each giving the json object key its value is
+// sent under, the name of the column in the database, its type and whether it
+// may be null. The column also says whether it is sent when a row is added, so
+// the fields of a new row are picked by name rather than by position.
+//
+// The table functions give the structures made from them, these can be used to
+// validate incoming json objects, and format outgoing ones.
+
+const STRING: Value = Value::String(String::new());
+const INTEGER: Value = Value::Integer(0);
+
+pub const SUPPLIER: Schema = Schema {
+    source: Source::Table("supplier"),
+    columns: &[
+        Column::new(data_keys::ID, "id", INTEGER, true, Insert::Generated),
+        Column::new(data_keys::NAME, "name", STRING, true, Insert::Given),
+        // a new supplier is active unless stated otherwise
+        Column::new(data_keys::ACTIVE, "active", INTEGER, true, Insert::Defaulted(Value::Boolean(true))),
+        // set once the address is added
+        Column::new(data_keys::ADDRESS_ID, "fk_address", INTEGER, false, Insert::Generated),
+        // the contact is added along with the supplier, by the database
+        Column::new(data_keys::CONTACT_ID, "fk_contact", INTEGER, false, Insert::Generated),
+        // set once the rep is added
+        Column::new(data_keys::REP_ID, "fk_supply_rep", INTEGER, false, Insert::Generated),
+    ],
+};
+
+pub const EMAIL: Schema = Schema {
+    source: Source::Table("emails"),
+    columns: &[
+        Column::new(data_keys::ID, "id", INTEGER, true, Insert::Generated),
+        Column::new(data_keys::EMAIL, "Email", STRING, true, Insert::Given),
+    ],
+};
+
+pub const NUMBERS: Schema = Schema {
+    source: Source::Table("phone_numbers"),
+    columns: &[
+        Column::new(data_keys::ID, "id", INTEGER, true, Insert::Generated),
+        Column::new(data_keys::NUMBER, "Number", STRING, true, Insert::Given),
+    ],
+};
+
+pub const ADDRESS: Schema = Schema {
+    source: Source::Table("address"),
+    columns: &[
+        Column::new(data_keys::ID, "id", INTEGER, true, Insert::Generated),
+        Column::new(data_keys::ADDRESS_LINE1, "Line1", STRING, true, Insert::Given),
+        Column::new(data_keys::ADDRESS_LINE2, "Line2", STRING, false, Insert::Given),
+        Column::new(data_keys::ADDRESS_TOWN, "Town", STRING, true, Insert::Given),
+        Column::new(data_keys::ADDRESS_COUNCIL, "Council", STRING, false, Insert::Given),
+        Column::new(data_keys::ADDRESS_POSTCODE, "Postcode", STRING, true, Insert::Given),
+    ],
+};
+
+pub const REP: Schema = Schema {
+    source: Source::Table("supply_rep"),
+    columns: &[
+        Column::new(data_keys::ID, "id", INTEGER, true, Insert::Generated),
+        Column::looked_up(data_keys::TITLE, "fk_person_title", "person_title", true, Insert::Given),
+        Column::new(data_keys::FIRST_NAME, "FirstName", STRING, true, Insert::Given),
+        Column::new(data_keys::LAST_NAME, "LastName", STRING, true, Insert::Given),
+        Column::new(data_keys::CONTACT_ID, "fk_contact", INTEGER, false, Insert::Generated),
+    ],
+};
+
+// the views give the email addresses and phone numbers of each supplier and rep, along with
+// the id of the supplier or rep
+pub const SUPPLIER_EMAILS: Schema = Schema {
+    source: Source::View("view_suppliers_email"),
+    columns: &[
+        Column::new(data_keys::ID, "supplierId", INTEGER, true, Insert::Generated),
+        Column::new(data_keys::EMAIL, "Email", STRING, true, Insert::Generated),
+    ],
+};
+
+pub const SUPPLIER_NUMBERS: Schema = Schema {
+    source: Source::View("view_suppliers_numbers"),
+    columns: &[
+        Column::new(data_keys::ID, "supplierId", INTEGER, true, Insert::Generated),
+        Column::new(data_keys::NUMBER, "Number", STRING, true, Insert::Generated),
+    ],
+};
+
+pub const REP_EMAILS: Schema = Schema {
+    source: Source::View("view_supply_rep_email"),
+    columns: &[
+        Column::new(data_keys::ID, "SupplyRepID", INTEGER, true, Insert::Generated),
+        Column::new(data_keys::EMAIL, "Email", STRING, true, Insert::Generated),
+    ],
+};
+
+pub const REP_NUMBERS: Schema = Schema {
+    source: Source::View("view_supply_rep_numbers"),
+    columns: &[
+        Column::new(data_keys::ID, "SupplyRepID", INTEGER, true, Insert::Generated),
+        Column::new(data_keys::NUMBER, "Number", STRING, true, Insert::Generated),
+    ],
+};
+
+pub const CATEGORIES: Schema = Schema {
+    source: Source::Table("supply_categories"),
+    columns: &[
+        Column::new(data_keys::ID, "id", INTEGER, true, Insert::Generated),
+        Column::new(data_keys::CATEGORY_TYPE, "Type", STRING, true, Insert::Given),
+    ],
+};
+
+// search results are put together by the search query, rather than read from a table
+pub const SEARCH: Schema = Schema {
+    source: Source::Query,
+    columns: &[
+        Column::new(data_keys::SEARCH_TYPE, "type", STRING, true, Insert::Generated),
+        Column::new(data_keys::ID, "id", INTEGER, true, Insert::Generated),
+        Column::new(data_keys::SEARCH_TEXT, "text", STRING, true, Insert::Generated),
+        Column::new(data_keys::SUPPLIER_ID, "supplierId", INTEGER, false, Insert::Generated),
+        Column::new(data_keys::REP_ID, "repId", INTEGER, false, Insert::Generated),
+        Column::new(data_keys::SEARCH_RANK, "rank", Value::Float(0.0), true, Insert::Generated),
+    ],
+};
+
+// The tables and views the server reads, which are checked against the database as the server starts
+pub const SCHEMAS: &[&Schema] = &[
+    &SUPPLIER, &EMAIL, &NUMBERS, &ADDRESS, &REP, &CATEGORIES,
+    &SUPPLIER_EMAILS, &SUPPLIER_NUMBERS, &REP_EMAILS, &REP_NUMBERS,
+];
+
+pub fn supplier_table() -> DBTableStruct {
+    SUPPLIER.select()
+}
+
+pub fn email_table() -> DBTableStruct {
+    EMAIL.select()
+}
+
+pub fn numbers_table() -> DBTableStruct {
+    NUMBERS.select()
+}
+
+pub fn id_table() -> DBTableStruct {
+    SUPPLIER.select_keys(&[data_keys::ID])
+}
+
+pub fn supplier_name_table() -> DBTableStruct {
+    SUPPLIER.select_keys(&[data_keys::NAME])
+}
+
+pub fn address_table() -> DBTableStruct {
+    ADDRESS.select()
+}
+
+pub fn rep_table() -> DBTableStruct {
+    REP.select()
+}
+
+pub fn categories_table() -> DBTableStruct {
+    CATEGORIES.select()
+}
+
+pub fn search_table() -> DBTableStruct {
+    SEARCH.select()
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn keys(table: &DBTableStruct) -> Vec<(usize, &str, bool)> {
+        table.fields.iter().map(|field| (field.index, field.name.as_str(), field.not_null)).collect()
+    }
+
+    #[test]
+    fn test_new_rows_are_sent_without_generated_columns() {
+        let supplier = SUPPLIER.insert();
+        assert_eq!(keys(&supplier), vec![(0, data_keys::NAME, true), (1, data_keys::ACTIVE, false)]);
+        assert_eq!(supplier.fields[1].field_type, Value::Boolean(true));
+
+        let rep = REP.insert();
+        assert_eq!(keys(&rep), vec![(0, data_keys::TITLE, true), (1, data_keys::FIRST_NAME, true), (2, data_keys::LAST_NAME, true)]);
+        assert_eq!(keys(&ADDRESS.insert()).len(), 5);
+
+        // the selected columns are read in the order they are named
+        let names = REP.select_keys(&[data_keys::LAST_NAME, data_keys::CONTACT_ID]);
+        assert_eq!(keys(&names), vec![(0, data_keys::LAST_NAME, true), (1, data_keys::CONTACT_ID, false)]);
+        assert_eq!(SUPPLIER.column_list(), "id, name, active, fk_address, fk_contact, fk_supply_rep");
+    }
+}
@@ -0,0 +1,163 @@
+use crate::errors::DatabaseError;
+use crate::server::databases::{
+    backend::Backend,
+    data_structs::Value,
+    config::data_keys,
+};
+
+///
+/// A SQL statement along with the values bound to its "?" parameters. Values taken from a 
+/// request are only ever bound, never written into the SQL itself.
+///
+#[derive(Debug, Clone, PartialEq)]
+pub struct BoundSql {
+    pub sql: String,
+    pub values: Vec<Value>,
+}
+
+impl BoundSql {
+    pub fn new(sql: &str, values: Vec<Value>) -> Self {
+        BoundSql {
+            sql: sql.to_string(),
+            values,
+        }
+    }
+
+    ///
+    /// Runs an insert statement whose RETURNING clause gives the id of the row it added, and
+    /// returns that id
+    ///
+    pub fn insert(&self, error_message: &str, connection: &dyn Backend) -> Result<i64, DatabaseError> {
+        let error = || DatabaseError::SubmissionError(error_message.to_string());
+
+        match connection.query_row(&self.sql, &self.values, &[Value::Integer(0)]).map_err(|_| error())? {
+            Some(row) => match row[0] {
+                Value::Integer(id) => Ok(id),
+                _ => Err(error()),
+            },
+            None => Err(error()),
+        }
+    }
+}
+
+///
+/// Removes the email addresses or phone numbers, as named by `key`, of a contact. The rows
+/// linking them to the contact are removed, and then each address or number which no other
+/// contact holds, as one held by another contact is still in use.
+///
+pub fn remove_contact_details(key: &str, contact_id: i64, connection: &dyn Backend) -> Result<(), DatabaseError> {
+    let (delete_details, delete_links) = match key {
+        data_keys::EMAIL => (r"DELETE FROM emails WHERE id IN (SELECT fk_email_addresses FROM contact_email WHERE fk_contact = ?1)
+            AND id NOT IN (SELECT fk_email_addresses FROM contact_email WHERE fk_contact != ?1)", "DELETE FROM contact_email WHERE fk_contact = ?1"),
+        _ => (r"DELETE FROM phone_numbers WHERE id IN (SELECT fk_phone_number FROM contact_phone WHERE fk_contact = ?1)
+            AND id NOT IN (SELECT fk_phone_number FROM contact_phone WHERE fk_contact != ?1)", "DELETE FROM contact_phone WHERE fk_contact = ?1"),
+    };
+    execute_with_values(delete_details, &[Value::Integer(contact_id)], "Failed to remove contact details", connection)?;
+    execute_with_values(delete_links, &[Value::Integer(contact_id)], "Failed to remove contact details", connection)
+}
+
+// Removes an address no longer used by any supplier
+pub fn remove_unused_address(address_id: i64, connection: &dyn Backend) -> Result<(), DatabaseError> {
+    let sql = "DELETE FROM address WHERE id = ? AND id NOT IN (SELECT fk_address FROM supplier WHERE fk_address IS NOT NULL)";
+    execute_with_values(sql, &[Value::Integer(address_id)], "Failed to remove the address", connection)
+}
+
+// Removes a rep no longer used by any supplier, along with the rep's contact details
+pub fn remove_unused_rep(rep_id: i64, connection: &dyn Backend) -> Result<(), DatabaseError> {
+    let contact_id = supply_rep_contact_id(rep_id, connection)?;
+    let sql = r"DELETE FROM supply_rep WHERE id = ? AND id NOT IN (SELECT fk_supply_rep FROM supplier WHERE fk_supply_rep IS NOT NULL)
+        RETURNING id";
+    let removed = connection.query_row(sql, &[Value::Integer(rep_id)], &[Value::Integer(0)])
+        .map_err(|_| DatabaseError::SubmissionError("Failed to remove the supplier representative".to_string()))?;
+
+    // the contact details of a rep still used by another supplier are kept
+    match (removed, contact_id) {
+        (Some(_), Some(contact_id)) => remove_unused_contact(contact_id, connection),
+        _ => Ok(()),
+    }
+}
+
+// Removes the email addresses and phone numbers of a contact which are not shared with another
+// contact, and then the contact itself if it is no longer used by a supplier or rep
+pub fn remove_unused_contact(contact_id: i64, connection: &dyn Backend) -> Result<(), DatabaseError> {
+    remove_contact_details(data_keys::EMAIL, contact_id, connection)?;
+    remove_contact_details(data_keys::NUMBER, contact_id, connection)?;
+
+    let sql = r"DELETE FROM contact WHERE id = ?1
+        AND id NOT IN (SELECT fk_contact FROM supplier WHERE fk_contact IS NOT NULL)
+        AND id NOT IN (SELECT fk_contact FROM supply_rep WHERE fk_contact IS NOT NULL)";
+    execute_with_values(sql, &[Value::Integer(contact_id)], "Failed to remove contact details", connection)
+}
+
+/// The ids of the address, contact and rep rows of a supplier
+pub struct SupplierLinks {
+    pub address_id: Option<i64>,
+    pub contact_id: Option<i64>,
+    pub rep_id: Option<i64>,
+}
+
+pub fn supplier_links(id: i64, connection: &dyn Backend) -> Result<SupplierLinks, DatabaseError> {
+    let error = || DatabaseError::QueryError("Failed to get the details of the supplier".to_string());
+
+    let sql = "SELECT fk_address, fk_contact, fk_supply_rep FROM supplier WHERE id = ?";
+    let row = connection.query_row(sql, &[Value::Integer(id)], &[Value::Integer(0), Value::Integer(0), Value::Integer(0)])
+        .map_err(|_| error())?;
+    match row {
+        Some(row) => Ok(SupplierLinks {
+            address_id: optional_id(&row[0]),
+            contact_id: optional_id(&row[1]),
+            rep_id: optional_id(&row[2]),
+        }),
+        None => Err(DatabaseError::NotFound(format!("There is no supplier with the id {}", id))),
+    }
+}
+
+// the id of the contact row of a rep, a rep which does not exist is not found
+pub fn supply_rep_contact_id(rep_id: i64, connection: &dyn Backend) -> Result<Option<i64>, DatabaseError> {
+    let error = || DatabaseError::QueryError("Failed to get the contact of the supplier representative".to_string());
+
+    match connection.query_row("SELECT fk_contact FROM supply_rep WHERE id = ?", &[Value::Integer(rep_id)], &[Value::Integer(0)]).map_err(|_| error())? {
+        Some(row) => Ok(optional_id(&row[0])),
+        None => Err(DatabaseError::NotFound(format!("There is no supplier representative with the id {}", rep_id))),
+    }
+}
+
+// an id read from a column which may be null
+fn optional_id(cell: &Value) -> Option<i64> {
+    match cell {
+        Value::Integer(id) => Some(*id),
+        _ => None,
+    }
+}
+
+// runs a statement with its "?" parameters bound to the values given. A statement refused by a
+// constraint of the database fails with a SubmissionError, any other failure with a QueryError.
+pub fn execute_with_values(sql: &str, values: &[Value], error_message: &str, connection: &dyn Backend) -> Result<(), DatabaseError> {
+    connection.execute(sql, values).map_err(|error| match error {
+        DatabaseError::SubmissionError(_) => DatabaseError::SubmissionError(error_message.to_string()),
+        _ => DatabaseError::QueryError(error_message.to_string()),
+    })
+}
+
+
+#[cfg(test)]
+mod test {
+    use crate::server::databases::sqlite::pool::SqliteConnection;
+
+    #[test]
+    fn test_insert_returns_the_id_of_its_own_row() {
+        let connection = sqlite::open(":memory:").unwrap();
+        connection.execute(r"
+            CREATE TABLE contact (id INTEGER PRIMARY KEY);
+            CREATE TABLE emails (id INTEGER PRIMARY KEY, Email TEXT);
+            INSERT INTO contact (id) VALUES (10);
+            CREATE TRIGGER email_contact AFTER INSERT ON emails BEGIN INSERT INTO contact (id) VALUES (NULL); END;
+        ").unwrap();
+        let connection = SqliteConnection::new(connection);
+
+        // the same value added twice is given two rows, and the rows added by the trigger are not returned
+        let sql = super::BoundSql::new("INSERT INTO emails (Email) VALUES (?) RETURNING id", vec![super::Value::String("a@b.c".to_string())]);
+        assert_eq!(sql.insert("failed", &connection).unwrap(), 1);
+        assert_eq!(sql.insert("failed", &connection).unwrap(), 2);
+    }
+}
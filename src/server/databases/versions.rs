@@ -0,0 +1,129 @@
+use crate::errors::DatabaseError;
+use crate::server::api::query_types::{Query, FieldSelection};
+use crate::server::databases::{
+    backend::Backend,
+    data_structs::Value,
+    util::execute_with_values,
+};
+
+///
+/// The query which gives the current representation of the resource changed by a query, and
+/// so the resource whose version is checked before the change is made
+///
+pub fn resource_query(query: &Query) -> Option<Query> {
+    match query {
+        Query::GETSupplierFromId(_, _) |
+        Query::GETSupplierAddressFromId(_) |
+        Query::GETSupplierRepFromId(_) |
+        Query::GETSupplyRepFromId(_) => Some(query.clone()),
+        Query::PATCHSupplier(id) |
+        Query::PUTSupplier(id) |
+        Query::DELETESupplier(id, _) |
+        Query::POSTSupplierRestore(id) => Some(Query::GETSupplierFromId(*id, FieldSelection::default())),
+        Query::PUTSupplierAddress(id) => Some(Query::GETSupplierAddressFromId(*id)),
+        Query::PUTSupplyRep(id) => Some(Query::GETSupplyRepFromId(*id)),
+        _ => None,
+    }
+}
+
+///
+/// The current version of the resource named by a query, see resource_query. None is returned
+/// if the query does not name a versioned resource, or the resource does not exist.
+///
+pub fn current_version(query: &Query, connection: &dyn Backend) -> Result<Option<i64>, DatabaseError> {
+    let (sql, id) = match resource_query(query) {
+        Some(Query::GETSupplierFromId(id, _)) => ("SELECT version FROM supplier WHERE id = ?", id),
+        Some(Query::GETSupplierAddressFromId(id)) => (r"SELECT a.version FROM address as a
+            JOIN supplier as s ON s.fk_address = a.id WHERE s.id = ?", id),
+        Some(Query::GETSupplierRepFromId(id)) => (r"SELECT r.version FROM supply_rep as r
+            JOIN supplier as s ON s.fk_supply_rep = r.id WHERE s.id = ?", id),
+        Some(Query::GETSupplyRepFromId(id)) => ("SELECT version FROM supply_rep WHERE id = ?", id),
+        _ => return Ok(None),
+    };
+    let error = || DatabaseError::QueryError("Failed to read the version of the resource".to_string());
+
+    match connection.query_row(sql, &[Value::Integer(id as i64)], &[Value::Integer(0)]).map_err(|_| error())? {
+        Some(row) => match row[0] {
+            Value::Integer(version) => Ok(Some(version)),
+            _ => Err(error()),
+        },
+        None => Ok(None),
+    }
+}
+
+///
+/// Checks the If-Match header of a request which changes a resource against the resource's
+/// current version. A resource which exists can only be changed by a request naming its
+/// current version (or "*"), so that a change made since the client read the resource is
+/// not overwritten. A resource which does not yet exist has nothing to overwrite.
+///
+pub fn check_precondition(query: &Query, if_match: Option<&str>, connection: &dyn Backend) -> Result<(), DatabaseError> {
+    let version = current_version(query, connection)?;
+
+    match (version, if_match) {
+        (None, None) => Ok(()),
+        (Some(_), None) => Err(DatabaseError::PreconditionRequired(
+            "An If-Match header holding the ETag of the resource is needed to change it".to_string()
+        )),
+        (version, Some(if_match)) => {
+            if if_match_matches(if_match, version) {
+                Ok(())
+            } else {
+                Err(DatabaseError::PreconditionFailed(
+                    "The resource has been changed since it was read, the If-Match header does not hold its current ETag".to_string()
+                ))
+            }
+        },
+    }
+}
+
+/// The ETag of a version of a resource
+pub fn etag(version: i64) -> String {
+    format!("\"{}\"", version)
+}
+
+// If-Match holds "*" or a list of ETags, compared using the strong comparison of RFC 9110 so a
+// weak ETag never matches. Neither matches a resource which does not exist.
+fn if_match_matches(if_match: &str, version: Option<i64>) -> bool {
+    let version = match version {
+        Some(version) => version,
+        None => return false,
+    };
+    let current = etag(version);
+    if_match.split(',').map(|tag| tag.trim()).any(|tag| tag == "*" || tag == current)
+}
+
+/// Raises the version of a supplier
+pub fn bump_supplier(id: i64, connection: &dyn Backend) -> Result<(), DatabaseError> {
+    let sql = "UPDATE supplier SET version = version + 1 WHERE id = ?";
+    execute_with_values(sql, &[Value::Integer(id)], "Failed to update the supplier version", connection)
+}
+
+/// Raises the version of an address, and of each supplier holding it
+pub fn bump_address(id: i64, connection: &dyn Backend) -> Result<(), DatabaseError> {
+    let values = [Value::Integer(id)];
+    execute_with_values("UPDATE address SET version = version + 1 WHERE id = ?", &values, "Failed to update the address version", connection)?;
+    execute_with_values("UPDATE supplier SET version = version + 1 WHERE fk_address = ?", &values, "Failed to update the supplier version", connection)
+}
+
+/// Raises the version of a rep, and of each supplier holding it
+pub fn bump_rep(id: i64, connection: &dyn Backend) -> Result<(), DatabaseError> {
+    let values = [Value::Integer(id)];
+    execute_with_values("UPDATE supply_rep SET version = version + 1 WHERE id = ?", &values, "Failed to update the rep version", connection)?;
+    execute_with_values("UPDATE supplier SET version = version + 1 WHERE fk_supply_rep = ?", &values, "Failed to update the supplier version", connection)
+}
+
+
+#[cfg(test)]
+mod test {
+
+    #[test]
+    fn test_if_match_matches() {
+        assert!(super::if_match_matches("\"3\"", Some(3)));
+        assert!(super::if_match_matches("\"1\", \"3\"", Some(3)));
+        assert!(super::if_match_matches("*", Some(3)));
+        assert!(!super::if_match_matches("\"2\"", Some(3)));
+        assert!(!super::if_match_matches("W/\"3\"", Some(3)));
+        assert!(!super::if_match_matches("*", None));
+    }
+}
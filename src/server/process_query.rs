@@ -1,24 +1,35 @@
 
 
 use crate::errors::DatabaseError;
-use crate::server::api::query_types::Query;
-use crate::server::connection::Request;
-use crate::server::databases::sqlite;
+use crate::server::api::query_types::{Query, Content, DeleteMode, OutputFormat, FieldSelection};
+use crate::config::{MAX_IDEMPOTENCY_KEY_LEN, ServerConfig};
+use crate::server::api::parsing::negotiate_format;
+use crate::server::connection::{Request, is_json_media_type};
+use crate::server::databases::{backend::Database, requests, versions, idempotency::{self, Reservation, StoredResponse}};
 
-use crate::server::api::config::responses::{self, standard_json_response};
+use crate::server::api::problem::Problem;
 
-pub fn get_request(query: Query, request: Request) -> (String, String, String) {
+pub fn get_request(query: Query, request: Request, headers: &mut Vec<(String, String)>, database: &Database) -> (String, String, String) {
 
     // specify which process function to use based on the query
     // these will unusually tied to some sort of database query
     let result: Result<String,DatabaseError>;
 
+    // database rows can be rendered as json, csv or xml
+    let format = match negotiate_format(request.header("Accept"), &request.query_params) {
+        Some(format) => format,
+        None => {
+            let detail = "The resource can only be given as application/json, text/csv or application/xml";
+            return Problem::new(406, detail).response(&request.path, &request.request_id);
+        },
+    };
+
     match query {
-        Query::GETSuppliers | 
+        Query::GETSuppliers(_) | 
         Query::GETSuppliersCategories | 
-        Query::GETSuppliersEmail | 
-        Query::GETSuppliersNumbers | 
-        Query::GETSupplierFromId(_) |
+        Query::GETSuppliersEmail(_) | 
+        Query::GETSuppliersNumbers(_) | 
+        Query::GETSupplierFromId(_, _) |
         Query::GETSupplierIdFromName(_) |
         Query::GETSupplierNameFromId(_) |
         Query::GETSupplierEmailFromId(_) |
@@ -28,8 +39,12 @@ pub fn get_request(query: Query, request: Request) -> (String, String, String) {
         Query::GETSupplierRepFromId(_) |
         Query::GETSupplyRepFromId(_) |
         Query::GETSupplyRepPhoneNumbersFromId(_) |
-        Query::GETSupplyRepEmailFromId(_) => {
-            result = sqlite::get_request(query);
+        Query::GETSupplyRepEmailFromId(_) |
+        Query::GETSearch(_) => {
+            if let Err(error) = resource_etag(&query, headers, database) {
+                return Problem::from_database_error(&error).response(&request.path, &request.request_id);
+            }
+            result = requests::get_request(query, request.api_version, format, database);
         },
         _ => {
             panic!("Invalid GET query: {:?}", query);
@@ -40,11 +55,10 @@ pub fn get_request(query: Query, request: Request) -> (String, String, String) {
     }
     match result {
         Ok(content) => {
-            return (content, String::from("application/json"), String::from("HTTP/1.1 200 OK"));
+            (content, String::from(format.content_type()), String::from("HTTP/1.1 200 OK"))
         },
         Err(error) => {
-            println!("Error: {:?}", error);
-            return standard_json_response(responses::JSON_SERVER_ERROR);
+            Problem::from_database_error(&error).response(&request.path, &request.request_id)
         }
     }
 
@@ -53,29 +67,764 @@ pub fn get_request(query: Query, request: Request) -> (String, String, String) {
 
 
 
-pub fn post_request(query: Query, request: Request) -> (String, String, String) {
-    let mut result: Result<String,DatabaseError>;
+// Adds the ETag of the resource named by a query to the response, if it is versioned
+fn resource_etag(query: &Query, headers: &mut Vec<(String, String)>, database: &Database) -> Result<(), DatabaseError> {
+    if let Some(etag) = requests::resource_etag(query, database)? {
+        headers.push((String::from("ETag"), etag));
+    }
+    Ok(())
+}
+
+// Adds the ETag of a resource which a request has changed. The change has been made whether or not
+// its ETag can be read, so the response is given without it, and the failure is logged.
+fn changed_resource_etag(query: &Query, headers: &mut Vec<(String, String)>, database: &Database) {
+    if let Err(error) = resource_etag(query, headers, database) {
+        println!("Error: The ETag of a changed resource could not be read: {}", error.message());
+    }
+}
+
+// The problem response for a request which failed. A request which failed because the resource 
+// has changed since the client read it is given the resource as it is now, along with its ETag,
+// so the client can apply its change again.
+fn error_response(query: &Query, error: &DatabaseError, request: &Request, headers: &mut Vec<(String, String)>, database: &Database) -> (String, String, String) {
+    let mut problem = Problem::from_database_error(error);
 
-    match &query {
-        Query::POSTSupplier(_) => {
-            result = sqlite::post_request(query, request.body);
+    // the current resource is only of use to the client along with its ETag
+    if let (DatabaseError::PreconditionFailed(_), Some(resource)) = (error, versions::resource_query(query)) {
+        if resource_etag(&resource, headers, database).is_err() {
+            return problem.response(&request.path, &request.request_id);
+        }
+        let current = requests::get_request(resource, request.api_version, OutputFormat::Json, database)
+            .ok()
+            .and_then(|content| json::parse(&content).ok());
+        if let Some(current) = current {
+            problem.members.push((String::from("current"), current["payload"].clone()));
+        }
+    }
+    problem.response(&request.path, &request.request_id)
+}
+
+// The problem with the body of a request which should hold json, None if the body is json
+fn json_body_problem(request: &Request) -> Option<Problem> {
+    if matches!(request.body, Content::Json(_)) {
+        return None;
+    }
+    match request.header("Content-Type") {
+        Some(content_type) if is_json_media_type(content_type) => {
+            Some(Problem::new(400, "The request body is not valid json"))
+        },
+        _ => Some(Problem::new(415, "The request body must be sent as application/json")),
+    }
+}
+
+// Identifies a request by its method, target (the path and query string) and body, so that a key sent
+// again with a different request can be told apart from a retry
+fn request_fingerprint(request: &Request) -> String {
+    let body = match &request.body {
+        Content::Json(body) => body.dump(),
+        _ => String::new(),
+    };
+    idempotency::fingerprint(&[&request.method, &request.target, &body])
+}
+
+// A retry is given the response stored for its key, along with its headers, or told to wait while
+// the request which holds the key is carried out, while a key reused for a different request is
+// rejected. None if the key was reserved by this request.
+fn idempotent_response(reservation: Reservation, fingerprint: &str, request: &Request, headers: &mut Vec<(String, String)>) -> Option<(String, String, String)> {
+    let held = match &reservation {
+        Reservation::Reserved => return None,
+        Reservation::InFlight(held) => held,
+        Reservation::Stored(stored) => &stored.fingerprint,
+    };
+    if held != fingerprint {
+        let detail = "The Idempotency-Key has already been used for a different request";
+        return Some(Problem::new(422, detail).response(&request.path, &request.request_id));
+    }
+    match reservation {
+        Reservation::Stored(mut stored) => {
+            headers.append(&mut stored.headers);
+            Some((stored.content, stored.content_type, stored.status_line))
+        },
+        _ => {
+            let detail = "A request sent with the Idempotency-Key is still being carried out";
+            Some(Problem::new(409, detail).response(&request.path, &request.request_id))
+        },
+    }
+}
+
+pub fn post_request(query: Query, request: Request, headers: &mut Vec<(String, String)>, database: &Database) -> (String, String, String) {
+
+    // restoring a supplier is the only post request which does not take a body
+    if !matches!(query, Query::POSTSupplierRestore(_)) {
+        if let Some(problem) = json_body_problem(&request) {
+            return problem.response(&request.path, &request.request_id);
+        }
+    }
+
+    // a request sent with an Idempotency-Key is only carried out once, see idempotent_response
+    let key = match request.header("Idempotency-Key").map(|key| key.trim()) {
+        Some(key) if key.is_empty() || key.len() > MAX_IDEMPOTENCY_KEY_LEN => {
+            let detail = format!("The Idempotency-Key must be between 1 and {} characters long", MAX_IDEMPOTENCY_KEY_LEN);
+            return Problem::new(400, &detail).response(&request.path, &request.request_id);
+        },
+        Some(key) => Some(key.to_string()),
+        None => None,
+    };
+    let fingerprint = request_fingerprint(&request);
+    if let Some(key) = &key {
+        match idempotency::reserve(key, &fingerprint, database) {
+            Ok(reservation) => {
+                if let Some(response) = idempotent_response(reservation, &fingerprint, &request, headers) {
+                    return response;
+                }
+            },
+            Err(error) => return Problem::from_database_error(&error).response(&request.path, &request.request_id),
+        }
+    }
+
+    // a batch reports the outcome of each of its items rather than creating a single resource
+    let status_line = match &query {
+        Query::POSTSuppliersBatch(_) |
+        Query::POSTSupplierRestore(_) => String::from("HTTP/1.1 200 OK"),
+        _ => String::from("HTTP/1.1 201 Created"),
+    };
+
+    let resource = query.clone();
+    let result = match &query {
+        Query::POSTSupplier(_) |
+        Query::POSTSuppliersBatch(_) => {
+            requests::post_request(query, request.body.clone(), database)
+        },
+        Query::POSTSupplierRestore(_) => {
+            requests::delete_request(query, request.api_version, request.header("If-Match"), database)
         },
         _ => {
             panic!("Invalid POST query: {:?}", query);
         }
+    };
+    match result {
+        Ok(content) => {
+            // a created supplier is named by its Location, and given the ETag of its first version
+            match created_supplier(&resource, &content, &request) {
+                Some((location, supplier)) => {
+                    headers.push((String::from("Location"), location));
+                    changed_resource_etag(&supplier, headers, database);
+                },
+                None => changed_resource_etag(&resource, headers, database),
+            }
+
+            // only a request which succeeded is stored, a failed request changed nothing so can be sent again.
+            // A key whose response could not be stored is given up once IDEMPOTENCY_IN_FLIGHT_SECS have passed.
+            if let Some(key) = &key {
+                let stored = StoredResponse {
+                    fingerprint,
+                    content: content.clone(),
+                    content_type: String::from("application/json"),
+                    status_line: status_line.clone(),
+                    headers: headers.iter().filter(|(name, _)| name == "ETag" || name == "Location").cloned().collect(),
+                };
+                let _ = idempotency::store_response(key, &stored, database);
+            }
+            (content, String::from("application/json"), status_line)
+        },
+        Err(error) => {
+            // the key of a failed request is given up, so the request can be sent again, a key which
+            // could not be released is given up once IDEMPOTENCY_IN_FLIGHT_SECS have passed
+            if let Some(key) = &key {
+                let _ = idempotency::release(key, database);
+            }
+            error_response(&resource, &error, &request, headers, database)
+        },
     }
-    (String::from("content"), String::from("content_type"), String::from("status_line"))
 
 }
 
-pub fn put_request(query: Query, request: Request) -> (String, String, String) {
-    (String::from("content"), String::from("content_type"), String::from("status_line"))
+// The Location of the supplier created by a request, e.g. "/api/v2/supplier/3", along with the
+// query for the supplier. None if the request did not create a supplier.
+fn created_supplier(query: &Query, content: &str, request: &Request) -> Option<(String, Query)> {
+    if !matches!(query, Query::POSTSupplier(_)) {
+        return None;
+    }
+    let id = json::parse(content).ok()?["payload"]["id"].as_u64()?;
+    let location = format!("{}/supplier/{}", request.path.strip_suffix("/suppliers")?, id);
+    Some((location, Query::GETSupplierFromId(id, FieldSelection::default())))
+}
+
+pub fn patch_request(query: Query, request: Request, headers: &mut Vec<(String, String)>, database: &Database) -> (String, String, String) {
+
+    // a merge patch is sent as application/merge-patch+json, though application/json is also accepted
+    if let Some(problem) = json_body_problem(&request) {
+        return problem.response(&request.path, &request.request_id);
+    }
+
+    let resource = query.clone();
+    let result = match &query {
+        Query::PATCHSupplier(_) => {
+            requests::patch_request(query, request.body.clone(), request.api_version, request.header("If-Match"), database)
+        },
+        _ => {
+            panic!("Invalid PATCH query: {:?}", query);
+        }
+    };
+    match result {
+        Ok(content) => {
+            changed_resource_etag(&resource, headers, database);
+            (content, String::from("application/json"), String::from("HTTP/1.1 200 OK"))
+        },
+        Err(error) => error_response(&resource, &error, &request, headers, database),
+    }
+
+}
+
+pub fn put_request(query: Query, request: Request, headers: &mut Vec<(String, String)>, database: &Database) -> (String, String, String) {
+
+    if let Some(problem) = json_body_problem(&request) {
+        return problem.response(&request.path, &request.request_id);
+    }
+
+    let resource = query.clone();
+    let result = match &query {
+        Query::PUTSupplier(_) |
+        Query::PUTSupplierAddress(_) |
+        Query::PUTSupplyRep(_) => {
+            requests::put_request(query, request.body.clone(), request.api_version, request.header("If-Match"), database)
+        },
+        _ => {
+            panic!("Invalid PUT query: {:?}", query);
+        }
+    };
+    match result {
+        Ok(content) => {
+            changed_resource_etag(&resource, headers, database);
+            (content, String::from("application/json"), String::from("HTTP/1.1 200 OK"))
+        },
+        Err(error) => error_response(&resource, &error, &request, headers, database),
+    }
+
+}
+
+pub fn delete_request(query: Query, request: Request, headers: &mut Vec<(String, String)>, config: &ServerConfig, database: &Database) -> (String, String, String) {
+
+    if let Query::DELETESupplier(_, DeleteMode::Purge) = query {
+        if let Some(problem) = admin_problem(&request, config) {
+            return problem.response(&request.path, &request.request_id);
+        }
+    }
+
+    let resource = query.clone();
+    let result = match &query {
+        Query::DELETESupplier(_, _) => {
+            requests::delete_request(query, request.api_version, request.header("If-Match"), database)
+        },
+        _ => {
+            panic!("Invalid DELETE query: {:?}", query);
+        }
+    };
+    match result {
+        Ok(_) => (String::new(), String::from("application/json"), String::from("HTTP/1.1 204 No Content")),
+        Err(error) => error_response(&resource, &error, &request, headers, database),
+    }
 
 }
 
-pub fn delete_request(query: Query, request: Request) -> (String, String, String) {
-    (String::from("content"), String::from("content_type"), String::from("status_line"))
+// Purging data is limited to admins, who send the admin token of the server's config, read from
+// the POS_ADMIN_TOKEN environment variable, as a bearer token. Nothing can be purged without one.
+fn admin_problem(request: &Request, config: &ServerConfig) -> Option<Problem> {
+    let token = config.admin_token.as_ref();
+    let given = request.header("Authorization")
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(|value| value.trim());
+
+    match (token, given) {
+        (Some(token), Some(given)) if tokens_match(token, given) => None,
+        _ => Some(Problem::new(403, "An admin token is needed to purge data")),
+    }
+}
 
+// compares every byte of the tokens, so the time taken does not give away how much of a token is right
+fn tokens_match(token: &str, given: &str) -> bool {
+    token.len() == given.len() && token.bytes().zip(given.bytes()).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
 }
 
 
+
+#[cfg(test)]
+mod test {
+    use json::JsonValue;
+    use crate::server::api::{uri_to_api_query, uri::split_request_target, routing::ApiVersions, query_types::Content};
+    use crate::server::connection::Request;
+    use crate::config::{IDEMPOTENCY_IN_FLIGHT_SECS, ServerConfig};
+    use crate::server::databases::{backend::Database, data_structs::Value, migrations, idempotency};
+
+    // Two suppliers, the first with an address and an email address
+    const SEED: &str = r"
+        INSERT INTO address (Line1, Town, Postcode) VALUES ('1 High St', 'Leeds', 'LS1 1AA');
+        INSERT INTO supplier (name, fk_address) VALUES ('Acme Tools', 1);
+        INSERT INTO supplier (name) VALUES ('Bolt Supplies');
+        INSERT INTO emails (Email) VALUES ('sales@acme.test');
+        INSERT INTO contact_email (fk_email_addresses, fk_contact) VALUES (1, (SELECT fk_contact FROM supplier WHERE id = 1));
+    ";
+
+    // A database of its own for each test, held in memory, so tests can run side by side
+    fn database() -> Database {
+        let database = Database::sqlite(":memory:", 1).unwrap();
+        database.connect(|connection| {
+            migrations::migrate(connection)?;
+            connection.execute_batch(SEED)
+        }).unwrap();
+        database
+    }
+
+    // Sends a request as send_to does, to a server whose admin token is "admin-token"
+    fn send(method: &str, target: &str, body: Option<JsonValue>, headers: &[(&str, &str)], database: &Database) -> (String, JsonValue, Vec<(String, String)>) {
+        let config = ServerConfig { admin_token: Some(String::from("admin-token")) };
+        send_to(&config, method, target, body, headers, database)
+    }
+
+    // Sends a request through the api routes to the handler of its method, as a connection does,
+    // returning the status line, the json content and the headers of the response
+    fn send_to(config: &ServerConfig, method: &str, target: &str, body: Option<JsonValue>, headers: &[(&str, &str)], database: &Database) -> (String, JsonValue, Vec<(String, String)>) {
+        let api_versions = ApiVersions::new();
+        let (path, query_params) = split_request_target(target).unwrap();
+        let api_tree = api_versions.for_path(&path);
+        let query = uri_to_api_query(&path, method, &query_params, api_tree).unwrap();
+        let request = Request {
+            method: method.to_string(),
+            target: target.to_string(),
+            path,
+            query_params,
+            http_version: String::from("HTTP/1.1"),
+            headers: headers.iter().map(|(name, value)| (name.to_string(), value.to_string())).collect(),
+            body: body.map(Content::Json).unwrap_or(Content::None),
+            api_version: api_tree.version,
+            request_id: String::from("test"),
+        };
+
+        let mut response_headers = Vec::new();
+        let (content, _, status_line) = match method {
+            "GET" => super::get_request(query, request, &mut response_headers, database),
+            "POST" => super::post_request(query, request, &mut response_headers, database),
+            "PATCH" => super::patch_request(query, request, &mut response_headers, database),
+            "PUT" => super::put_request(query, request, &mut response_headers, database),
+            "DELETE" => super::delete_request(query, request, &mut response_headers, config, database),
+            _ => panic!("Unsupported method {}", method),
+        };
+        (status_line, json::parse(&content).unwrap_or(JsonValue::Null), response_headers)
+    }
+
+    fn etag(headers: &[(String, String)]) -> Option<&str> {
+        headers.iter().find(|(name, _)| name == "ETag").map(|(_, value)| value.as_str())
+    }
+
+    #[test]
+    fn test_get_reads_the_seeded_database() {
+        let database = database();
+
+        let (status, content, headers) = send("GET", "/api/supplier/1", None, &[], &database);
+        assert_eq!(status, "HTTP/1.1 200 OK");
+        assert_eq!(content["payload"]["name"], "Acme Tools");
+        assert_eq!(content["payload"]["address"]["postcode"], "LS1 1AA");
+        assert_eq!(content["payload"]["contact"]["email"][0], "sales@acme.test");
+        assert_eq!(etag(&headers), Some("\"1\""));
+
+        let (_, content, _) = send("GET", "/api/suppliers?limit=1", None, &[], &database);
+        assert_eq!(content["meta"]["total"], 2);
+        assert_eq!(content["payload"].len(), 1);
+
+        let (status, content, headers) = send("GET", "/api/supplier/9", None, &[], &database);
+        assert_eq!(status, "HTTP/1.1 404 Not Found");
+        assert_eq!(content["status"], 404);
+        assert_eq!(etag(&headers), None);
+        let (status, _, _) = send("GET", "/api/supplier/9?format=csv", None, &[], &database);
+        assert_eq!(status, "HTTP/1.1 404 Not Found");
+    }
+
+    #[test]
+    fn test_expanded_resources_are_kept_with_the_chosen_fields() {
+        let database = database();
+
+        let (status, content, _) = send("GET", "/api/suppliers?fields=name&expand=address", None, &[], &database);
+        assert_eq!(status, "HTTP/1.1 200 OK");
+        let supplier = &content["payload"][0];
+        assert_eq!(supplier.entries().map(|(key, _)| key).collect::<Vec<_>>(), vec!["name", "address"]);
+        assert_eq!(supplier["address"]["postcode"], "LS1 1AA");
+        assert!(content["payload"][1]["address"].is_null());
+
+        let (_, content, _) = send("GET", "/api/supplier/1?fields=id&expand=contact", None, &[], &database);
+        assert_eq!(content["payload"].entries().map(|(key, _)| key).collect::<Vec<_>>(), vec!["id", "contact"]);
+        assert_eq!(content["payload"]["contact"]["email"][0], "sales@acme.test");
+    }
+
+    #[test]
+    fn test_changes_are_made_to_the_current_version() {
+        let database = database();
+
+        let body = json::object!{ "name" => "Cog Works", "contact" => json::object!{ "email" => json::array!["cogs@cog.test"] } };
+        let (status, content, _) = send("POST", "/api/suppliers", Some(body), &[], &database);
+        assert_eq!(status, "HTTP/1.1 201 Created");
+        assert_eq!(content["payload"]["id"], 3);
+
+        // a change needs the ETag of the version it was made to
+        let patch = json::object!{ "name" => "Cog Works Ltd" };
+        let (status, _, _) = send("PATCH", "/api/supplier/3", Some(patch.clone()), &[], &database);
+        assert_eq!(status, "HTTP/1.1 428 Precondition Required");
+        let (status, content, headers) = send("PATCH", "/api/supplier/3", Some(patch.clone()), &[("If-Match", "\"1\"")], &database);
+        assert_eq!(status, "HTTP/1.1 200 OK");
+        assert_eq!(content["payload"]["name"], "Cog Works Ltd");
+        assert_eq!(etag(&headers), Some("\"2\""));
+
+        // a stale ETag is refused, and the supplier as it is now is given back
+        let (status, content, _) = send("PATCH", "/api/supplier/3", Some(patch), &[("If-Match", "\"1\"")], &database);
+        assert_eq!(status, "HTTP/1.1 412 Precondition Failed");
+        assert_eq!(content["current"]["name"], "Cog Works Ltd");
+
+        // a soft deleted supplier is kept, but is no longer active
+        let (status, _, _) = send("DELETE", "/api/supplier/3", None, &[("If-Match", "*")], &database);
+        assert_eq!(status, "HTTP/1.1 204 No Content");
+        let (_, content, _) = send("GET", "/api/supplier/3", None, &[], &database);
+        assert_eq!(content["payload"]["active"], false);
+    }
+
+    #[test]
+    fn test_a_deleted_supplier_can_be_restored() {
+        let database = database();
+
+        // a delete needs the current ETag of the supplier
+        let (status, _, _) = send("DELETE", "/api/supplier/1", None, &[], &database);
+        assert_eq!(status, "HTTP/1.1 428 Precondition Required");
+        let (status, content, _) = send("DELETE", "/api/supplier/1", None, &[("If-Match", "\"9\"")], &database);
+        assert_eq!(status, "HTTP/1.1 412 Precondition Failed");
+        assert_eq!(content["current"]["active"], true);
+
+        let (status, _, _) = send("DELETE", "/api/supplier/1", None, &[("If-Match", "\"1\"")], &database);
+        assert_eq!(status, "HTTP/1.1 204 No Content");
+        let (_, content, headers) = send("GET", "/api/supplier/1", None, &[], &database);
+        assert_eq!(content["payload"]["active"], false);
+        assert_eq!(etag(&headers), Some("\"2\""));
+
+        // as is a restore, which gives back the supplier as it now is
+        let (status, _, _) = send("POST", "/api/supplier/1/restore", None, &[("If-Match", "\"1\"")], &database);
+        assert_eq!(status, "HTTP/1.1 412 Precondition Failed");
+        let (status, content, headers) = send("POST", "/api/supplier/1/restore", None, &[("If-Match", "\"2\"")], &database);
+        assert_eq!(status, "HTTP/1.1 200 OK");
+        assert_eq!(content["payload"]["active"], true);
+        assert_eq!(content["payload"]["address"]["postcode"], "LS1 1AA");
+        assert_eq!(etag(&headers), Some("\"3\""));
+    }
+
+    #[test]
+    fn test_a_purge_removes_only_the_rows_no_other_supplier_uses() {
+        let admin = [("If-Match", "*"), ("Authorization", "Bearer admin-token")];
+        let database = database();
+
+        // the second supplier shares the address and email address of the first, whose phone number is its own
+        database.connect(|connection| connection.execute_batch(r"
+            UPDATE supplier SET fk_address = 1 WHERE id = 2;
+            INSERT INTO contact_email (fk_email_addresses, fk_contact) VALUES (1, (SELECT fk_contact FROM supplier WHERE id = 2));
+            INSERT INTO phone_numbers (Number) VALUES ('0113 496 0000');
+            INSERT INTO contact_phone (fk_phone_number, fk_contact) VALUES (1, (SELECT fk_contact FROM supplier WHERE id = 1));
+        ")).unwrap();
+        let count = |table: &str| database.connect(|connection| {
+            connection.query_row(&format!("SELECT COUNT(*) FROM {}", table), &[], &[Value::Integer(0)])
+        }).unwrap().unwrap()[0].clone();
+
+        let (status, _, _) = send("DELETE", "/api/supplier/1?mode=purge", None, &[("If-Match", "*")], &database);
+        assert_eq!(status, "HTTP/1.1 403 Forbidden");
+        let (status, _, _) = send_to(&ServerConfig::default(), "DELETE", "/api/supplier/1?mode=purge", None, &admin, &database);
+        assert_eq!(status, "HTTP/1.1 403 Forbidden");
+
+        let (status, _, _) = send("DELETE", "/api/supplier/1?mode=purge", None, &admin, &database);
+        assert_eq!(status, "HTTP/1.1 204 No Content");
+        let (status, _, _) = send("GET", "/api/supplier/1", None, &[], &database);
+        assert_eq!(status, "HTTP/1.1 404 Not Found");
+
+        // the shared rows are kept for the second supplier, and the phone number is removed
+        let (_, content, _) = send("GET", "/api/supplier/2", None, &[], &database);
+        assert_eq!(content["payload"]["address"]["postcode"], "LS1 1AA");
+        assert_eq!(content["payload"]["contact"]["email"][0], "sales@acme.test");
+        assert_eq!(count("phone_numbers"), Value::Integer(0));
+
+        // once no supplier uses them, they are removed along with the supplier
+        let (status, _, _) = send("DELETE", "/api/supplier/2?mode=purge", None, &admin, &database);
+        assert_eq!(status, "HTTP/1.1 204 No Content");
+        for table in ["supplier", "address", "emails", "contact_email", "contact"] {
+            assert_eq!(count(table), Value::Integer(0), "{} still has rows", table);
+        }
+    }
+
+    #[test]
+    fn test_a_change_keeps_the_rows_another_supplier_uses() {
+        let database = database();
+        let rep = json::object!{ "title" => "Dr", "firstName" => "Al", "lastName" => "Smith" };
+        let (status, _, _) = send("PATCH", "/api/supplier/1", Some(json::object!{ "rep" => rep }), &[("If-Match", "*")], &database);
+        assert_eq!(status, "HTTP/1.1 200 OK");
+
+        // the second supplier shares the address, email address and rep of the first
+        database.connect(|connection| connection.execute_batch(r"
+            UPDATE supplier SET fk_address = 1, fk_supply_rep = 1 WHERE id = 2;
+            INSERT INTO contact_email (fk_email_addresses, fk_contact) VALUES (1, (SELECT fk_contact FROM supplier WHERE id = 2));
+        ")).unwrap();
+
+        let patch = json::object!{ "address" => JsonValue::Null, "rep" => JsonValue::Null, "contact" => json::object!{ "email" => json::array!["orders@acme.test"] } };
+        let (status, content, _) = send("PATCH", "/api/supplier/1", Some(patch), &[("If-Match", "*")], &database);
+        assert_eq!(status, "HTTP/1.1 200 OK");
+        assert!(content["payload"]["address"].is_null());
+        assert!(content["payload"]["rep"].is_null());
+        assert_eq!(content["payload"]["contact"]["email"][0], "orders@acme.test");
+
+        let (_, content, _) = send("GET", "/api/supplier/2", None, &[], &database);
+        assert_eq!(content["payload"]["address"]["postcode"], "LS1 1AA");
+        assert_eq!(content["payload"]["rep"]["lastName"], "Smith");
+        assert_eq!(content["payload"]["contact"]["email"][0], "sales@acme.test");
+
+        // a replacement leaves them in place too, and once no supplier uses them they are removed
+        database.connect(|connection| connection.execute_batch("UPDATE supplier SET fk_address = 1, fk_supply_rep = 1 WHERE id = 1")).unwrap();
+        let (status, _, _) = send("PUT", "/api/supplier/2", Some(json::object!{ "name" => "Bolt Supplies" }), &[("If-Match", "*")], &database);
+        assert_eq!(status, "HTTP/1.1 200 OK");
+        let (_, content, _) = send("GET", "/api/supplier/1", None, &[], &database);
+        assert_eq!(content["payload"]["address"]["postcode"], "LS1 1AA");
+        assert_eq!(content["payload"]["rep"]["lastName"], "Smith");
+
+        let (status, _, _) = send("PUT", "/api/supplier/1", Some(json::object!{ "name" => "Acme Tools" }), &[("If-Match", "*")], &database);
+        assert_eq!(status, "HTTP/1.1 200 OK");
+        let count = |table: &str| database.connect(|connection| {
+            connection.query_row(&format!("SELECT COUNT(*) FROM {}", table), &[], &[Value::Integer(0)])
+        }).unwrap().unwrap()[0].clone();
+        for table in ["address", "supply_rep", "emails"] {
+            assert_eq!(count(table), Value::Integer(0), "{} still has rows", table);
+        }
+    }
+
+    #[test]
+    fn test_a_resource_whose_etag_can_not_be_read_is_not_given() {
+        let database = database();
+        database.connect(|connection| connection.execute_batch("ALTER TABLE address RENAME COLUMN version TO revision")).unwrap();
+
+        let (status, content, headers) = send("GET", "/api/supplier/1/address", None, &[], &database);
+        assert_eq!(status, "HTTP/1.1 500 Internal Server Error");
+        assert_eq!(content["status"], 500);
+        assert_eq!(etag(&headers), None);
+    }
+
+    #[test]
+    fn test_a_retried_post_is_carried_out_once() {
+        let database = database();
+        let key = [("Idempotency-Key", "retry-1")];
+
+        let (status, first, first_headers) = send("POST", "/api/suppliers", Some(json::object!{ "name" => "Dowel" }), &key, &database);
+        assert_eq!(status, "HTTP/1.1 201 Created");
+        let location = |headers: &[(String, String)]| headers.iter().find(|(name, _)| name == "Location").map(|(_, value)| value.clone());
+        assert_eq!(location(&first_headers).as_deref(), Some("/api/supplier/3"));
+        assert_eq!(etag(&first_headers), Some("\"1\""));
+
+        // the retry is given the response of the first request, along with its Location and ETag
+        let (status, _, _) = send("PATCH", "/api/supplier/3", Some(json::object!{ "name" => "Dowels" }), &[("If-Match", "*")], &database);
+        assert_eq!(status, "HTTP/1.1 200 OK");
+        let (status, retried, headers) = send("POST", "/api/suppliers", Some(json::object!{ "name" => "Dowel" }), &key, &database);
+        assert_eq!(status, "HTTP/1.1 201 Created");
+        assert_eq!(first, retried);
+        assert_eq!(location(&headers), location(&first_headers));
+        assert_eq!(etag(&headers), Some("\"1\""));
+
+        let (status, _, _) = send("POST", "/api/suppliers", Some(json::object!{ "name" => "Other" }), &key, &database);
+        assert_eq!(status, "HTTP/1.1 422 Unprocessable Entity");
+
+        let (_, content, _) = send("GET", "/api/suppliers", None, &[], &database);
+        assert_eq!(content["meta"]["total"], 3);
+    }
+
+    #[test]
+    fn test_a_key_is_held_by_one_of_the_requests_sent_with_it_at_once() {
+        // requests run side by side on connections of their own, so the database is kept in a file
+        let path = std::env::temp_dir().join(format!("pos-idempotency-{}.db", std::process::id()));
+        let path = path.to_str().unwrap().to_string();
+        let database = Database::sqlite(&path, 4).unwrap();
+        database.connect(|connection| {
+            migrations::migrate(connection)?;
+            connection.execute_batch(SEED)
+        }).unwrap();
+
+        let key = [("Idempotency-Key", "race-1")];
+        let barrier = std::sync::Barrier::new(4);
+        let statuses: Vec<String> = std::thread::scope(|scope| {
+            let sent: Vec<_> = (0..4).map(|_| scope.spawn(|| {
+                barrier.wait();
+                send("POST", "/api/suppliers", Some(json::object!{ "name" => "Dowel" }), &key, &database).0
+            })).collect();
+            sent.into_iter().map(|sent| sent.join().unwrap()).collect()
+        });
+        let (_, content, _) = send("GET", "/api/suppliers", None, &[], &database);
+        for file in ["", "-wal", "-shm"] {
+            let _ = std::fs::remove_file(format!("{}{}", path, file));
+        }
+
+        // the others are given the stored response, or told the key is still in use
+        assert!(statuses.iter().all(|status| status == "HTTP/1.1 201 Created" || status == "HTTP/1.1 409 Conflict"), "{:?}", statuses);
+        assert!(statuses.iter().any(|status| status == "HTTP/1.1 201 Created"));
+        assert_eq!(content["meta"]["total"], 3);
+    }
+
+    #[test]
+    fn test_a_key_in_use_is_not_carried_out_again() {
+        let database = database();
+        let key = [("Idempotency-Key", "in-flight-1")];
+        let body = json::object!{ "name" => "Dowel" };
+
+        // the key is reserved as it is by a request which has not yet finished
+        let fingerprint = idempotency::fingerprint(&["POST", "/api/suppliers", &body.dump()]);
+        assert!(matches!(idempotency::reserve("in-flight-1", &fingerprint, &database).unwrap(), idempotency::Reservation::Reserved));
+
+        let (status, _, _) = send("POST", "/api/suppliers", Some(body.clone()), &key, &database);
+        assert_eq!(status, "HTTP/1.1 409 Conflict");
+        let (status, _, _) = send("POST", "/api/suppliers", Some(json::object!{ "name" => "Other" }), &key, &database);
+        assert_eq!(status, "HTTP/1.1 422 Unprocessable Entity");
+
+        // once given up, as by a request which failed, the key can be used again
+        idempotency::release("in-flight-1", &database).unwrap();
+        let (status, _, _) = send("POST", "/api/suppliers", Some(body), &key, &database);
+        assert_eq!(status, "HTTP/1.1 201 Created");
+
+        // a key held by a request which never finished is given up once the request could no longer be running
+        let key = [("Idempotency-Key", "in-flight-2")];
+        let body = json::object!{ "name" => "Rivet" };
+        let fingerprint = idempotency::fingerprint(&["POST", "/api/suppliers", &body.dump()]);
+        assert!(matches!(idempotency::reserve("in-flight-2", &fingerprint, &database).unwrap(), idempotency::Reservation::Reserved));
+        let (status, _, _) = send("POST", "/api/suppliers", Some(body.clone()), &key, &database);
+        assert_eq!(status, "HTTP/1.1 409 Conflict");
+        let started = format!("UPDATE idempotency_keys SET created_at = created_at - {} WHERE key = 'in-flight-2'", IDEMPOTENCY_IN_FLIGHT_SECS + 1);
+        database.connect(|connection| connection.execute_batch(&started)).unwrap();
+        let (status, _, _) = send("POST", "/api/suppliers", Some(body), &key, &database);
+        assert_eq!(status, "HTTP/1.1 201 Created");
+    }
+
+    #[test]
+    fn test_search_follows_the_changes_made_through_the_api() {
+        let database = database();
+        let suppliers = |text: &str| {
+            let (_, content, _) = send("GET", &format!("/api/search?q={}", text), None, &[], &database);
+            content["payload"]["suppliers"].members().map(|supplier| supplier["text"].to_string()).collect::<Vec<_>>()
+        };
+        assert_eq!(suppliers("acme"), vec!["Acme Tools"]);
+
+        let (status, _, _) = send("PATCH", "/api/supplier/1", Some(json::object!{ "name" => "Zenith Tools" }), &[("If-Match", "*")], &database);
+        assert_eq!(status, "HTTP/1.1 200 OK");
+        assert!(suppliers("acme").is_empty());
+        assert_eq!(suppliers("tools"), vec!["Zenith Tools"]);
+
+        let (status, _, _) = send("PUT", "/api/supplier/2", Some(json::object!{ "name" => "Bolt Tools" }), &[("If-Match", "*")], &database);
+        assert_eq!(status, "HTTP/1.1 200 OK");
+        assert!(suppliers("supplies").is_empty());
+        assert_eq!(suppliers("tools").len(), 2);
+
+        let admin = [("If-Match", "*"), ("Authorization", "Bearer admin-token")];
+        let (status, _, _) = send("DELETE", "/api/supplier/1?mode=purge", None, &admin, &database);
+        assert_eq!(status, "HTTP/1.1 204 No Content");
+        assert_eq!(suppliers("tools"), vec!["Bolt Tools"]);
+        let (_, content, _) = send("GET", "/api/search?q=acme.test", None, &[], &database);
+        assert!(content["payload"]["emails"].is_empty());
+    }
+
+    #[test]
+    fn test_a_put_replaces_the_whole_resource() {
+        let database = database();
+
+        let (status, _, _) = send("PUT", "/api/supplier/1", Some(json::object!{ "name" => "Acme Tools Ltd" }), &[], &database);
+        assert_eq!(status, "HTTP/1.1 428 Precondition Required");
+
+        // the parts of the supplier left out of its replacement are removed
+        let (status, content, headers) = send("PUT", "/api/supplier/1", Some(json::object!{ "name" => "Acme Tools Ltd" }), &[("If-Match", "\"1\"")], &database);
+        assert_eq!(status, "HTTP/1.1 200 OK");
+        assert_eq!(content["payload"]["name"], "Acme Tools Ltd");
+        assert_eq!(content["payload"]["active"], true);
+        assert!(content["payload"]["address"].is_null());
+        assert!(content["payload"]["contact"]["email"].is_empty());
+        assert_eq!(etag(&headers), Some("\"2\""));
+
+        let (status, content, _) = send("PUT", "/api/supplier/1", Some(json::object!{ "id" => 2, "name" => "Bolt" }), &[("If-Match", "*")], &database);
+        assert_eq!(status, "HTTP/1.1 422 Unprocessable Entity");
+        assert_eq!(content["errors"][0]["field"], "id");
+        let (status, _, _) = send("PUT", "/api/supplier/9", Some(json::object!{ "name" => "Nobody" }), &[("If-Match", "*")], &database);
+        assert_eq!(status, "HTTP/1.1 404 Not Found");
+
+        // an address put in place of none has nothing to overwrite, so needs no ETag
+        let address = json::object!{ "line1" => "2 Mill Lane", "town" => "York", "postcode" => "YO1 7HH" };
+        let (status, _, _) = send("PUT", "/api/supplier/2/address", Some(address.clone()), &[("If-Match", "*")], &database);
+        assert_eq!(status, "HTTP/1.1 412 Precondition Failed");
+        let (status, content, _) = send("PUT", "/api/supplier/2/address", Some(address), &[], &database);
+        assert_eq!(status, "HTTP/1.1 200 OK");
+        assert_eq!(content["payload"]["postcode"], "YO1 7HH");
+        let (_, content, _) = send("GET", "/api/supplier/2", None, &[], &database);
+        assert_eq!(content["payload"]["address"]["town"], "York");
+    }
+
+    #[test]
+    fn test_a_batch_is_added_whole_or_item_by_item() {
+        let database = database();
+        let batch = json::array![
+            json::object!{ "name" => "Cog Works" },
+            json::object!{ "contact" => json::object!{ "email" => json::array!["nameless@cog.test"] } },
+        ];
+
+        // by default nothing is added while any supplier of the batch is at fault
+        let (status, content, _) = send("POST", "/api/suppliers/batch", Some(batch.clone()), &[], &database);
+        assert_eq!(status, "HTTP/1.1 422 Unprocessable Entity");
+        assert_eq!(content["errors"][0]["field"], "[1].name");
+        let (_, content, _) = send("GET", "/api/suppliers", None, &[], &database);
+        assert_eq!(content["meta"]["total"], 2);
+
+        let (status, content, _) = send("POST", "/api/suppliers/batch?mode=best-effort", Some(batch), &[], &database);
+        assert_eq!(status, "HTTP/1.1 200 OK");
+        assert_eq!(content["meta"]["created"], 1);
+        assert_eq!(content["meta"]["failed"], 1);
+        assert_eq!(content["payload"][0]["id"], 3);
+        assert_eq!(content["payload"][1]["success"], false);
+        assert_eq!(content["payload"][1]["errors"][0]["field"], "[1].name");
+
+        let (status, _, _) = send("POST", "/api/suppliers/batch", Some(json::object!{ "name" => "Cog Works" }), &[], &database);
+        assert_eq!(status, "HTTP/1.1 422 Unprocessable Entity");
+        let (_, content, _) = send("GET", "/api/suppliers", None, &[], &database);
+        assert_eq!(content["meta"]["total"], 3);
+    }
+
+    #[test]
+    fn test_search_groups_its_results_by_type() {
+        let database = database();
+
+        let (status, content, _) = send("GET", "/api/search?q=acme", None, &[], &database);
+        assert_eq!(status, "HTTP/1.1 200 OK");
+        let payload = &content["payload"];
+        assert_eq!(payload.entries().map(|(group, _)| group).collect::<Vec<_>>(), vec!["suppliers", "reps", "emails", "numbers", "addresses"]);
+        assert_eq!(payload["suppliers"][0]["id"], 1);
+        assert_eq!(payload["emails"][0]["text"], "sales@acme.test");
+        assert_eq!(payload["emails"][0]["supplierId"], 1);
+        assert!(payload["reps"].is_empty());
+
+        let (status, _, _) = send("GET", "/api/search?q=ac", None, &[], &database);
+        assert_eq!(status, "HTTP/1.1 400 Bad Request");
+    }
+
+    #[test]
+    fn test_a_rep_title_must_be_one_of_those_held() {
+        let database = database();
+
+        let rep = |title: &str| json::object!{ "title" => title, "firstName" => "Al", "lastName" => "Smith" };
+        let (status, content, _) = send("POST", "/api/suppliers", Some(json::object!{ "name" => "Cog Works", "rep" => rep("Prof") }), &[], &database);
+        assert_eq!(status, "HTTP/1.1 422 Unprocessable Entity");
+        assert_eq!(content["errors"][0]["field"], "rep.title");
+
+        let (status, _, _) = send("POST", "/api/suppliers", Some(json::object!{ "name" => "Cog Works", "rep" => rep("Dr") }), &[], &database);
+        assert_eq!(status, "HTTP/1.1 201 Created");
+
+        // the rep can not be given an unknown title in place of its own either
+        let (status, content, _) = send("PUT", "/api/supplier/rep/1", Some(rep("Prof")), &[("If-Match", "*")], &database);
+        assert_eq!(status, "HTTP/1.1 422 Unprocessable Entity");
+        assert_eq!(content["errors"][0]["field"], "title");
+        let patch = json::object!{ "rep" => rep("Prof") };
+        let (status, _, _) = send("PATCH", "/api/supplier/3", Some(patch), &[("If-Match", "*")], &database);
+        assert_eq!(status, "HTTP/1.1 422 Unprocessable Entity");
+
+        let (status, content, _) = send("GET", "/api/supplier/3/rep", None, &[], &database);
+        assert_eq!(status, "HTTP/1.1 200 OK");
+        assert_eq!(content["payload"]["title"], "Dr");
+    }
+}